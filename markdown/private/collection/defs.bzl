@@ -9,15 +9,30 @@ def _md_collection_src_impl(ctx):
     extra_args = []
     if ctx.attr.date:
         extra_args += ["--date", ctx.attr.date]
+    for author in ctx.attr.authors:
+        extra_args += ["--author", author]
+    if ctx.attr.contributors:
+        extra_args.append("--contributors")
+    if ctx.attr.dedication:
+        extra_args += ["--dedication", ctx.attr.dedication]
+    if ctx.attr.acknowledgements:
+        extra_args.append("--acknowledgements")
+    if ctx.attr.contents:
+        extra_args.append("--contents")
+    inputs = [ctx.attr.deps[MdGroupInfo].metadata]
+    if ctx.file.manifest:
+        extra_args += ["--manifest", ctx.file.manifest.path, "--package", ctx.label.package]
+        inputs.append(ctx.file.manifest)
+    else:
+        extra_args += ["--order", ctx.attr.order]
     for dep in ctx.attr.deps[MdGroupInfo].deps:
         extra_args += ["--dep", dep.label.package + ":" + dep.label.name]
     ctx.actions.run(
         outputs = [output],
-        inputs = [ctx.attr.deps[MdGroupInfo].metadata],
+        inputs = inputs,
         executable = ctx.executable._gen_collection_src,
         arguments = extra_args + [
             ctx.attr.title,
-            ctx.attr.author,
             ctx.attr.deps[MdGroupInfo].metadata.path,
             output.path,
         ],
@@ -35,8 +50,33 @@ md_collection_src = rule(
         "title": attr.string(
             mandatory = True,
         ),
-        "author": attr.string(
+        "authors": attr.string_list(
             mandatory = True,
+            doc = "Authors or editors of the collection.",
+        ),
+        "contributors": attr.bool(
+            default = False,
+            doc = "Whether to list everyone who contributed to the deps at the end.",
+        ),
+        "dedication": attr.string(
+            doc = "Dedication to put at the start of the collection.",
+        ),
+        "acknowledgements": attr.bool(
+            default = False,
+            doc = "Whether to say where each dep was first published.",
+        ),
+        "contents": attr.bool(
+            default = False,
+            doc = "Whether to generate a table of contents with authors and wordcounts.",
+        ),
+        "order": attr.string(
+            default = "deps",
+            values = ["deps", "date", "title", "wordcount", "published"],
+            doc = "How to order the deps, if there's no manifest.",
+        ),
+        "manifest": attr.label(
+            allow_single_file = [".json"],
+            doc = "JSON file listing the deps in order, grouped into sections.",
         ),
         "date": attr.string(),
         "deps": attr.label(
@@ -1,9 +1,27 @@
-use clap::Parser;
+use chrono::NaiveDate;
+use clap::{Parser, ValueEnum};
 use markdown::args;
+use markdown::bazel::Label;
 use markdown::json::from_json;
-use markdown::metadata::{InputMetadataBuilder, MetadataMap};
+use markdown::metadata::{InputMetadataBuilder, MetadataMap, OutputMetadata};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fs::{read_to_string, write};
+use validator::Validate;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Order {
+    /// The order the deps are given in
+    Deps,
+    /// Earliest parsed date first
+    Date,
+    Title,
+    /// Shortest first
+    Wordcount,
+    /// Earliest first publication first
+    Published,
+}
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -11,8 +29,10 @@ struct Cli {
     #[arg(value_parser = args::non_empty())]
     title: String,
 
+    /// Author or editor of the collection; may be given more than once
+    #[arg(long = "author", required = true)]
     #[arg(value_parser = args::non_empty())]
-    author: String,
+    authors: Vec<String>,
 
     #[arg(long)]
     #[arg(value_parser = args::non_empty())]
@@ -27,6 +47,243 @@ struct Cli {
     #[arg(long = "dep")]
     #[arg(value_parser = args::non_empty())]
     deps: Vec<String>,
+
+    /// Add a list of everyone who contributed to the deps at the end
+    #[arg(long)]
+    contributors: bool,
+
+    /// Dedication to put at the start of the collection
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    dedication: Option<String>,
+
+    /// Add a page saying where each dep was first published
+    #[arg(long)]
+    acknowledgements: bool,
+
+    /// Add a table of contents listing each dep's authors and wordcount
+    #[arg(long)]
+    contents: bool,
+
+    #[arg(long, value_enum, default_value_t = Order::Deps, conflicts_with = "manifest")]
+    order: Order,
+
+    /// JSON file listing the deps in order, grouped into sections
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    manifest: Option<String>,
+
+    /// Package of the collection, which relative labels in the manifest are
+    /// resolved against
+    #[arg(long, default_value = "")]
+    package: String,
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+struct ManifestSection {
+    /// Sections without a heading just group deps
+    #[serde(default)]
+    #[validate(length(min = 1))]
+    heading: Option<String>,
+    #[validate(length(min = 1))]
+    deps: Vec<String>,
+}
+
+/// The deps of a collection in order, grouped into sections.
+#[derive(Clone, Debug, PartialEq, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    #[validate(nested)]
+    sections: Vec<ManifestSection>,
+}
+
+struct Section<'a> {
+    heading: Option<String>,
+    deps: Vec<(&'a str, &'a OutputMetadata)>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Role {
+    Author,
+    Translator,
+    Illustrator,
+}
+
+impl Role {
+    fn name(&self) -> &'static str {
+        match self {
+            Role::Author => "author",
+            Role::Translator => "translator",
+            Role::Illustrator => "illustrator",
+        }
+    }
+}
+
+/// Joins names as "A", "A and B", or "A, B and C".
+fn names(names: &[String]) -> String {
+    match names {
+        [] => String::new(),
+        [name] => name.clone(),
+        [init @ .., last] => format!("{} and {last}", init.join(", ")),
+    }
+}
+
+fn byline(m: &OutputMetadata, collection_authors: &[String]) -> String {
+    let mut parts = Vec::new();
+    // Pieces by the collection's own authors don't need to repeat them
+    let same_authors = m.authors().iter().collect::<BTreeSet<_>>()
+        == collection_authors.iter().collect::<BTreeSet<_>>();
+    if !m.authors().is_empty() && !same_authors {
+        parts.push(names(m.authors()));
+    }
+    if !m.translators().is_empty() {
+        parts.push(format!("translated by {}", names(m.translators())));
+    }
+    if !m.illustrators().is_empty() {
+        parts.push(format!("illustrated by {}", names(m.illustrators())));
+    }
+    if let Some(date) = m.date() {
+        parts.push(date.clone());
+    }
+    parts.join(", ")
+}
+
+fn contributors<'a, I>(metadata: I) -> BTreeMap<String, BTreeSet<Role>>
+where
+    I: IntoIterator<Item = &'a OutputMetadata>,
+{
+    let mut contributors = BTreeMap::<String, BTreeSet<Role>>::new();
+    for m in metadata {
+        for (role, people) in [
+            (Role::Author, m.authors()),
+            (Role::Translator, m.translators()),
+            (Role::Illustrator, m.illustrators()),
+        ] {
+            for person in people {
+                contributors.entry(person.clone()).or_default().insert(role);
+            }
+        }
+    }
+    contributors
+}
+
+fn dedication(text: &str) -> String {
+    format!("# Dedication {{.unnumbered .unlisted}}\n\n{text}\n")
+}
+
+/// When and where a piece first came out, if it has been published.
+fn first_publication(m: &OutputMetadata) -> Option<(&NaiveDate, &str)> {
+    m.publications()
+        .publications()
+        .iter()
+        .filter_map(|p| {
+            [p.published(), p.self_published()]
+                .into_iter()
+                .flatten()
+                .min()
+                .map(|date| (date, p.venue()))
+        })
+        .min()
+}
+
+fn first_published(m: &OutputMetadata) -> Option<String> {
+    first_publication(m)
+        .map(|(date, venue)| format!("First published in {venue}, {}.", date.format("%Y")))
+}
+
+fn acknowledgements(deps: &[&OutputMetadata]) -> Option<String> {
+    let entries: Vec<_> = deps
+        .iter()
+        .filter_map(|m| {
+            first_published(m)
+                .map(|p| format!("**{}**: {p}", m.title().cloned().unwrap_or_default()))
+        })
+        .collect();
+    if entries.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "::: nospellcheck\n\n# Acknowledgements {{.unnumbered .unlisted}}\n\n{}\n\n:::\n",
+        entries.join("\n\n")
+    ))
+}
+
+fn contents(sections: &[Section]) -> String {
+    let mut entries = Vec::new();
+    for section in sections {
+        let indent = match &section.heading {
+            Some(heading) => {
+                entries.push(format!("- {heading}"));
+                "    "
+            }
+            None => "",
+        };
+        for (_, m) in &section.deps {
+            let mut entry = format!("{indent}- **{}**", m.title().cloned().unwrap_or_default());
+            if !m.authors().is_empty() {
+                entry += &format!(", by {}", names(m.authors()));
+            }
+            entries.push(entry + &format!(" ({} words)", m.wordcount()));
+        }
+    }
+    format!(
+        "::: nospellcheck\n\n# Contents {{.unnumbered .unlisted}}\n\n{}\n\n:::\n",
+        entries.join("\n")
+    )
+}
+
+// Deps with nothing to sort by go last.
+fn last_if_none<T: Ord>(key: Option<T>) -> (bool, Option<T>) {
+    (key.is_none(), key)
+}
+
+fn sort(deps: &mut [(&str, &OutputMetadata)], order: Order) {
+    match order {
+        Order::Deps => {}
+        Order::Date => deps.sort_by_key(|(_, m)| last_if_none(m.parsed_dates().dates().first())),
+        Order::Title => {
+            deps.sort_by_key(|(_, m)| last_if_none(m.title().map(|t| t.to_lowercase())))
+        }
+        Order::Wordcount => deps.sort_by_key(|(_, m)| m.wordcount()),
+        Order::Published => {
+            deps.sort_by_key(|(_, m)| last_if_none(first_publication(m).map(|(date, _)| *date)))
+        }
+    }
+}
+
+fn sections<'a>(
+    manifest: &Manifest,
+    package: &str,
+    deps: &BTreeMap<&'a str, &'a OutputMetadata>,
+) -> Result<Vec<Section<'a>>, String> {
+    let mut seen = BTreeSet::new();
+    let mut sections = Vec::new();
+    for s in &manifest.sections {
+        let mut section = Section {
+            heading: s.heading.clone(),
+            deps: Vec::new(),
+        };
+        for entry in &s.deps {
+            let label = Label::canonicalise(entry, package)
+                .map_err(|e| format!("manifest entry '{entry}': {e}"))?;
+            let target = format!("{}:{}", label.package(), label.target());
+            let Some((&target, &m)) = deps.get_key_value(target.as_str()) else {
+                return Err(format!(
+                    "manifest entry '{entry}' is not a dep of the collection"
+                ));
+            };
+            if !seen.insert(target) {
+                return Err(format!("manifest entry '{entry}' is listed more than once"));
+            }
+            section.deps.push((target, m));
+        }
+        sections.push(section);
+    }
+    if let Some(target) = deps.keys().find(|t| !seen.contains(*t)) {
+        return Err(format!("dep '{target}' is not in the manifest"));
+    }
+    Ok(sections)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -34,7 +291,7 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let mut builder = InputMetadataBuilder::default();
     builder.title(args.title);
-    builder.authors([args.author.clone()]);
+    builder.authors(args.authors.clone());
 
     if let Some(date) = args.date {
         builder.date(date);
@@ -54,45 +311,404 @@ fn main() -> Result<(), Box<dyn Error>> {
 
     let metadata: MetadataMap = from_json(&read_to_string(args.metadata_file)?)?;
 
-    for target in args.deps {
-        let Some(m) = metadata.data().get(&target) else {
+    let mut deps = Vec::new();
+    for target in &args.deps {
+        let Some(m) = metadata.data().get(target) else {
             return Err(format!("target '{}' not found", target).into());
         };
-        output.push(format!(
-            "::: nospellcheck
+        deps.push((target.as_str(), m));
+    }
 
-# {}",
-            m.title().cloned().unwrap_or_default()
-        ));
-        let mut tagline = Vec::new();
-        if let Some(author) = m.authors().first() {
-            if *author != args.author {
-                tagline.push(author.clone());
-            }
+    let sections = match &args.manifest {
+        Some(path) => {
+            let manifest = from_json::<Manifest>(&read_to_string(path)?)?;
+            sections(&manifest, &args.package, &BTreeMap::from_iter(deps))?
         }
-        if let Some(date) = m.date() {
-            tagline.push(date.clone());
+        None => {
+            sort(&mut deps, args.order);
+            vec![Section {
+                heading: None,
+                deps,
+            }]
         }
-        if !tagline.is_empty() {
+    };
+    let deps_metadata: Vec<_> = sections
+        .iter()
+        .flat_map(|s| s.deps.iter().map(|(_, m)| *m))
+        .collect();
+
+    if let Some(text) = &args.dedication {
+        output.push(dedication(text));
+    }
+    if args.acknowledgements {
+        output.extend(acknowledgements(&deps_metadata));
+    }
+    if args.contents {
+        output.push(contents(&sections));
+    }
+
+    for section in &sections {
+        // Pieces go under their section's heading, if it has one, and are top
+        // level otherwise, so a manifest can mix parts with standalone pieces
+        let (piece_heading, include_options) = match &section.heading {
+            Some(heading) => {
+                output.push(format!("# {heading}\n"));
+                ("##", " shift=1")
+            }
+            None => ("#", ""),
+        };
+        for (target, m) in &section.deps {
             output.push(format!(
-                "
-**{}**
+                "::: nospellcheck
+
+{piece_heading} {}",
+                m.title().cloned().unwrap_or_default()
+            ));
+            let byline = byline(m, &args.authors);
+            if !byline.is_empty() {
+                output.push(format!(
+                    "
+**{byline}**
 
 ::: collectionseparator
 &nbsp;
-:::",
-                tagline.join(", ")
-            ));
-        }
-        output.push(format!(
-            "
+:::"
+                ));
+            }
+            output.push(format!(
+                "
 :::
 
-!include //{target}
+!include //{target}{include_options}
 "
+            ));
+        }
+    }
+
+    if args.contributors {
+        output.push(String::from(
+            "::: nospellcheck\n\n# Contributors {.unnumbered .unlisted}\n",
         ));
+        for (person, roles) in contributors(deps_metadata) {
+            let roles: Vec<_> = roles.iter().map(Role::name).collect();
+            output.push(format!("- {person} ({})", roles.join(", ")));
+        }
+        output.push(String::from("\n:::\n"));
     }
 
     write(args.out_file, output.join("\n"))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod gen_collection_src_test {
+    use super::{
+        Manifest, Order, OutputMetadata, Role, Section, acknowledgements, byline, contents,
+        contributors, first_published, names, sections, sort,
+    };
+    use markdown::json::from_json;
+    use markdown_testing::{output_metadata, strings};
+    use std::collections::{BTreeMap, BTreeSet};
+
+    fn metadata(authors: &[&str], translators: &[&str], illustrators: &[&str]) -> OutputMetadata {
+        output_metadata()
+            .authors(strings(authors))
+            .translators(strings(translators))
+            .illustrators(strings(illustrators))
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_names() {
+        assert_eq!(names(&[]), "");
+        assert_eq!(names(&strings(&["A"])), "A");
+        assert_eq!(names(&strings(&["A", "B"])), "A and B");
+        assert_eq!(names(&strings(&["A", "B", "C"])), "A, B and C");
+    }
+
+    #[test]
+    fn test_byline() {
+        let editors = strings(&["A", "B"]);
+        assert_eq!(byline(&metadata(&["B", "A"], &[], &[]), &editors), "");
+        assert_eq!(byline(&metadata(&["A"], &[], &[]), &editors), "A");
+        assert_eq!(
+            byline(&metadata(&["A", "B"], &["C"], &["D", "E"]), &editors),
+            "translated by C, illustrated by D and E"
+        );
+        assert_eq!(
+            byline(&metadata(&["C", "D", "E"], &["F"], &[]), &editors),
+            "C, D and E, translated by F"
+        );
+    }
+
+    #[test]
+    fn test_contributors() {
+        let deps = [
+            metadata(&["B", "A"], &[], &[]),
+            metadata(&["C"], &["A"], &["B"]),
+            metadata(&[], &[], &["A"]),
+        ];
+        let c = contributors(&deps);
+        assert_eq!(
+            c.into_iter().collect::<Vec<_>>(),
+            [
+                (
+                    String::from("A"),
+                    BTreeSet::from([Role::Author, Role::Translator, Role::Illustrator])
+                ),
+                (
+                    String::from("B"),
+                    BTreeSet::from([Role::Author, Role::Illustrator])
+                ),
+                (String::from("C"), BTreeSet::from([Role::Author])),
+            ]
+        );
+    }
+
+    fn published(title: &str, publications: &str) -> OutputMetadata {
+        from_json(&format!(
+            r#"{{
+  "title": "{title}",
+  "author": ["A"],
+  "publications": {publications},
+  "wordcount": 1000,
+  "poetry-lines": 0,
+  "lang": "en",
+  "version": "1",
+  "repo": "foo",
+  "source-hash": "abc",
+  "parsed-dates": []
+}}"#
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_first_published() {
+        assert_eq!(first_published(&published("Foo", "[]")), None);
+        assert_eq!(
+            first_published(&published(
+                "Foo",
+                r#"[{"venue": "Bar", "submitted": "2020-01-01", "rejected": "2020-02-01"}]"#
+            )),
+            None
+        );
+        assert_eq!(
+            first_published(&published(
+                "Foo",
+                r#"[
+  {"venue": "Later", "self-published": "2022-05-01"},
+  {
+    "venue": "Earlier",
+    "submitted": "2020-05-01",
+    "accepted": "2020-06-01",
+    "published": "2021-03-01"
+  }
+]"#
+            )),
+            Some(String::from("First published in Earlier, 2021."))
+        );
+    }
+
+    #[test]
+    fn test_acknowledgements() {
+        let unpublished = published("Foo", "[]");
+        assert_eq!(acknowledgements(&[&unpublished]), None);
+
+        let bar = published(
+            "Bar",
+            r#"[{
+  "venue": "Baz",
+  "submitted": "2020-05-01",
+  "accepted": "2020-06-01",
+  "published": "2021-03-01"
+}]"#,
+        );
+        assert_eq!(
+            acknowledgements(&[&unpublished, &bar]).unwrap(),
+            "::: nospellcheck
+
+# Acknowledgements {.unnumbered .unlisted}
+
+**Bar**: First published in Baz, 2021.
+
+:::
+"
+        );
+    }
+
+    #[test]
+    fn test_contents() {
+        let foo = published("Foo", "[]");
+        let untitled = metadata(&["B", "C"], &[], &[]);
+        assert_eq!(
+            contents(&[Section {
+                heading: None,
+                deps: vec![("a:foo", &foo), ("a:untitled", &untitled)],
+            }]),
+            "::: nospellcheck
+
+# Contents {.unnumbered .unlisted}
+
+- **Foo**, by A (1000 words)
+- ****, by B and C (10 words)
+
+:::
+"
+        );
+        assert_eq!(
+            contents(&[
+                Section {
+                    heading: Some(String::from("Part One")),
+                    deps: vec![("a:foo", &foo)],
+                },
+                Section {
+                    heading: Some(String::from("Part Two")),
+                    deps: vec![("a:untitled", &untitled)],
+                },
+            ]),
+            "::: nospellcheck
+
+# Contents {.unnumbered .unlisted}
+
+- Part One
+    - **Foo**, by A (1000 words)
+- Part Two
+    - ****, by B and C (10 words)
+
+:::
+"
+        );
+    }
+
+    fn piece(title: &str, date: &str, wordcount: u32, published: &str) -> OutputMetadata {
+        let mut fields = vec![format!(r#""wordcount": {wordcount}"#)];
+        if !title.is_empty() {
+            fields.push(format!(r#""title": "{title}""#));
+        }
+        if !date.is_empty() {
+            fields.push(format!(r#""parsed-dates": ["{date}"]"#));
+        } else {
+            fields.push(String::from(r#""parsed-dates": []"#));
+        }
+        if !published.is_empty() {
+            fields.push(format!(
+                r#""publications": [{{
+  "venue": "Foo",
+  "submitted": "2000-01-01",
+  "accepted": "2000-01-02",
+  "published": "{published}"
+}}]"#
+            ));
+        }
+        from_json(&format!(
+            r#"{{
+  {},
+  "poetry-lines": 0,
+  "lang": "en",
+  "version": "1",
+  "repo": "foo",
+  "source-hash": "abc"
+}}"#,
+            fields.join(",\n  ")
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_sort() {
+        let a = piece("beta", "2021", 30, "");
+        let b = piece("Alpha", "", 10, "2022-01-01");
+        let c = piece("", "2020/06", 20, "2021-01-01");
+        let sorted = |order| {
+            let mut deps = vec![("a:a", &a), ("a:b", &b), ("a:c", &c)];
+            sort(&mut deps, order);
+            deps.into_iter().map(|(t, _)| t).collect::<Vec<_>>()
+        };
+        assert_eq!(sorted(Order::Deps), ["a:a", "a:b", "a:c"]);
+        assert_eq!(sorted(Order::Date), ["a:c", "a:a", "a:b"]);
+        assert_eq!(sorted(Order::Title), ["a:b", "a:a", "a:c"]);
+        assert_eq!(sorted(Order::Wordcount), ["a:b", "a:c", "a:a"]);
+        assert_eq!(sorted(Order::Published), ["a:c", "a:b", "a:a"]);
+    }
+
+    #[test]
+    fn test_sections() {
+        let a = piece("A", "", 10, "");
+        let b = piece("B", "", 10, "");
+        let deps = BTreeMap::from([("a:a", &a), ("b:b", &b)]);
+        let manifest = |json: &str| from_json::<Manifest>(json).unwrap();
+        let targets = |s: &[Section]| {
+            s.iter()
+                .map(|s| {
+                    (
+                        s.heading.clone(),
+                        s.deps.iter().map(|(t, _)| String::from(*t)).collect(),
+                    )
+                })
+                .collect::<Vec<(Option<String>, Vec<String>)>>()
+        };
+
+        let s = sections(
+            &manifest(
+                r#"{"sections": [
+  {"heading": "One", "deps": ["//b"]},
+  {"deps": [":a"]}
+]}"#,
+            ),
+            "a",
+            &deps,
+        )
+        .unwrap();
+        assert_eq!(
+            targets(&s),
+            [
+                (Some(String::from("One")), vec![String::from("b:b")]),
+                (None, vec![String::from("a:a")])
+            ]
+        );
+
+        assert_eq!(
+            sections(
+                &manifest(r#"{"sections": [{"deps": [":a", "//b:b", "//c"]}]}"#),
+                "a",
+                &deps
+            )
+            .err(),
+            Some(String::from(
+                "manifest entry '//c' is not a dep of the collection"
+            ))
+        );
+        assert_eq!(
+            sections(
+                &manifest(r#"{"sections": [{"deps": [":a", "//b", "//a"]}]}"#),
+                "a",
+                &deps
+            )
+            .err(),
+            Some(String::from(
+                "manifest entry '//a' is listed more than once"
+            ))
+        );
+        assert_eq!(
+            sections(&manifest(r#"{"sections": [{"deps": [":a"]}]}"#), "a", &deps).err(),
+            Some(String::from("dep 'b:b' is not in the manifest"))
+        );
+
+        assert_eq!(
+            sections(
+                &manifest(r#"{"sections": [{"deps": ["b:b"]}]}"#),
+                "a",
+                &deps
+            )
+            .err(),
+            Some(String::from("manifest entry 'b:b': invalid label 'b:b'"))
+        );
+
+        assert!(from_json::<Manifest>(r#"{"sections": [{"deps": []}]}"#).is_err());
+        assert!(
+            from_json::<Manifest>(r#"{"sections": [{"heading": "", "deps": [":a"]}]}"#).is_err()
+        );
+    }
+}
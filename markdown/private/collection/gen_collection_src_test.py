@@ -1,5 +1,6 @@
 import os
 import os.path
+import subprocess
 from collections.abc import Mapping, Sequence
 from typing import Any
 
@@ -10,16 +11,24 @@ class TestGenCollectionSrc(test_utils.ScriptTestCase):
     def run_script(  # type: ignore[override]
         self,
         title: str,
-        author: str,
+        authors: Sequence[str],
         date: str | None,
         metadata: Sequence[tuple[str, Mapping[str, Any]]],
+        contributors: bool = False,
+        extra_args: Sequence[str] = (),
     ) -> str:
         metadata_out = {}
-        args = []
+        args = list(extra_args)
+
+        for author in authors:
+            args += ["--author", author]
 
         if date:
             args += ["--date", date]
 
+        if contributors:
+            args.append("--contributors")
+
         for target, data in metadata:
             metadata_out[target] = data
             args += ["--dep", target]
@@ -33,7 +42,6 @@ class TestGenCollectionSrc(test_utils.ScriptTestCase):
             args=[
                 *args,
                 title,
-                author,
                 metadata_file,
                 out_file,
             ],
@@ -44,7 +52,7 @@ class TestGenCollectionSrc(test_utils.ScriptTestCase):
     def test_gen_collection_src_simple(self) -> None:
         out = self.run_script(
             "The Title",
-            "The Author",
+            ["The Author"],
             None,
             [
                 (
@@ -91,7 +99,7 @@ author:
     def test_gen_collection_src_complex(self) -> None:
         out = self.run_script(
             "The Title",
-            "The Author",
+            ["The Author"],
             "1 January",
             [
                 (
@@ -189,6 +197,375 @@ date: 1 January
 """,
         )
 
+    def test_gen_collection_src_multiple_authors(self) -> None:
+        out = self.run_script(
+            "The Title",
+            ["A", "B"],
+            None,
+            [
+                (
+                    "foo",
+                    {
+                        "title": "Foo",
+                        "author": ["B", "A"],
+                        "translator": ["C"],
+                        "wordcount": "10",
+                        "poetry-lines": "0",
+                        "lang": "en-GB",
+                        "version": "foo",
+                        "repo": "bar",
+                        "source-hash": "1",
+                        "parsed-dates": [],
+                    },
+                ),
+                (
+                    "bar",
+                    {
+                        "title": "Bar",
+                        "author": ["A", "C", "D"],
+                        "illustrator": ["B", "E"],
+                        "wordcount": "10",
+                        "poetry-lines": "0",
+                        "lang": "en-GB",
+                        "version": "foo",
+                        "repo": "bar",
+                        "source-hash": "1",
+                        "parsed-dates": [],
+                    },
+                ),
+            ],
+            contributors=True,
+        )
+
+        self.assertEqual(
+            out,
+            """---
+title: The Title
+author:
+- A
+- B
+---
+
+::: nospellcheck
+
+# Foo
+
+**translated by C**
+
+::: collectionseparator
+&nbsp;
+:::
+
+:::
+
+!include //foo
+
+::: nospellcheck
+
+# Bar
+
+**A, C and D, illustrated by B and E**
+
+::: collectionseparator
+&nbsp;
+:::
+
+:::
+
+!include //bar
+
+::: nospellcheck
+
+# Contributors {.unnumbered .unlisted}
+
+- A (author)
+- B (author, illustrator)
+- C (author, translator)
+- D (author)
+- E (illustrator)
+
+:::
+""",
+        )
+
+    def test_gen_collection_src_front_matter(self) -> None:
+        out = self.run_script(
+            "The Title",
+            ["The Author"],
+            None,
+            [
+                (
+                    "foo",
+                    {
+                        "title": "Foo",
+                        "author": ["The Author"],
+                        "publications": [
+                            {
+                                "venue": "Bar",
+                                "submitted": "2021-01-01",
+                                "accepted": "2021-02-01",
+                                "published": "2021-03-01",
+                            },
+                        ],
+                        "wordcount": "1000",
+                        "poetry-lines": "0",
+                        "lang": "en-GB",
+                        "version": "foo",
+                        "repo": "bar",
+                        "source-hash": "1",
+                        "parsed-dates": [],
+                    },
+                ),
+                (
+                    "bar",
+                    {
+                        "title": "Bar",
+                        "author": ["Baz", "Quux"],
+                        "wordcount": "10",
+                        "poetry-lines": "0",
+                        "lang": "en-GB",
+                        "version": "foo",
+                        "repo": "bar",
+                        "source-hash": "1",
+                        "parsed-dates": [],
+                    },
+                ),
+            ],
+            extra_args=["--dedication", "For Foo", "--acknowledgements", "--contents"],
+        )
+
+        self.assertEqual(
+            out,
+            """---
+title: The Title
+author:
+- The Author
+---
+
+# Dedication {.unnumbered .unlisted}
+
+For Foo
+
+::: nospellcheck
+
+# Acknowledgements {.unnumbered .unlisted}
+
+**Foo**: First published in Bar, 2021.
+
+:::
+
+::: nospellcheck
+
+# Contents {.unnumbered .unlisted}
+
+- **Foo**, by The Author (1000 words)
+- **Bar**, by Baz and Quux (10 words)
+
+:::
+
+::: nospellcheck
+
+# Foo
+
+:::
+
+!include //foo
+
+::: nospellcheck
+
+# Bar
+
+**Baz and Quux**
+
+::: collectionseparator
+&nbsp;
+:::
+
+:::
+
+!include //bar
+""",
+        )
+
+    def metadata(self, title: str, date: str, wordcount: int) -> dict[str, Any]:
+        return {
+            "title": title,
+            "author": ["The Author"],
+            "wordcount": str(wordcount),
+            "poetry-lines": "0",
+            "lang": "en-GB",
+            "version": "foo",
+            "repo": "bar",
+            "source-hash": "1",
+            "parsed-dates": [date],
+        }
+
+    def test_gen_collection_src_order(self) -> None:
+        deps = [
+            ("a:foo", self.metadata("Foo", "2021", 30)),
+            ("a:bar", self.metadata("Bar", "2022", 10)),
+            ("a:baz", self.metadata("Baz", "2020", 20)),
+        ]
+        for order, expected in [
+            ("deps", ["foo", "bar", "baz"]),
+            ("date", ["baz", "foo", "bar"]),
+            ("title", ["bar", "baz", "foo"]),
+            ("wordcount", ["bar", "baz", "foo"]),
+        ]:
+            with self.subTest(order=order):
+                out = self.run_script(
+                    "The Title", ["The Author"], None, deps, extra_args=["--order", order]
+                )
+                self.assertEqual(
+                    [line for line in out.split("\n") if line.startswith("!include")],
+                    [f"!include //a:{target}" for target in expected],
+                )
+
+    def test_gen_collection_src_manifest(self) -> None:
+        manifest = os.path.join(self.tmpdir(), "manifest.json")
+        self.dump_json(
+            manifest,
+            {
+                "sections": [
+                    {"heading": "Part One", "deps": [":bar", "//a:foo"]},
+                    {"heading": "Part Two", "deps": ["//b"]},
+                ],
+            },
+        )
+        out = self.run_script(
+            "The Title",
+            ["The Author"],
+            None,
+            [
+                ("a:foo", self.metadata("Foo", "2021", 30)),
+                ("a:bar", self.metadata("Bar", "2022", 10)),
+                ("b:b", self.metadata("Baz", "2020", 20)),
+            ],
+            extra_args=["--manifest", manifest, "--package", "a"],
+        )
+
+        self.assertEqual(
+            out,
+            """---
+title: The Title
+author:
+- The Author
+---
+
+# Part One
+
+::: nospellcheck
+
+## Bar
+
+:::
+
+!include //a:bar shift=1
+
+::: nospellcheck
+
+## Foo
+
+:::
+
+!include //a:foo shift=1
+
+# Part Two
+
+::: nospellcheck
+
+## Baz
+
+:::
+
+!include //b:b shift=1
+""",
+        )
+
+    def test_gen_collection_src_manifest_mixed(self) -> None:
+        manifest = os.path.join(self.tmpdir(), "manifest.json")
+        self.dump_json(
+            manifest,
+            {
+                "sections": [
+                    {"deps": ["//b"]},
+                    {"heading": "Part One", "deps": [":bar"]},
+                    {"deps": ["//a:foo"]},
+                ],
+            },
+        )
+        out = self.run_script(
+            "The Title",
+            ["The Author"],
+            None,
+            [
+                ("a:foo", self.metadata("Foo", "2021", 30)),
+                ("a:bar", self.metadata("Bar", "2022", 10)),
+                ("b:b", self.metadata("Baz", "2020", 20)),
+            ],
+            extra_args=["--manifest", manifest, "--package", "a", "--contents"],
+        )
+
+        # Pieces without a heading are at the top level, alongside the parts
+        self.assertEqual(
+            out,
+            """---
+title: The Title
+author:
+- The Author
+---
+
+::: nospellcheck
+
+# Contents {.unnumbered .unlisted}
+
+- **Baz**, by The Author (20 words)
+- Part One
+    - **Bar**, by The Author (10 words)
+- **Foo**, by The Author (30 words)
+
+:::
+
+::: nospellcheck
+
+# Baz
+
+:::
+
+!include //b:b
+
+# Part One
+
+::: nospellcheck
+
+## Bar
+
+:::
+
+!include //a:bar shift=1
+
+::: nospellcheck
+
+# Foo
+
+:::
+
+!include //a:foo
+""",
+        )
+
+    def test_gen_collection_src_manifest_unknown_dep(self) -> None:
+        manifest = os.path.join(self.tmpdir(), "manifest.json")
+        self.dump_json(manifest, {"sections": [{"deps": [":foo", ":bar"]}]})
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(
+                "The Title",
+                ["The Author"],
+                None,
+                [("a:foo", self.metadata("Foo", "2021", 30))],
+                extra_args=["--manifest", manifest, "--package", "a"],
+            )
+
 
 if __name__ == "__main__":
     test_utils.ScriptTestCase.main()
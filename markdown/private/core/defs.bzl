@@ -11,6 +11,7 @@ MdGroupInfo = provider(
     fields = {
         "deps": "The files in the group",
         "metadata": "Metadata of all files in the group, as json",
+        "transitive_metadata": "Depset of the metadata of the group and of every group its files depend on",
     },
 )
 
@@ -19,8 +20,11 @@ MdFileInfo = provider(
     fields = {
         "name": "The name of the document",
         "output": "Compiled document, as json",
+        "preprocessed": "Preprocessed source of the document, which includes select lines from",
         "metadata": "Document metadata, as json",
         "data": "Data deps of the document",
+        "include_graph": "Transitive include graph of the document, as json",
+        "deps_metadata": "Depset of the metadata of the groups the document transitively depends on",
     },
 )
 
@@ -39,17 +43,33 @@ def _md_group_impl(ctx):
                 dep[MdFileInfo].metadata.path,
             ),
         ]
+    cache_args = []
+    if ctx.attr.cache_dir:
+        # Unchanged deps are copied from the previous output in a persistent
+        # writable cache dir, rather than re-parsed
+        cache_args = [
+            "--cache-dir",
+            "/".join([ctx.attr.cache_dir, "combine_deps_metadata", ctx.label.package, ctx.label.name]),
+        ]
     ctx.actions.run(
         outputs = [metadata],
         inputs = [dep[MdFileInfo].metadata for dep in ctx.attr.deps],
         executable = ctx.executable._combine_deps_metadata,
-        arguments = metadata_args + [metadata.path],
+        arguments = metadata_args + cache_args + [metadata.path],
+        execution_requirements = {"local": "1"} if ctx.attr.cache_dir else {},
         progress_message = "%{label}: combining deps metadata",
     )
 
     return [
         DefaultInfo(files = depset([metadata] + output)),
-        MdGroupInfo(deps = ctx.attr.deps, metadata = metadata),
+        MdGroupInfo(
+            deps = ctx.attr.deps,
+            metadata = metadata,
+            transitive_metadata = depset(
+                [metadata],
+                transitive = [dep[MdFileInfo].deps_metadata for dep in ctx.attr.deps],
+            ),
+        ),
     ]
 
 md_group = rule(
@@ -61,6 +81,9 @@ md_group = rule(
             providers = [DefaultInfo, MdFileInfo],
             doc = "md_file targets to include in the group.",
         ),
+        "cache_dir": attr.string(
+            doc = "Dir for the deps metadata cache.",
+        ),
         "_combine_deps_metadata": attr.label(
             default = "//markdown/private/core/group:combine_deps_metadata",
             executable = True,
@@ -90,23 +113,55 @@ def _standard_lint(ctx):
 
     return standard_lint_ok
 
+def _warnings_args(ctx):
+    if ctx.attr.warnings_as_errors:
+        return ["--warnings-as-errors"]
+    return []
+
 def _custom_lint(ctx):
     custom_lint_ok = ctx.actions.declare_file(ctx.label.name + "_custom_lint_ok.txt")
+    config_inputs = []
+    config_args = _warnings_args(ctx)
+    if ctx.attr.lint_config:
+        config_inputs.append(ctx.file.lint_config)
+        config_args += ["--config", ctx.file.lint_config.path]
     ctx.actions.run(
         outputs = [custom_lint_ok],
         inputs = [
             ctx.file.src,
-        ],
+        ] + config_inputs,
         executable = ctx.executable._custom_lint,
         arguments = [
             ctx.file.src.path,
             custom_lint_ok.path,
-        ],
+        ] + config_args,
         progress_message = "%{label}: linting markdown with custom linter",
     )
 
     return custom_lint_ok
 
+def _prose_lint(ctx):
+    prose_report = ctx.actions.declare_file(ctx.label.name + "_prose_report.txt")
+    config_inputs = []
+    config_args = _warnings_args(ctx)
+    if ctx.attr.prose_lint_config:
+        config_inputs.append(ctx.file.prose_lint_config)
+        config_args += ["--config", ctx.file.prose_lint_config.path]
+    ctx.actions.run(
+        outputs = [prose_report],
+        inputs = [
+            ctx.file.src,
+        ] + config_inputs,
+        executable = ctx.executable._prose_lint,
+        arguments = [
+            ctx.file.src.path,
+            prose_report.path,
+        ] + config_args,
+        progress_message = "%{label}: linting prose",
+    )
+
+    return prose_report
+
 def _spellcheck(ctx):
     custom_dictionary = ctx.actions.declare_file(ctx.label.name + "_dictionary.dic")
     if ctx.attr.dictionaries:
@@ -176,14 +231,19 @@ def _spellcheck(ctx):
     return spellcheck_ok
 
 def _md_file_impl(ctx):
+    prose_report = _prose_lint(ctx)
     lint_ok = [
         _standard_lint(ctx),
         _custom_lint(ctx),
         _spellcheck(ctx),
+        prose_report,
     ]
 
     preprocessed = ctx.actions.declare_file(ctx.label.name + "_stage1_preprocessed.md")
+    include_graph = ctx.actions.declare_file(ctx.label.name + "_include_graph.json")
+    preprocess_warnings = ctx.actions.declare_file(ctx.label.name + "_preprocess_warnings.txt")
     dep_args = []
+    dep_include_graphs = []
     for dep in ctx.attr.deps[MdGroupInfo].deps:
         dep_args += [
             "--dep",
@@ -191,7 +251,17 @@ def _md_file_impl(ctx):
                 dep.label.package + ":" + dep.label.name,
                 dep[MdFileInfo].output.path,
             ),
+            "--dep-src",
+            key_value_arg(
+                dep.label.package + ":" + dep.label.name,
+                dep[MdFileInfo].preprocessed.path,
+            ),
+            "--dep-include-graph",
+            dep[MdFileInfo].include_graph.path,
         ]
+        dep_include_graphs.append(dep[MdFileInfo].include_graph)
+    if ctx.attr.max_include_depth:
+        dep_args += ["--max-include-depth", str(ctx.attr.max_include_depth)]
     image_args = []
     for image in ctx.attr.images:
         image_args += [
@@ -202,10 +272,16 @@ def _md_file_impl(ctx):
             ),
         ]
     ctx.actions.run(
-        outputs = [preprocessed],
-        inputs = [ctx.file.src],
+        outputs = [preprocessed, include_graph, preprocess_warnings],
+        inputs = [ctx.file.src] + dep_include_graphs,
         executable = ctx.executable._preprocess,
-        arguments = dep_args + image_args + [
+        arguments = dep_args + image_args + _warnings_args(ctx) + [
+            "--name",
+            ctx.label.name,
+            "--include-graph-out-file",
+            include_graph.path,
+            "--report-file",
+            preprocess_warnings.path,
             ctx.file.src.path,
             preprocessed.path,
             ctx.label.package,
@@ -231,7 +307,8 @@ def _md_file_impl(ctx):
             ctx.file._header_auto_ids,
             ctx.file._wordcount,
             ctx.file._poetry_lines,
-        ] + [dep[MdFileInfo].output for dep in ctx.attr.deps[MdGroupInfo].deps],
+        ] + [dep[MdFileInfo].output for dep in ctx.attr.deps[MdGroupInfo].deps] +
+        [dep[MdFileInfo].preprocessed for dep in ctx.attr.deps[MdGroupInfo].deps],
         executable = ctx.executable._pandoc,
         arguments = [
             "--lua-filter=" + ctx.file._validate_ids.path,
@@ -256,13 +333,16 @@ def _md_file_impl(ctx):
     )
 
     input_metadata = ctx.actions.declare_file(ctx.label.name + "_input_metadata.json")
+    inputs = [input_metadata_raw, ctx.file.src]
+    args = ["--source-file", ctx.file.src.path]
+    if ctx.file.venues:
+        inputs.append(ctx.file.venues)
+        args += ["--venues-file", ctx.file.venues.path]
     ctx.actions.run(
         outputs = [input_metadata],
-        inputs = [
-            input_metadata_raw,
-        ],
+        inputs = inputs,
         executable = ctx.executable._validate_input_metadata,
-        arguments = [
+        arguments = args + [
             input_metadata_raw.path,
             input_metadata.path,
         ],
@@ -303,11 +383,28 @@ def _md_file_impl(ctx):
     )
 
     source_hash = ctx.actions.declare_file(ctx.label.name + "_source_hash.json")
+    hash_inputs = list(ctx.files.images)
+    if ctx.file.lint_config:
+        hash_inputs.append(ctx.file.lint_config)
+    if ctx.file.prose_lint_config:
+        hash_inputs.append(ctx.file.prose_lint_config)
+    hash_args = [
+        "--algorithm",
+        ctx.attr.hash_algorithm,
+        "--src-name",
+        ctx.file.src.short_path,
+    ]
+    for f in hash_inputs:
+        hash_args += ["--input", key_value_arg(f.short_path, f.path)]
+    for f in [ctx.file.src] + hash_inputs:
+        # Only source files in this workspace can be reread to check for drift
+        if not f.is_source or f.owner.workspace_name:
+            hash_args += ["--generated", f.short_path]
     ctx.actions.run(
         outputs = [source_hash],
-        inputs = [ctx.file.src, ctx.attr.deps[MdGroupInfo].metadata],
+        inputs = [ctx.file.src, ctx.attr.deps[MdGroupInfo].metadata] + hash_inputs,
         executable = ctx.executable._source_hash,
-        arguments = [
+        arguments = hash_args + [
             ctx.file.src.path,
             ctx.attr.deps[MdGroupInfo].metadata.path,
             source_hash.path,
@@ -397,8 +494,15 @@ def _md_file_impl(ctx):
         MdFileInfo(
             name = ctx.label.name,
             output = output,
+            preprocessed = preprocessed,
             metadata = output_metadata,
             data = data,
+            include_graph = include_graph,
+            deps_metadata = ctx.attr.deps[MdGroupInfo].transitive_metadata,
+        ),
+        OutputGroupInfo(
+            prose_report = depset([prose_report]),
+            warnings = depset([preprocess_warnings]),
         ),
     ]
 
@@ -429,6 +533,22 @@ md_file = rule(
             allow_files = True,
             doc = "Image dependencies.",
         ),
+        "lint_config": attr.label(
+            allow_single_file = [".json"],
+            doc = "Lint rule configuration for the custom linter.",
+        ),
+        "prose_lint_config": attr.label(
+            allow_single_file = [".json"],
+            doc = "Configuration for the prose linter.",
+        ),
+        "warnings_as_errors": attr.bool(
+            default = False,
+            doc = "If true, warnings from preprocessing and linting fail the build.",
+        ),
+        "max_include_depth": attr.int(
+            default = 0,
+            doc = "Maximum depth of nested includes, or 0 for no limit.",
+        ),
         "increment_included_headers": attr.bool(
             default = False,
             doc = "If true, header level in included files is incremented, e.g. level 1 headers become level 2 headers. If false, headers are unchanged.",
@@ -437,6 +557,15 @@ md_file = rule(
             allow_single_file = [".json"],
             doc = "File with version info.",
         ),
+        "venues": attr.label(
+            allow_single_file = [".json"],
+            doc = "Venue registry; if set, publication venues must be in the registry.",
+        ),
+        "hash_algorithm": attr.string(
+            default = "sha256",
+            values = ["sha256", "blake3"],
+            doc = "Algorithm for the content hash; the md5 source hash is always computed.",
+        ),
         "version_override": attr.string(
             default = "",
             doc = "Set the document version to this value, rather than the computed value. Should only be used for testing.",
@@ -469,6 +598,11 @@ md_file = rule(
             executable = True,
             cfg = "exec",
         ),
+        "_prose_lint": attr.label(
+            default = "//markdown/private/core/lint:prose_lint",
+            executable = True,
+            cfg = "exec",
+        ),
         "_gen_dictionary": attr.label(
             default = "//markdown/private/core/spelling:gen_dictionary",
             executable = True,
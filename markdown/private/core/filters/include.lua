@@ -1,27 +1,134 @@
 local increment_included_headers = false
 
+-- The format md_file compiles sources from; see _SRC_FORMAT in defs.bzl.
+local SRC_FORMAT = "markdown+smart-pandoc_title_block-auto_identifiers"
+local MAX_HEADER_LEVEL = 6
+
+function fail(msg)
+    io.stderr:write(msg .. "\n")
+    os.exit(1)
+end
+
 function increment(elem)
     elem.level = elem.level + 1
     return elem
 end
 
+-- Options are validated by the preprocessor, so they're only parsed here.
+function parse_options(content)
+    local options = {}
+    for i = 4, #content, 2 do
+        if content[i].tag ~= "Space" or content[i + 1] == nil or content[i + 1].tag ~= "Str" then
+            return nil
+        end
+        local key, value = content[i + 1].text:match("^(%a+)=(.+)$")
+        if key == nil then
+            return nil
+        end
+        options[key] = value
+    end
+    return options
+end
+
+-- The header with the given id, and everything up to the next header at the
+-- same level or higher.
+function select_section(blocks, id)
+    local out = {}
+    local level = nil
+    for _, block in ipairs(blocks) do
+        if level == nil then
+            if block.tag == "Header" and block.identifier == id then
+                level = block.level
+                table.insert(out, block)
+            end
+        elseif block.tag == "Header" and block.level <= level then
+            break
+        else
+            table.insert(out, block)
+        end
+    end
+    if level == nil then
+        fail("Section not found in included file: #" .. id)
+    end
+    return out
+end
+
+-- Lines are counted in the preprocessed source, which has the same lines as
+-- the original, and are sliced before parsing so that nothing is lost by
+-- re-serialising it.
+function select_lines(text, first, last)
+    if text:sub(-1) ~= "\n" then
+        text = text .. "\n"
+    end
+    local lines = {}
+    for line in text:gmatch("(.-)\n") do
+        table.insert(lines, line)
+    end
+    if last > #lines then
+        fail("Included file has " .. #lines .. " lines, fewer than " .. last)
+    end
+    local blocks = pandoc.read(table.concat(lines, "\n", first, last), SRC_FORMAT,
+                               {strip_comments = true}).blocks
+    -- Includes in the selected lines point at the included file's deps, which
+    -- aren't available here.
+    pandoc.walk_block(pandoc.Div(blocks), {
+        Para = function(elem)
+            if #elem.content >= 1 and elem.content[1].tag == "Str" and elem.content[1].text ==
+                "!include" then
+                fail("Selected lines " .. first .. "-" .. last .. " contain an include")
+            end
+        end,
+    })
+    return blocks
+end
+
+function shift_headers(blocks, shift)
+    pandoc.walk_block(pandoc.Div(blocks), {
+        Header = function(elem)
+            if elem.level + shift > MAX_HEADER_LEVEL then
+                fail("Shifting headers by " .. shift .. " takes a level " .. elem.level ..
+                         " header past level " .. MAX_HEADER_LEVEL .. ": " ..
+                         pandoc.utils.stringify(elem))
+            end
+        end,
+    })
+    for _ = 1, shift do
+        blocks = pandoc.walk_block(pandoc.Div(blocks), {Header = increment}).content
+    end
+    return blocks
+end
+
 function include(elem)
-    if #elem.content == 3 and elem.content[1].tag == "Str" and elem.content[1].text == "!include" and
+    if #elem.content >= 3 and elem.content[1].tag == "Str" and elem.content[1].text == "!include" and
         elem.content[2].tag == "Space" and elem.content[3].tag == "Str" then
+        local options = parse_options(elem.content)
+        if options == nil then
+            return nil
+        end
         local path = elem.content[3].text
         local includedFile = io.open(path, "r")
         if includedFile == nil then
-            io.stderr:write("Failed to open included file: " .. path .. "\n")
-            os.exit(1)
+            fail("Failed to open included file: " .. path)
         end
         local content = includedFile:read("*all")
         includedFile:close()
-        local doc = pandoc.read(content, "json")
-        if increment_included_headers then
-            return pandoc.walk_block(pandoc.Div(doc.blocks), {Header = increment}).content
+        -- If lines are selected, the preprocessor includes the source rather
+        -- than the compiled json.
+        local blocks
+        if options.lines then
+            local first, last = options.lines:match("^(%d+)-(%d+)$")
+            blocks = select_lines(content, tonumber(first), tonumber(last))
         else
-            return doc.blocks
+            blocks = pandoc.read(content, "json").blocks
+        end
+        if options.section then
+            blocks = select_section(blocks, options.section:sub(2))
+        end
+        local shift = tonumber(options.shift or "0")
+        if increment_included_headers then
+            shift = shift + 1
         end
+        return shift_headers(blocks, shift)
     end
 end
 
@@ -1,7 +1,7 @@
 import os
 import os.path
 
-from panflute import Header, Para, Str
+from panflute import Header, Note, Para, Str
 
 from markdown.private.utils import test_utils
 
@@ -27,6 +27,45 @@ increment-included-headers: t
 Bar.
 """
 
+DOC3 = """# Foo {#foo}
+
+One.
+
+## Bar {#bar}
+
+Two.
+
+Three.
+
+# Baz {#baz}
+
+Four.
+"""
+
+DOC5 = """Foo[^1].
+
+[^1]: Note.
+
+!include foo.json
+"""
+
+DOC4 = """# Start
+
+!include %s %s
+
+End.
+"""
+
+DOC4_INC = """---
+increment-included-headers: t
+---
+# Start
+
+!include %s %s
+
+End.
+"""
+
 
 class TestInclude(test_utils.PandocLuaFilterTestCase):
     def test_include(self) -> None:
@@ -70,6 +109,98 @@ class TestInclude(test_utils.PandocLuaFilterTestCase):
         )
         self.assertEqual(dict(doc2_inc.metadata), {})
 
+    def test_include_options(self) -> None:
+        doc3_file = os.path.join(self.tmpdir(), "doc3.json")
+        self.dump_doc(doc3_file, self.run_filter(DOC3))
+        # Lines are selected from the source
+        doc3_src = os.path.join(self.tmpdir(), "doc3.md")
+        self.dump_file(doc3_src, DOC3)
+        doc5_src = os.path.join(self.tmpdir(), "doc5.md")
+        self.dump_file(doc5_src, DOC5)
+
+        doc = self.run_filter(DOC4 % (doc3_file, "section=#bar"))
+        self.assertEqual(
+            list(doc.content),
+            [
+                Header(Str("Start"), level=1),
+                Header(Str("Bar"), level=2, identifier="bar"),
+                Para(Str("Two.")),
+                Para(Str("Three.")),
+                Para(Str("End.")),
+            ],
+        )
+
+        doc = self.run_filter(DOC4 % (doc3_src, "section=#bar lines=3-9"))
+        self.assertEqual(
+            list(doc.content),
+            [
+                Header(Str("Start"), level=1),
+                Header(Str("Bar"), level=2, identifier="bar"),
+                Para(Str("Two.")),
+                Para(Str("Three.")),
+                Para(Str("End.")),
+            ],
+        )
+
+        doc = self.run_filter(DOC4 % (doc3_src, "shift=2 lines=1-3"))
+        self.assertEqual(
+            list(doc.content),
+            [
+                Header(Str("Start"), level=1),
+                Header(Str("Foo"), level=3, identifier="foo"),
+                Para(Str("One.")),
+                Para(Str("End.")),
+            ],
+        )
+
+        # Footnotes are kept, since the source isn't re-serialised
+        doc = self.run_filter(DOC4 % (doc5_src, "lines=1-3"))
+        self.assertEqual(
+            list(doc.content),
+            [
+                Header(Str("Start"), level=1),
+                Para(Str("Foo"), Note(Para(Str("Note."))), Str(".")),
+                Para(Str("End.")),
+            ],
+        )
+
+        # Shifts add to increment-included-headers
+        doc = self.run_filter(DOC4_INC % (doc3_file, "shift=1 section=#baz"))
+        self.assertEqual(
+            list(doc.content),
+            [
+                Header(Str("Start"), level=1),
+                Header(Str("Baz"), level=3, identifier="baz"),
+                Para(Str("Four.")),
+                Para(Str("End.")),
+            ],
+        )
+
+    def test_include_options_fail(self) -> None:
+        doc3_file = os.path.join(self.tmpdir(), "doc3.json")
+        self.dump_doc(doc3_file, self.run_filter(DOC3))
+
+        with self.assertRaises(ValueError):
+            self.run_filter(DOC4 % (doc3_file, "section=#quux"))
+
+        doc3_src = os.path.join(self.tmpdir(), "doc3.md")
+        self.dump_file(doc3_src, DOC3)
+        with self.assertRaises(ValueError):
+            self.run_filter(DOC4 % (doc3_src, "lines=1-100"))
+
+        # Nested includes can't be resolved
+        doc5_src = os.path.join(self.tmpdir(), "doc5.md")
+        self.dump_file(doc5_src, DOC5)
+        with self.assertRaises(ValueError):
+            self.run_filter(DOC4 % (doc5_src, "lines=1-5"))
+
+        # Headers only go down to level 6
+        with self.assertRaises(ValueError):
+            self.run_filter(DOC4 % (doc3_file, "shift=5"))
+        with self.assertRaises(ValueError):
+            self.run_filter(DOC4_INC % (doc3_file, "shift=5 section=#foo"))
+        self.run_filter(DOC4_INC % (doc3_file, "shift=4 section=#baz"))
+
     def test_include_fails(self) -> None:
         bad_file = os.path.join(self.tmpdir(), "bad.json")
 
@@ -2,9 +2,12 @@ use clap::Parser;
 use markdown::args::{KeyValue, non_empty};
 use markdown::json::{JsonSerializable, from_json};
 use markdown::metadata::{MetadataMap, OutputMetadata};
+use serde::{Deserialize, Serialize};
 use std::collections::BTreeMap;
 use std::error::Error;
-use std::fs::read_to_string;
+use std::fs::{create_dir_all, read_to_string, remove_file, rename, write};
+use std::path::Path;
+use validator::Validate;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -14,21 +17,247 @@ struct Cli {
 
     #[arg(long = "metadata-file")]
     metadata_files: Vec<KeyValue>,
+
+    /// Persistent writable dir holding the output of the previous run;
+    /// entries whose metadata file is unchanged since then are copied from it
+    /// rather than re-parsed
+    #[arg(long)]
+    #[arg(value_parser = non_empty())]
+    cache_dir: Option<String>,
+}
+
+const CACHED_METADATA: &str = "metadata.json";
+const CACHED_HASHES: &str = "hashes.json";
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(transparent)]
+struct ContentHashes {
+    hashes: BTreeMap<String, String>,
+}
+
+impl JsonSerializable for ContentHashes {}
+
+struct Previous {
+    data: BTreeMap<String, OutputMetadata>,
+    hashes: ContentHashes,
+}
+
+impl Previous {
+    fn get(&self, key: &str, hash: &str) -> Option<&OutputMetadata> {
+        if self.hashes.hashes.get(key).is_some_and(|h| h == hash) {
+            return self.data.get(key);
+        }
+        None
+    }
+
+    fn read(dir: &Path) -> Result<Previous, Box<dyn Error>> {
+        Ok(Previous {
+            data: serde_json::from_str(&read_to_string(dir.join(CACHED_METADATA))?)?,
+            hashes: from_json(&read_to_string(dir.join(CACHED_HASHES))?)?,
+        })
+    }
+}
+
+fn content_hash(content: &str) -> String {
+    format!("{:x}", md5::compute(content))
+}
+
+fn combine(
+    contents: &BTreeMap<String, String>,
+    previous: Option<&Previous>,
+) -> Result<(MetadataMap, ContentHashes), Box<dyn Error>> {
+    let mut data = BTreeMap::new();
+    let mut hashes = ContentHashes::default();
+    for (key, content) in contents {
+        let hash = content_hash(content);
+        let metadata = match previous.and_then(|p| p.get(key, &hash)) {
+            Some(metadata) => metadata.clone(),
+            None => from_json::<OutputMetadata>(content)?,
+        };
+        data.insert(key.clone(), metadata);
+        hashes.hashes.insert(key.clone(), hash);
+    }
+    // Cached entries are deserialised without validation, so validate the
+    // whole map before it is written.
+    Ok((MetadataMap::build(data)?, hashes))
+}
+
+fn write_atomic(path: &Path, content: &str) -> Result<(), Box<dyn Error>> {
+    let tmp = path.with_extension("tmp");
+    write(&tmp, content)?;
+    rename(tmp, path)?;
+    Ok(())
+}
+
+fn write_cache(dir: &Path, out: &str, hashes: &ContentHashes) -> Result<(), Box<dyn Error>> {
+    create_dir_all(dir)?;
+    // Hashes are removed first, so that an interrupted write can't leave
+    // hashes that match stale metadata.
+    let hashes_path = dir.join(CACHED_HASHES);
+    if hashes_path.exists() {
+        remove_file(&hashes_path)?;
+    }
+    write_atomic(&dir.join(CACHED_METADATA), out)?;
+    write_atomic(&hashes_path, &hashes.to_json()?)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Cli::parse();
 
-    let out = args
+    let contents = args
         .metadata_files
         .into_iter()
-        .map(|kv| {
-            Ok((
-                String::from(kv.key()),
-                from_json(&read_to_string(kv.value())?)?,
-            ))
-        })
-        .collect::<Result<BTreeMap<String, OutputMetadata>, Box<dyn Error>>>()?;
+        .map(|kv| Ok((String::from(kv.key()), read_to_string(kv.value())?)))
+        .collect::<Result<BTreeMap<String, String>, Box<dyn Error>>>()?;
+
+    let cache_dir = args.cache_dir.as_deref().map(Path::new);
+    // A missing or unreadable cache just means a full rebuild
+    let previous = cache_dir.and_then(|dir| Previous::read(dir).ok());
+
+    let (out, hashes) = combine(&contents, previous.as_ref())?;
+    let out = out.to_json()?;
+    write(args.out_file, &out)?;
+    if let Some(dir) = cache_dir {
+        write_cache(dir, &out, &hashes)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod combine_deps_metadata_test {
+    use super::{BTreeMap, Previous, combine, content_hash, write_cache};
+    use markdown::json::{JsonSerializable, from_json};
+    use markdown::metadata::{MetadataMap, OutputMetadata};
+    use std::path::Path;
+
+    fn metadata(version: &str) -> String {
+        format!(
+            r#"{{
+  "title": "Title",
+  "author": "A",
+  "wordcount": "100",
+  "poetry-lines": "0",
+  "lang": "en-GB",
+  "version": "{version}",
+  "repo": "repo",
+  "source-hash": "abc",
+  "parsed-dates": ["2020/01"],
+  "publications": [
+    {{
+      "venue": "Foo",
+      "submitted": "2023-01-01",
+      "accepted": "2023-02-01",
+      "paid": {{"currency": "USD", "rate-per-word": 0.08, "status": "pending"}}
+    }}
+  ]
+}}"#
+        )
+    }
+
+    fn contents(entries: &[(&str, String)]) -> BTreeMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (String::from(*k), v.clone()))
+            .collect()
+    }
+
+    fn full_rebuild(contents: &BTreeMap<String, String>) -> String {
+        MetadataMap::build(
+            contents
+                .iter()
+                .map(|(k, v)| (k.clone(), from_json::<OutputMetadata>(v).unwrap()))
+                .collect::<BTreeMap<String, OutputMetadata>>(),
+        )
+        .unwrap()
+        .to_json()
+        .unwrap()
+    }
+
+    fn previous(out: &MetadataMap, hashes: super::ContentHashes) -> Previous {
+        Previous {
+            data: out.data().clone(),
+            hashes,
+        }
+    }
+
+    #[test]
+    fn test_combine_full() {
+        let c = contents(&[("a", metadata("1")), ("b", metadata("2"))]);
+        let (out, hashes) = combine(&c, None).unwrap();
+        assert_eq!(out.to_json().unwrap(), full_rebuild(&c));
+        assert_eq!(hashes.hashes["a"], content_hash(&metadata("1")));
+        assert_eq!(hashes.hashes["b"], content_hash(&metadata("2")));
+    }
+
+    #[test]
+    fn test_combine_incremental_equivalence() {
+        let before = contents(&[
+            ("a", metadata("1")),
+            ("b", metadata("2")),
+            ("c", metadata("3")),
+        ]);
+        let (out, hashes) = combine(&before, None).unwrap();
+        let previous = previous(&out, hashes);
+
+        // One entry changed, one removed and one added
+        let after = contents(&[
+            ("a", metadata("1")),
+            ("b", metadata("4")),
+            ("d", metadata("5")),
+        ]);
+        let (incremental, _) = combine(&after, Some(&previous)).unwrap();
+        assert_eq!(incremental.to_json().unwrap(), full_rebuild(&after));
+    }
+
+    #[test]
+    fn test_combine_incremental_reuses_unchanged() {
+        let c = contents(&[("a", metadata("1"))]);
+        let (_, hashes) = combine(&c, None).unwrap();
+
+        // If the hash matches, the previous entry is used as is, without
+        // parsing the file.
+        let (cached, _) = combine(&contents(&[("a", metadata("2"))]), None).unwrap();
+        let previous = previous(&cached, hashes);
+        let (out, _) = combine(&c, Some(&previous)).unwrap();
+        assert_eq!(out.data()["a"].version(), "2");
+
+        // If it doesn't match, the file is re-parsed.
+        let c = contents(&[("a", String::from("{}"))]);
+        assert!(combine(&c, Some(&previous)).is_err());
+    }
+
+    #[test]
+    fn test_combine_incremental_validates_cached() {
+        let c = contents(&[("a", metadata("1"))]);
+        let (_, hashes) = combine(&c, None).unwrap();
+
+        // Cached entries are deserialised without validation, so an invalid
+        // one is caught when the map is built.
+        let previous = Previous {
+            data: serde_json::from_str(
+                &metadata("1").replace(r#""venue": "Foo""#, r#""venue": """#),
+            )
+            .map(|m: OutputMetadata| BTreeMap::from([(String::from("a"), m)]))
+            .unwrap(),
+            hashes,
+        };
+        assert!(combine(&c, Some(&previous)).is_err());
+    }
+
+    #[test]
+    fn test_cache_round_trip() {
+        let dir =
+            std::env::temp_dir().join(format!("combine_deps_metadata_test_{}", std::process::id()));
+        let c = contents(&[("a", metadata("1")), ("b", metadata("2"))]);
+        let (out, hashes) = combine(&c, None).unwrap();
+        write_cache(&dir, &out.to_json().unwrap(), &hashes).unwrap();
+
+        let previous = Previous::read(Path::new(&dir)).unwrap();
+        assert_eq!(previous.hashes, hashes);
+        let (incremental, _) = combine(&c, Some(&previous)).unwrap();
+        assert_eq!(incremental.to_json().unwrap(), full_rebuild(&c));
 
-    MetadataMap::build(out)?.write_json(args.out_file)
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
 }
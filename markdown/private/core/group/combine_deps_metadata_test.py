@@ -6,6 +6,26 @@ from typing import Any
 from markdown.private.utils import test_utils
 
 
+def metadata(version: str) -> dict[str, Any]:
+    return {
+        "wordcount": "10",
+        "poetry-lines": "0",
+        "lang": "en-GB",
+        "version": version,
+        "repo": "bar",
+        "source-hash": "quux",
+        "parsed-dates": ["2020"],
+        "publications": [
+            {
+                "venue": "Foo",
+                "submitted": "2023-01-01",
+                "accepted": "2023-02-01",
+                "paid": {"currency": "USD", "rate-per-word": 0.07, "status": "pending"},
+            },
+        ],
+    }
+
+
 class TestCombineDepsMetadata(test_utils.ScriptTestCase):
     def run_script(self, metadata: Sequence[Mapping[str, str | list[str]]]) -> dict[str, Any]:  # type: ignore[override]
         metadata_args = []
@@ -71,6 +91,40 @@ class TestCombineDepsMetadata(test_utils.ScriptTestCase):
             },
         )
 
+    def run_incremental(
+        self,
+        metadata: Mapping[str, Mapping[str, Any]],
+        args: Sequence[str] = (),
+    ) -> str:
+        metadata_args = []
+        for key, d in metadata.items():
+            filename = os.path.join(self.tmpdir(), f"metadata_{key}.json")
+            self.dump_json(filename, d)
+            metadata_args += ["--metadata-file", f"{key}={filename}"]
+
+        out_file = os.path.join(self.tmpdir(), "out.json")
+
+        super().run_script(args=[out_file, *args, *metadata_args])
+
+        return self.load_file(out_file)
+
+    def test_incremental(self) -> None:
+        cache_dir = os.path.join(self.tmpdir(), "cache")
+
+        self.run_incremental(
+            {"a": metadata("1"), "b": metadata("2"), "c": metadata("3")},
+            ["--cache-dir", cache_dir],
+        )
+
+        after = {"a": metadata("1"), "b": metadata("4"), "d": metadata("5")}
+        self.assertEqual(
+            self.run_incremental(after, ["--cache-dir", cache_dir]),
+            self.run_incremental(after),
+        )
+        self.assertEqual(
+            self.load_file(os.path.join(cache_dir, "metadata.json")),
+            self.run_incremental(after),
+        )
 
 if __name__ == "__main__":
     test_utils.ScriptTestCase.main()
@@ -0,0 +1,39 @@
+use clap::{Parser, ValueEnum};
+use markdown::args;
+use markdown::metadata::{InputMetadata, MetadataMap, OutputMetadata};
+use markdown::publications::Publication;
+use markdown::schema::document;
+use std::error::Error;
+use std::fs::write;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Type {
+    InputMetadata,
+    OutputMetadata,
+    Publication,
+    MetadataMap,
+}
+
+#[derive(Parser)]
+#[command(version, about = "Write the JSON Schema for a metadata type", long_about = None)]
+struct Cli {
+    #[arg(value_enum)]
+    schema_type: Type,
+
+    #[arg(value_parser = args::non_empty())]
+    out_file: String,
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+
+    let schema = match args.schema_type {
+        Type::InputMetadata => document::<InputMetadata>("InputMetadata"),
+        Type::OutputMetadata => document::<OutputMetadata>("OutputMetadata"),
+        Type::Publication => document::<Publication>("Publication"),
+        Type::MetadataMap => document::<MetadataMap>("MetadataMap"),
+    };
+
+    write(args.out_file, serde_json::to_string_pretty(&schema)? + "\n")?;
+    Ok(())
+}
@@ -0,0 +1,52 @@
+import os
+import os.path
+import subprocess
+from typing import Any
+
+from markdown.private.utils import test_utils
+
+
+class TestJsonSchema(test_utils.ScriptTestCase):
+    def run_script(self, schema_type: str) -> dict[str, Any]:  # type: ignore[override]
+        out_file = os.path.join(self.tmpdir(), "out.json")
+
+        super().run_script(args=[schema_type, out_file])
+
+        return self.load_json(out_file)
+
+    def test_input_metadata(self) -> None:
+        schema = self.run_script("input-metadata")
+        self.assertEqual(schema["title"], "InputMetadata")
+        self.assertEqual(schema["$schema"], "https://json-schema.org/draft/2020-12/schema")
+        self.assertFalse(schema["additionalProperties"])
+        self.assertEqual(schema["required"], [])
+        self.assertIn("publications", schema["properties"])
+
+    def test_output_metadata(self) -> None:
+        schema = self.run_script("output-metadata")
+        self.assertEqual(schema["title"], "OutputMetadata")
+        self.assertIn("source-hash", schema["required"])
+        self.assertEqual(
+            schema["properties"]["parsed-dates"]["items"]["pattern"],
+            "^[0-9]{4}(/[0-9]{2}(/[0-9]{2})?)?$",
+        )
+
+    def test_publication(self) -> None:
+        schema = self.run_script("publication")
+        self.assertEqual(schema["title"], "Publication")
+        self.assertEqual(schema["required"], ["venue"])
+        self.assertEqual(schema["properties"]["venue"], {"type": "string", "minLength": 1})
+        self.assertEqual(schema["properties"]["submitted"], {"type": "string", "format": "date"})
+
+    def test_metadata_map(self) -> None:
+        schema = self.run_script("metadata-map")
+        self.assertEqual(schema["title"], "MetadataMap")
+        self.assertEqual(schema["additionalProperties"]["type"], "object")
+
+    def test_unknown_type(self) -> None:
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script("foo")
+
+
+if __name__ == "__main__":
+    test_utils.ScriptTestCase.main()
@@ -1,17 +1,36 @@
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use markdown::args;
-use markdown::problems::{Problems, RowColProblem};
+use markdown::json::from_json;
+use markdown::lint::RuleConfigs;
+use markdown::problems::{Diagnostic, Format, Problems, RowColProblem, Severity};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
-use std::fmt::Display;
 use std::fs::{read_to_string, write};
+use validator::Validate;
 
 const CURLY_QUOTES: &str = "“”‘’";
+const CURLY_QUOTES_NAME: &str = "curly quotes";
+const CURLY_QUOTES_RULE: &str = "curly-quotes";
 const CURLY_QUOTE_MSG: &str = "Literal curly quotes must be backslash-escaped";
 const BAD_CHARS: [(char, &str, &str); 3] = [
     ('–', "en-dashes", "--"),
     ('—', "em-dashes", "---"),
     ('…', "ellipses", "..."),
 ];
+const DOUBLE_SPACES_RULE: &str = "double-spaces";
+const TRAILING_WHITESPACE_RULE: &str = "trailing-whitespace";
+const BRITISH_SPELLINGS_RULE: &str = "british-spellings";
+const FORBIDDEN_WORDS_RULE: &str = "forbidden-words";
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+enum QuoteFix {
+    /// Backslash-escape curly quotes, keeping them as they are
+    Escape,
+    /// Replace curly quotes with straight quotes, which become curly in output
+    Straighten,
+}
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -19,59 +38,357 @@ struct Cli {
     #[arg(value_parser = args::non_empty())]
     in_file: String,
 
+    #[arg(required_unless_present = "fix")]
+    #[arg(value_parser = args::non_empty())]
+    out_file: Option<String>,
+
+    /// Lint rule configuration; all rules have their defaults if unset
+    #[arg(long)]
     #[arg(value_parser = args::non_empty())]
-    out_file: String,
+    config: Option<String>,
+
+    /// Fix problems by rewriting in_file in place, instead of reporting them
+    #[arg(long)]
+    fix: bool,
+
+    /// How --fix handles literal curly quotes
+    #[arg(long, value_enum, default_value_t = QuoteFix::Escape)]
+    quotes: QuoteFix,
+
+    /// Format of any problems reported; defaults to $MARKDOWN_PROBLEMS_FORMAT,
+    /// or text
+    #[arg(long, value_enum)]
+    problems_format: Option<Format>,
+
+    /// Fail on warnings, as well as errors
+    #[arg(long)]
+    warnings_as_errors: bool,
 }
 
-fn lint(data: &str) -> Vec<RowColProblem> {
-    let mut problems = Vec::new();
+#[derive(Clone, Debug, PartialEq, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+struct CustomRule {
+    #[validate(length(min = 1))]
+    name: String,
+    #[validate(length(min = 1))]
+    pattern: String,
+    #[validate(length(min = 1))]
+    message: String,
+    #[serde(default)]
+    severity: Severity,
+}
 
-    for (row, line) in data.lines().enumerate() {
-        let chars: Vec<char> = line.chars().collect();
-        for (col, &c) in chars.iter().enumerate() {
-            if CURLY_QUOTES.contains(c) && (col == 0 || chars[col - 1] != '\\') {
-                problems.push(RowColProblem::new(row, col, CURLY_QUOTE_MSG));
+/// Lint rule configuration.
+#[derive(Clone, Debug, Default, PartialEq, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+struct LintConfig {
+    /// Settings for built-in rules
+    #[serde(default)]
+    #[validate(nested)]
+    rules: RuleConfigs,
+    /// Spellings that aren't allowed, and the British spellings to use instead
+    #[serde(default)]
+    british_spellings: BTreeMap<String, String>,
+    #[serde(default)]
+    forbidden_words: Vec<String>,
+    #[serde(default)]
+    #[validate(nested)]
+    custom_rules: Vec<CustomRule>,
+}
+
+enum Pattern {
+    CurlyQuotes,
+    Regex(Regex),
+}
+
+impl Pattern {
+    fn word(word: &str) -> Pattern {
+        Pattern::Regex(Regex::new(&format!(r"(?i)\b{}\b", regex::escape(word))).unwrap())
+    }
+
+    // Columns of each match in line.
+    fn find(&self, line: &str) -> Vec<usize> {
+        match self {
+            Pattern::CurlyQuotes => {
+                let chars: Vec<char> = line.chars().collect();
+                (0..chars.len())
+                    .filter(|&col| is_unescaped_quote(&chars, col))
+                    .collect()
             }
+            Pattern::Regex(re) => re
+                .find_iter(line)
+                .map(|m| line[..m.start()].chars().count())
+                .collect(),
         }
+    }
+}
+
+struct Rule {
+    name: String,
+    severity: Severity,
+    pattern: Pattern,
+    msg: String,
+}
+
+struct RuleSet {
+    rules: Vec<Rule>,
+}
 
+impl RuleSet {
+    fn build(config: LintConfig) -> Result<RuleSet, Box<dyn Error>> {
+        // Built-in rules, and whether they're enabled by default
+        let mut builtins = vec![(
+            CURLY_QUOTES_RULE,
+            true,
+            Pattern::CurlyQuotes,
+            String::from(CURLY_QUOTE_MSG),
+        )];
         for (bad_char, name, replacement) in BAD_CHARS {
-            if let Some(col) = chars.iter().position(|&c| c == bad_char) {
-                problems.push(RowColProblem::new(
-                    row,
-                    col,
-                    &format!("Literal {} must be replaced with '{}'", name, replacement),
-                ));
+            builtins.push((
+                name,
+                true,
+                Pattern::Regex(Regex::new(&regex::escape(&bad_char.to_string())).unwrap()),
+                format!("Literal {} must be replaced with '{}'", name, replacement),
+            ));
+        }
+        builtins.push((
+            DOUBLE_SPACES_RULE,
+            false,
+            Pattern::Regex(Regex::new(r"[.!?] {2,}\S").unwrap()),
+            String::from("Sentences must be separated by a single space"),
+        ));
+        builtins.push((
+            TRAILING_WHITESPACE_RULE,
+            false,
+            Pattern::Regex(Regex::new(r"\s+$").unwrap()),
+            String::from("Lines must not end with whitespace"),
+        ));
+        for (from, to) in &config.british_spellings {
+            builtins.push((
+                BRITISH_SPELLINGS_RULE,
+                true,
+                Pattern::word(from),
+                format!("Use the British spelling '{to}' rather than '{from}'"),
+            ));
+        }
+        for word in &config.forbidden_words {
+            builtins.push((
+                FORBIDDEN_WORDS_RULE,
+                true,
+                Pattern::word(word),
+                format!("The word '{word}' is forbidden"),
+            ));
+        }
+
+        let mut names =
+            BTreeSet::from([BRITISH_SPELLINGS_RULE, FORBIDDEN_WORDS_RULE].map(String::from));
+        names.extend(builtins.iter().map(|(name, _, _, _)| String::from(*name)));
+        config.rules.check_names(names.iter().map(String::as_str))?;
+
+        let mut rules = Vec::new();
+        for (name, default_enabled, pattern, msg) in builtins {
+            if let Some(severity) = config
+                .rules
+                .severity(name, default_enabled, Severity::Error)
+            {
+                rules.push(Rule {
+                    name: String::from(name),
+                    severity,
+                    pattern,
+                    msg,
+                });
+            }
+        }
+
+        for custom in config.custom_rules {
+            if !names.insert(custom.name.clone()) {
+                return Err(
+                    format!("Lint rule '{}' is defined more than once", custom.name).into(),
+                );
+            }
+            let re = Regex::new(&custom.pattern)
+                .map_err(|e| format!("Invalid pattern for lint rule '{}': {e}", custom.name))?;
+            rules.push(Rule {
+                name: custom.name.clone(),
+                severity: custom.severity,
+                pattern: Pattern::Regex(re),
+                msg: custom.message.clone(),
+            });
+        }
+
+        Ok(RuleSet { rules })
+    }
+
+    fn is_enabled(&self, name: &str) -> bool {
+        self.rules.iter().any(|r| r.name == name)
+    }
+}
+
+impl Default for RuleSet {
+    fn default() -> RuleSet {
+        RuleSet::build(LintConfig::default()).unwrap()
+    }
+}
+
+#[derive(Debug, Default)]
+struct LintResult {
+    errors: Vec<RowColProblem>,
+    // Warnings and info
+    warnings: Vec<Diagnostic>,
+}
+
+fn is_unescaped_quote(chars: &[char], col: usize) -> bool {
+    CURLY_QUOTES.contains(chars[col]) && (col == 0 || chars[col - 1] != '\\')
+}
+
+fn lint(data: &str, rules: &RuleSet) -> LintResult {
+    let mut result = LintResult::default();
+
+    for (row, line) in data.lines().enumerate() {
+        let mut found = Vec::new();
+        for rule in &rules.rules {
+            found.extend(rule.pattern.find(line).into_iter().map(|col| (col, rule)));
+        }
+        // Stable, so problems at the same column stay in rule order
+        found.sort_by_key(|(col, _)| *col);
+
+        for (col, rule) in found {
+            let problem = RowColProblem::new(row, col, &rule.msg).with_rule(&rule.name);
+            match rule.severity {
+                Severity::Error => result.errors.push(problem),
+                severity => result
+                    .warnings
+                    .push(Diagnostic::from(problem).with_severity(severity)),
+            }
+        }
+    }
+
+    result
+}
+
+/// Fixes the problems lint reports for enabled character rules, returning
+/// the fixed data and the number of each kind of character that was replaced.
+fn fix(data: &str, quotes: QuoteFix, rules: &RuleSet) -> (String, BTreeMap<&'static str, usize>) {
+    let mut out = String::with_capacity(data.len());
+    let mut counts = BTreeMap::new();
+    let chars: Vec<char> = data.chars().collect();
+    let bad_chars: Vec<_> = BAD_CHARS
+        .iter()
+        .filter(|(_, name, _)| rules.is_enabled(name))
+        .collect();
+    let fix_quotes = rules.is_enabled(CURLY_QUOTES_RULE);
+
+    for (i, &c) in chars.iter().enumerate() {
+        if fix_quotes && is_unescaped_quote(&chars, i) {
+            match quotes {
+                QuoteFix::Escape => {
+                    out.push('\\');
+                    out.push(c);
+                }
+                QuoteFix::Straighten => out.push(if "“”".contains(c) { '"' } else { '\'' }),
             }
+            *counts.entry(CURLY_QUOTES_NAME).or_default() += 1;
+        } else if let Some((_, name, replacement)) = bad_chars.iter().find(|(b, _, _)| *b == c) {
+            out.push_str(replacement);
+            *counts.entry(*name).or_default() += 1;
+        } else {
+            out.push(c);
         }
     }
 
-    problems
+    (out, counts)
+}
+
+fn fix_summary(file: &str, counts: &BTreeMap<&str, usize>) -> String {
+    if counts.is_empty() {
+        return format!("Nothing to fix in {file}");
+    }
+    let mut lines = vec![format!(
+        "Fixed {} problems in {file}:",
+        counts.values().sum::<usize>()
+    )];
+    lines.extend(counts.iter().map(|(name, n)| format!("  {n} {name}")));
+    lines.join("\n")
+}
+
+fn ok_output(warnings: &[Diagnostic]) -> String {
+    let mut out = String::from("OK\n");
+    for w in warnings {
+        out.push_str(&format!(
+            "{}: {w}\n",
+            w.severity().to_string().to_uppercase()
+        ));
+    }
+    out
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Cli::parse();
+    let data = read_to_string(&args.in_file)?;
+    let rules = match args.config {
+        Some(path) => RuleSet::build(from_json(&read_to_string(path)?)?)?,
+        None => RuleSet::default(),
+    };
 
-    let mut problems = Problems::new("linting failed");
-    problems.extend(
-        lint(&read_to_string(args.in_file)?)
-            .into_iter()
-            .map(|p| -> Box<dyn Display> { Box::new(p) }),
-    );
+    if args.fix {
+        let (fixed, counts) = fix(&data, args.quotes, &rules);
+        if fixed != data {
+            write(&args.in_file, fixed)?;
+        }
+        println!("{}", fix_summary(&args.in_file, &counts));
+        if let Some(out_file) = args.out_file {
+            write(out_file, "OK\n")?;
+        }
+        return Ok(());
+    }
+
+    let result = lint(&data, &rules);
+    let mut problems = Problems::new("linting failed")
+        .with_file(&args.in_file)
+        .with_format(args.problems_format)
+        .with_warnings_as_errors(args.warnings_as_errors);
+    // Only fatal problems are reported through problems; others go in the
+    // output.
+    let (fatal, warnings): (Vec<_>, Vec<_>) = result
+        .warnings
+        .into_iter()
+        .partition(|p| problems.is_fatal(p));
+    problems.extend(result.errors.into_iter().map(Diagnostic::from));
+    problems.extend(fatal);
     problems.check();
 
-    write(args.out_file, "OK\n")?;
+    if let Some(out_file) = args.out_file {
+        write(out_file, ok_output(&warnings))?;
+    }
     Ok(())
 }
 
 #[cfg(test)]
 mod custom_lint_test {
-    use crate::lint;
+    use crate::{LintConfig, QuoteFix, RowColProblem, RuleSet, fix, fix_summary, lint, ok_output};
+    use markdown::json::from_json;
+    use std::collections::BTreeMap;
+    use std::fmt::Display;
+
+    fn errors(data: &str) -> Vec<RowColProblem> {
+        lint(data, &RuleSet::default()).errors
+    }
+
+    fn strings<T: Display>(problems: &[T]) -> Vec<String> {
+        problems.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn rules(config: &str) -> RuleSet {
+        RuleSet::build(from_json::<LintConfig>(config).unwrap()).unwrap()
+    }
 
     #[test]
     fn test_lint() {
         // OK
         assert!(
-            lint(
+            errors(
                 "Foo bar.
 
 \\“Lots \\”of \\‘quotes\\’.
@@ -82,12 +399,173 @@ Some -- dashes---
             .is_empty()
         );
 
-        assert!(!lint("“").is_empty());
-        assert!(!lint("”").is_empty());
-        assert!(!lint("‘").is_empty());
-        assert!(!lint("’").is_empty());
-        assert!(!lint("–").is_empty());
-        assert!(!lint("—").is_empty());
-        assert!(!lint("…").is_empty());
+        assert!(!errors("“").is_empty());
+        assert!(!errors("”").is_empty());
+        assert!(!errors("‘").is_empty());
+        assert!(!errors("’").is_empty());
+        assert!(!errors("–").is_empty());
+        assert!(!errors("—").is_empty());
+        assert!(!errors("…").is_empty());
+    }
+
+    #[test]
+    fn test_lint_every_occurrence() {
+        assert_eq!(
+            strings(&errors("a – b – c……\n“x” \\“y\\”")),
+            [
+                "row 1 col 3: Literal en-dashes must be replaced with '--'",
+                "row 1 col 7: Literal en-dashes must be replaced with '--'",
+                "row 1 col 10: Literal ellipses must be replaced with '...'",
+                "row 1 col 11: Literal ellipses must be replaced with '...'",
+                "row 2 col 1: Literal curly quotes must be backslash-escaped",
+                "row 2 col 3: Literal curly quotes must be backslash-escaped",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_fix() {
+        let data = "“Foo” – ‘bar’ — baz…\n\\“Quux\\” – …\n";
+
+        let (fixed, counts) = fix(data, QuoteFix::Escape, &RuleSet::default());
+        assert_eq!(
+            fixed,
+            "\\“Foo\\” -- \\‘bar\\’ --- baz...\n\\“Quux\\” -- ...\n"
+        );
+        assert!(errors(&fixed).is_empty());
+        assert_eq!(
+            counts,
+            BTreeMap::from([
+                ("curly quotes", 4),
+                ("ellipses", 2),
+                ("em-dashes", 1),
+                ("en-dashes", 2),
+            ])
+        );
+
+        let (fixed, counts) = fix(data, QuoteFix::Straighten, &RuleSet::default());
+        assert_eq!(fixed, "\"Foo\" -- 'bar' --- baz...\n\\“Quux\\” -- ...\n");
+        assert!(errors(&fixed).is_empty());
+        assert_eq!(counts["curly quotes"], 4);
+
+        let (fixed, counts) = fix("Foo.\n", QuoteFix::Escape, &RuleSet::default());
+        assert_eq!(fixed, "Foo.\n");
+        assert!(counts.is_empty());
+    }
+
+    #[test]
+    fn test_fix_summary() {
+        assert_eq!(
+            fix_summary("a.md", &BTreeMap::new()),
+            "Nothing to fix in a.md"
+        );
+        assert_eq!(
+            fix_summary(
+                "a.md",
+                &BTreeMap::from([("curly quotes", 2), ("en-dashes", 1)])
+            ),
+            "Fixed 3 problems in a.md:
+  2 curly quotes
+  1 en-dashes"
+        );
+    }
+
+    #[test]
+    fn test_lint_config() {
+        let rules = rules(
+            r#"{
+  "rules": {
+    "em-dashes": {"enabled": false},
+    "ellipses": {"severity": "warning"},
+    "double-spaces": {"enabled": true},
+    "trailing-whitespace": {"enabled": true, "severity": "warning"},
+    "forbidden-words": {"severity": "warning"}
+  },
+  "british-spellings": {"color": "colour"},
+  "forbidden-words": ["very", "suddenly"],
+  "custom-rules": [
+    {"name": "no-todo", "pattern": "TODO", "message": "Remove TODOs"},
+    {"name": "no-but", "pattern": "^But", "message": "Don't start with 'but'", "severity": "warning"}
+  ]
+}"#,
+        );
+        let result = lint(
+            "But the Color was very — odd…  \nSuddenly.  TODO: it  was. \n",
+            &rules,
+        );
+        assert_eq!(
+            strings(&result.errors),
+            [
+                "row 1 col 9: Use the British spelling 'colour' rather than 'color'",
+                "row 2 col 9: Sentences must be separated by a single space",
+                "row 2 col 12: Remove TODOs",
+            ]
+        );
+        assert_eq!(
+            strings(&result.warnings),
+            [
+                "row 1 col 1: Don't start with 'but'",
+                "row 1 col 19: The word 'very' is forbidden",
+                "row 1 col 29: Literal ellipses must be replaced with '...'",
+                "row 1 col 30: Lines must not end with whitespace",
+                "row 2 col 1: The word 'suddenly' is forbidden",
+                "row 2 col 26: Lines must not end with whitespace",
+            ]
+        );
+
+        assert_eq!(
+            result.errors.iter().map(|p| p.rule()).collect::<Vec<_>>(),
+            [
+                Some("british-spellings"),
+                Some("double-spaces"),
+                Some("no-todo")
+            ]
+        );
+
+        // Fixes only apply to enabled rules
+        let (fixed, _) = fix("“a” — b…", QuoteFix::Escape, &rules);
+        assert_eq!(fixed, "\\“a\\” — b...");
+
+        assert_eq!(
+            ok_output(&result.warnings[..1]),
+            "OK\nWARNING: row 1 col 1: Don't start with 'but'\n"
+        );
+        assert_eq!(ok_output(&[]), "OK\n");
+    }
+
+    #[test]
+    fn test_lint_config_fails() {
+        for (config, msg) in [
+            (
+                r#"{"rules": {"foo": {"enabled": false}}}"#,
+                "Unknown lint rule 'foo'",
+            ),
+            (
+                r#"{"custom-rules": [{"name": "a", "pattern": "(", "message": "b"}]}"#,
+                "Invalid pattern for lint rule 'a'",
+            ),
+            (
+                r#"{"custom-rules": [{"name": "ellipses", "pattern": "a", "message": "b"}]}"#,
+                "Lint rule 'ellipses' is defined more than once",
+            ),
+            (
+                r#"{"custom-rules": [
+  {"name": "a", "pattern": "a", "message": "b"},
+  {"name": "a", "pattern": "b", "message": "b"}
+]}"#,
+                "Lint rule 'a' is defined more than once",
+            ),
+        ] {
+            let err = RuleSet::build(from_json(config).unwrap()).err().unwrap();
+            assert!(err.to_string().starts_with(msg), "{err}");
+        }
+
+        assert!(from_json::<LintConfig>(r#"{"foo": []}"#).is_err());
+        assert!(
+            from_json::<LintConfig>(
+                r#"{"custom-rules": [{"name": "", "pattern": "a", "message": "b"}]}"#
+            )
+            .is_err()
+        );
     }
 }
@@ -1,6 +1,8 @@
+import json
 import os
 import os.path
 import subprocess
+from typing import Any
 
 from markdown.private.utils import test_utils
 
@@ -16,28 +18,89 @@ class TestCustomLint(test_utils.ScriptTestCase):
     def run_script(  # type: ignore[override]
         self,
         content: str,
-    ) -> None:
+        config: dict[str, Any] | None = None,
+    ) -> str:
         in_file = os.path.join(self.tmpdir(), "in.md")
         self.dump_file(in_file, content)
 
         out_file = os.path.join(self.tmpdir(), "out.txt")
 
+        config_args = []
+        if config is not None:
+            config_file = os.path.join(self.tmpdir(), "config.json")
+            self.dump_json(config_file, config)
+            config_args = ["--config", config_file]
+
         super().run_script(
             args=[
                 in_file,
                 out_file,
+                *config_args,
             ],
         )
 
-        self.assertEqual(self.load_file(out_file), "OK\n")
+        return self.load_file(out_file)
 
     def test_main(self) -> None:
-        self.run_script(GOOD)
+        self.assertEqual(self.run_script(GOOD), "OK\n")
+
+    def test_config(self) -> None:
+        config = {
+            "rules": {"em-dashes": {"enabled": False}, "ellipses": {"severity": "warning"}},
+            "forbidden-words": ["very"],
+        }
+        self.assertEqual(
+            self.run_script("Foo — bar…\n", config),
+            "OK\nWARNING: row 1 col 10: Literal ellipses must be replaced with '...'\n",
+        )
+
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script("Very good.\n", config)
+
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(GOOD, {"rules": {"foo": {}}})
 
     def test_main_fails(self) -> None:
         with self.assertRaises(subprocess.CalledProcessError):
             self.run_script("“")
 
+    def test_fix(self) -> None:
+        in_file = os.path.join(self.tmpdir(), "in.md")
+        self.dump_file(in_file, "“Foo” – bar…\n")  # noqa: RUF001
+
+        result = super().run_script(args=["--fix", in_file])
+        self.assertEqual(self.load_file(in_file), "\\“Foo\\” -- bar...\n")  # noqa: RUF001
+        self.assertEqual(
+            result.stdout,
+            f"Fixed 4 problems in {in_file}:\n  2 curly quotes\n  1 ellipses\n  1 en-dashes\n",
+        )
+        self.run_script(self.load_file(in_file))
+
+        self.dump_file(in_file, "“Foo” ‘bar’\n")  # noqa: RUF001
+        super().run_script(args=["--fix", "--quotes=straighten", in_file])
+        self.assertEqual(self.load_file(in_file), "\"Foo\" 'bar'\n")
+
+    def test_problems_format(self) -> None:
+        in_file = os.path.join(self.tmpdir(), "in.md")
+        self.dump_file(in_file, "Foo…\n")
+        out_file = os.path.join(self.tmpdir(), "out.txt")
+
+        try:
+            super().run_script(args=[in_file, out_file, "--problems-format=json-lines"])
+            self.fail()
+        except subprocess.CalledProcessError as e:
+            self.assertEqual(
+                json.loads(e.stderr),
+                {
+                    "file": in_file,
+                    "row": 1,
+                    "column": 4,
+                    "severity": "error",
+                    "rule": "ellipses",
+                    "message": "Literal ellipses must be replaced with '...'",
+                },
+            )
+
 
 if __name__ == "__main__":
     test_utils.ScriptTestCase.main()
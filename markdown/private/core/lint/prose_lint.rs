@@ -0,0 +1,654 @@
+use clap::Parser;
+use markdown::args;
+use markdown::json::from_json;
+use markdown::lint::RuleConfigs;
+use markdown::problems::{Diagnostic, Format, Problems, RowColProblem, Severity};
+use regex::Regex;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::{read_to_string, write};
+use validator::Validate;
+
+const DOUBLED_WORDS_RULE: &str = "doubled-words";
+const LONG_SENTENCES_RULE: &str = "long-sentences";
+const OVERUSED_ADVERBS_RULE: &str = "overused-adverbs";
+const REPEATED_SENTENCE_OPENERS_RULE: &str = "repeated-sentence-openers";
+const REPEATED_PARAGRAPH_OPENERS_RULE: &str = "repeated-paragraph-openers";
+const FILTER_WORDS_RULE: &str = "filter-words";
+
+// Rules, and whether they're enabled by default. All rules are warnings by
+// default, so that they only fail the build if a project asks for it.
+const RULES: [(&str, bool); 6] = [
+    (DOUBLED_WORDS_RULE, true),
+    (LONG_SENTENCES_RULE, true),
+    (OVERUSED_ADVERBS_RULE, true),
+    (REPEATED_SENTENCE_OPENERS_RULE, true),
+    (REPEATED_PARAGRAPH_OPENERS_RULE, true),
+    (FILTER_WORDS_RULE, false),
+];
+
+// Words that distance the reader from a viewpoint character.
+const DEFAULT_FILTER_WORDS: [&str; 16] = [
+    "saw", "see", "heard", "hear", "felt", "feel", "noticed", "notice", "realised", "realized",
+    "wondered", "seemed", "decided", "thought", "knew", "watched",
+];
+
+// Words ending in -ly that aren't adverbs, or are too common to count.
+const NOT_ADVERBS: [&str; 24] = [
+    "only", "early", "daily", "family", "reply", "apply", "supply", "rely", "holy", "ugly",
+    "belly", "ally", "lily", "jelly", "bully", "july", "italy", "likely", "lovely", "lonely",
+    "friendly", "lively", "silly", "elderly",
+];
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[arg(value_parser = args::non_empty())]
+    in_file: String,
+
+    /// Report on the document's prose, including any warnings
+    #[arg(value_parser = args::non_empty())]
+    out_file: String,
+
+    /// Prose lint configuration; all rules have their defaults if unset
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    config: Option<String>,
+
+    /// Format of any problems reported; defaults to $MARKDOWN_PROBLEMS_FORMAT,
+    /// or text
+    #[arg(long, value_enum)]
+    problems_format: Option<Format>,
+
+    /// Fail on warnings, as well as errors
+    #[arg(long)]
+    warnings_as_errors: bool,
+}
+
+fn default_max_sentence_words() -> usize {
+    40
+}
+
+fn default_max_adverb_uses() -> usize {
+    3
+}
+
+fn default_max_repeated_openers() -> usize {
+    2
+}
+
+#[derive(Clone, Debug, PartialEq, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+struct ProseLintConfig {
+    #[serde(default)]
+    #[validate(nested)]
+    rules: RuleConfigs,
+
+    #[serde(default = "default_max_sentence_words")]
+    #[validate(range(min = 1))]
+    max_sentence_words: usize,
+
+    /// Maximum number of times each adverb can be used in the document
+    #[serde(default = "default_max_adverb_uses")]
+    #[validate(range(min = 1))]
+    max_adverb_uses: usize,
+
+    /// Maximum number of sentences in a row in a paragraph that can start
+    /// with the same word
+    #[serde(default = "default_max_repeated_openers")]
+    #[validate(range(min = 1))]
+    max_repeated_sentence_openers: usize,
+
+    /// Maximum number of paragraphs in a row that can start with the same word
+    #[serde(default = "default_max_repeated_openers")]
+    #[validate(range(min = 1))]
+    max_repeated_paragraph_openers: usize,
+
+    /// Replaces the default filter words
+    #[serde(default)]
+    filter_words: Option<Vec<String>>,
+}
+
+impl Default for ProseLintConfig {
+    fn default() -> ProseLintConfig {
+        from_json("{}").unwrap()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+struct Word {
+    row: usize,
+    col: usize,
+    text: String,
+    // Lower case
+    key: String,
+    // Whether this word starts a sentence
+    sentence_start: bool,
+    // Whether there's only whitespace between this and the previous word
+    plain_gap: bool,
+}
+
+type Paragraph = Vec<Word>;
+
+// Lines that aren't prose, and end any paragraph they follow.
+fn is_non_prose(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    ["#", "!include", "<", ":::", "|", "["]
+        .iter()
+        .any(|p| trimmed.starts_with(p))
+}
+
+/// Splits the prose in a markdown document into paragraphs of words, skipping
+/// front matter, code blocks, headers and other lines that aren't prose.
+fn paragraphs(data: &str) -> Vec<Paragraph> {
+    let word_re = Regex::new(r"[\p{L}\p{N}]+(?:['’-][\p{L}\p{N}]+)*").unwrap();
+    let sentence_end_re = Regex::new(r"[.!?][^\s\p{L}\p{N}]*\s").unwrap();
+
+    let lines: Vec<&str> = data.lines().collect();
+    let mut start = 0;
+    if lines.first().map(|l| l.trim_end()) == Some("---") {
+        if let Some(end) = lines
+            .iter()
+            .skip(1)
+            .position(|l| matches!(l.trim_end(), "---" | "..."))
+        {
+            start = end + 2;
+        }
+    }
+
+    let mut out = Vec::new();
+    let mut paragraph: Paragraph = Vec::new();
+    let mut gap = String::new();
+    let mut in_code = false;
+    for (row, line) in lines.iter().enumerate().skip(start) {
+        let trimmed = line.trim_start();
+        if trimmed.starts_with("```") || trimmed.starts_with("~~~") {
+            in_code = !in_code;
+        }
+        if in_code || trimmed.is_empty() || is_non_prose(line) || line.starts_with("    ") {
+            if !paragraph.is_empty() {
+                out.push(std::mem::take(&mut paragraph));
+            }
+            continue;
+        }
+
+        let mut last_end = 0;
+        for m in word_re.find_iter(line) {
+            gap.push_str(&line[last_end..m.start()]);
+            paragraph.push(Word {
+                row,
+                col: line[..m.start()].chars().count(),
+                text: String::from(m.as_str()),
+                key: m.as_str().to_lowercase(),
+                sentence_start: paragraph.is_empty() || sentence_end_re.is_match(&gap),
+                plain_gap: !paragraph.is_empty() && gap.trim().is_empty(),
+            });
+            gap.clear();
+            last_end = m.end();
+        }
+        gap.push_str(&line[last_end..]);
+        gap.push('\n');
+    }
+    if !paragraph.is_empty() {
+        out.push(paragraph);
+    }
+    out
+}
+
+fn sentences(paragraph: &[Word]) -> Vec<&[Word]> {
+    let mut out = Vec::new();
+    let mut start = 0;
+    for (i, w) in paragraph.iter().enumerate().skip(1) {
+        if w.sentence_start {
+            out.push(&paragraph[start..i]);
+            start = i;
+        }
+    }
+    if start < paragraph.len() {
+        out.push(&paragraph[start..]);
+    }
+    out
+}
+
+fn is_adverb(word: &str) -> bool {
+    word.chars().count() > 4 && word.ends_with("ly") && !NOT_ADVERBS.contains(&word)
+}
+
+/// Counts of each word, most frequent first.
+fn ranked(counts: &BTreeMap<String, usize>) -> Vec<(&str, usize)> {
+    let mut out: Vec<(&str, usize)> = counts.iter().map(|(w, n)| (w.as_str(), *n)).collect();
+    out.sort_by_key(|(w, n)| (std::cmp::Reverse(*n), *w));
+    out
+}
+
+#[derive(Clone, Debug, Default)]
+struct ProseReport {
+    words: usize,
+    sentences: usize,
+    paragraphs: usize,
+    // Length and row of the longest sentence
+    longest_sentence: Option<(usize, usize)>,
+    adverbs: BTreeMap<String, usize>,
+    filter_words: BTreeMap<String, usize>,
+    // Warnings and info
+    warnings: Vec<Diagnostic>,
+}
+
+impl ProseReport {
+    fn write_counts(
+        &self,
+        f: &mut Formatter<'_>,
+        name: &str,
+        counts: &BTreeMap<String, usize>,
+    ) -> std::fmt::Result {
+        let total: usize = counts.values().sum();
+        let per_thousand = if self.words == 0 {
+            0.0
+        } else {
+            total as f64 * 1000.0 / self.words as f64
+        };
+        writeln!(f, "{name}: {total} ({per_thousand:.1} per 1000 words)")?;
+        for (word, n) in ranked(counts) {
+            writeln!(f, "  {word}: {n}")?;
+        }
+        Ok(())
+    }
+}
+
+impl Display for ProseReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Words: {}", self.words)?;
+        writeln!(f, "Sentences: {}", self.sentences)?;
+        writeln!(f, "Paragraphs: {}", self.paragraphs)?;
+        let average = if self.sentences == 0 {
+            0.0
+        } else {
+            self.words as f64 / self.sentences as f64
+        };
+        writeln!(f, "Average sentence length: {average:.1} words")?;
+        if let Some((len, row)) = self.longest_sentence {
+            writeln!(f, "Longest sentence: {len} words, at row {}", row + 1)?;
+        }
+        self.write_counts(f, "Adverbs", &self.adverbs)?;
+        self.write_counts(f, "Filter words", &self.filter_words)?;
+        if !self.warnings.is_empty() {
+            writeln!(f, "\nWarnings:")?;
+            for w in &self.warnings {
+                writeln!(f, "  {w}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+struct LintResult {
+    errors: Vec<RowColProblem>,
+    report: ProseReport,
+}
+
+// Problems for runs of more than max items in a row starting with the same
+// word, reported at the first item that goes over.
+fn repeated_openers<'a>(
+    openers: impl Iterator<Item = &'a Word>,
+    max: usize,
+    name: &str,
+) -> Vec<RowColProblem> {
+    let mut problems = Vec::new();
+    let mut run: Vec<&Word> = Vec::new();
+    for w in openers {
+        if run.last().is_some_and(|last| last.key != w.key) {
+            run.clear();
+        }
+        run.push(w);
+        if run.len() == max + 1 {
+            problems.push(RowColProblem::new(
+                w.row,
+                w.col,
+                &format!(
+                    "{} {name} in a row start with '{}', more than the maximum of {max}",
+                    run.len(),
+                    w.text
+                ),
+            ));
+        }
+    }
+    problems
+}
+
+fn lint(data: &str, config: &ProseLintConfig) -> Result<LintResult, Box<dyn Error>> {
+    config
+        .rules
+        .check_names(RULES.iter().map(|(name, _)| *name))?;
+    let severity = |name: &str| {
+        let default_enabled = RULES.iter().any(|(n, enabled)| *n == name && *enabled);
+        config
+            .rules
+            .severity(name, default_enabled, Severity::Warning)
+    };
+    let filter_words: Vec<String> = match &config.filter_words {
+        Some(words) => words.iter().map(|w| w.to_lowercase()).collect(),
+        None => DEFAULT_FILTER_WORDS.map(String::from).to_vec(),
+    };
+
+    let paragraphs = paragraphs(data);
+    let mut report = ProseReport {
+        paragraphs: paragraphs.len(),
+        ..Default::default()
+    };
+    let mut found: Vec<(&str, RowColProblem)> = Vec::new();
+
+    for paragraph in &paragraphs {
+        for (prev, w) in paragraph.iter().zip(paragraph.iter().skip(1)) {
+            if w.plain_gap && prev.key == w.key {
+                found.push((
+                    DOUBLED_WORDS_RULE,
+                    RowColProblem::new(w.row, w.col, &format!("Doubled word '{}'", w.text)),
+                ));
+            }
+        }
+
+        let sentences = sentences(paragraph);
+        for sentence in &sentences {
+            let (len, first) = (sentence.len(), &sentence[0]);
+            report.sentences += 1;
+            if report.longest_sentence.is_none_or(|(l, _)| len > l) {
+                report.longest_sentence = Some((len, first.row));
+            }
+            if len > config.max_sentence_words {
+                found.push((
+                    LONG_SENTENCES_RULE,
+                    RowColProblem::new(
+                        first.row,
+                        first.col,
+                        &format!(
+                            "Sentence is {len} words long, more than the maximum of {}",
+                            config.max_sentence_words
+                        ),
+                    ),
+                ));
+            }
+        }
+        found.extend(
+            repeated_openers(
+                sentences.iter().map(|s| &s[0]),
+                config.max_repeated_sentence_openers,
+                "sentences",
+            )
+            .into_iter()
+            .map(|p| (REPEATED_SENTENCE_OPENERS_RULE, p)),
+        );
+
+        for w in paragraph {
+            report.words += 1;
+            if is_adverb(&w.key) {
+                *report.adverbs.entry(w.key.clone()).or_default() += 1;
+            }
+            if filter_words.contains(&w.key) {
+                *report.filter_words.entry(w.key.clone()).or_default() += 1;
+                found.push((
+                    FILTER_WORDS_RULE,
+                    RowColProblem::new(w.row, w.col, &format!("Filter word '{}'", w.text)),
+                ));
+            }
+        }
+    }
+
+    found.extend(
+        repeated_openers(
+            paragraphs.iter().map(|p| &p[0]),
+            config.max_repeated_paragraph_openers,
+            "paragraphs",
+        )
+        .into_iter()
+        .map(|p| (REPEATED_PARAGRAPH_OPENERS_RULE, p)),
+    );
+
+    // Overused adverbs are reported where they go over the maximum.
+    let mut adverb_uses: BTreeMap<&str, usize> = BTreeMap::new();
+    for w in paragraphs.iter().flatten() {
+        if let Some(&total) = report.adverbs.get(&w.key) {
+            let uses = adverb_uses.entry(&w.key).or_default();
+            *uses += 1;
+            if *uses == config.max_adverb_uses + 1 {
+                found.push((
+                    OVERUSED_ADVERBS_RULE,
+                    RowColProblem::new(
+                        w.row,
+                        w.col,
+                        &format!(
+                            "Adverb '{}' is used {total} times, more than the maximum of {}",
+                            w.key, config.max_adverb_uses
+                        ),
+                    ),
+                ));
+            }
+        }
+    }
+
+    found.sort_by_key(|(_, p)| (p.row(), p.col()));
+    let mut errors = Vec::new();
+    for (rule, problem) in found {
+        let problem = problem.with_rule(rule);
+        match severity(rule) {
+            Some(Severity::Error) => errors.push(problem),
+            Some(severity) => report
+                .warnings
+                .push(Diagnostic::from(problem).with_severity(severity)),
+            None => {}
+        }
+    }
+
+    Ok(LintResult { errors, report })
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+    let config = match args.config {
+        Some(path) => from_json(&read_to_string(path)?)?,
+        None => ProseLintConfig::default(),
+    };
+
+    let mut result = lint(&read_to_string(&args.in_file)?, &config)?;
+    let mut problems = Problems::new("prose linting failed")
+        .with_file(&args.in_file)
+        .with_format(args.problems_format)
+        .with_warnings_as_errors(args.warnings_as_errors);
+    // Only fatal problems are reported through problems; others go in the
+    // report.
+    let (fatal, warnings): (Vec<_>, Vec<_>) = result
+        .report
+        .warnings
+        .into_iter()
+        .partition(|p| problems.is_fatal(p));
+    result.report.warnings = warnings;
+    problems.extend(result.errors.into_iter().map(Diagnostic::from));
+    problems.extend(fatal);
+    problems.check();
+
+    write(args.out_file, result.report.to_string())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod prose_lint_test {
+    use super::{Display, ProseLintConfig, is_adverb, lint, paragraphs, sentences};
+    use markdown::json::from_json;
+
+    fn strings<T: Display>(problems: &[T]) -> Vec<String> {
+        problems.iter().map(|p| p.to_string()).collect()
+    }
+
+    fn config(json: &str) -> ProseLintConfig {
+        from_json(json).unwrap()
+    }
+
+    #[test]
+    fn test_paragraphs() {
+        let ps = paragraphs(
+            "---
+title: The the
+---
+
+# Header header
+
+It's the end. Of
+the world? \"Yes.\" No
+
+```
+code code
+```
+
+!include foo
+
+Well-known 3.5 *things*.
+",
+        );
+        let words: Vec<Vec<(&str, bool)>> = ps
+            .iter()
+            .map(|p| {
+                p.iter()
+                    .map(|w| (w.text.as_str(), w.sentence_start))
+                    .collect()
+            })
+            .collect();
+        assert_eq!(
+            words,
+            [
+                vec![
+                    ("It's", true),
+                    ("the", false),
+                    ("end", false),
+                    ("Of", true),
+                    ("the", false),
+                    ("world", false),
+                    ("Yes", true),
+                    ("No", true),
+                ],
+                vec![
+                    ("Well-known", true),
+                    ("3", false),
+                    ("5", false),
+                    ("things", false),
+                ],
+            ]
+        );
+        assert_eq!((ps[0][4].row, ps[0][4].col), (7, 0));
+        assert_eq!(
+            sentences(&ps[0])
+                .iter()
+                .map(|s| s.len())
+                .collect::<Vec<_>>(),
+            [3, 3, 1, 1]
+        );
+    }
+
+    #[test]
+    fn test_is_adverb() {
+        assert!(is_adverb("quickly"));
+        assert!(is_adverb("suddenly"));
+        assert!(!is_adverb("only"));
+        assert!(!is_adverb("family"));
+        assert!(!is_adverb("fly"));
+        assert!(!is_adverb("quick"));
+    }
+
+    #[test]
+    fn test_lint() {
+        let data = "He ran the the race. He won it quickly. He sat down slowly, \
+quickly and quietly.
+
+Then he left *the* the house quickly.
+
+Then he came back.
+
+Then she saw him.
+";
+        let result = lint(data, &ProseLintConfig::default()).unwrap();
+        assert!(result.errors.is_empty());
+        assert_eq!(
+            strings(&result.report.warnings),
+            [
+                "row 1 col 12: Doubled word 'the'",
+                "row 1 col 41: 3 sentences in a row start with 'He', more than the maximum of 2",
+                "row 7 col 1: 3 paragraphs in a row start with 'Then', more than the maximum of 2",
+            ]
+        );
+        assert_eq!(
+            result.report.to_string(),
+            "Words: 31
+Sentences: 6
+Paragraphs: 4
+Average sentence length: 5.2 words
+Longest sentence: 7 words, at row 1
+Adverbs: 5 (161.3 per 1000 words)
+  quickly: 3
+  quietly: 1
+  slowly: 1
+Filter words: 1 (32.3 per 1000 words)
+  saw: 1
+
+Warnings:
+  row 1 col 12: Doubled word 'the'
+  row 1 col 41: 3 sentences in a row start with 'He', more than the maximum of 2
+  row 7 col 1: 3 paragraphs in a row start with 'Then', more than the maximum of 2
+"
+        );
+
+        assert_eq!(
+            result
+                .report
+                .warnings
+                .iter()
+                .map(|p| p.rule())
+                .collect::<Vec<_>>(),
+            [
+                Some("doubled-words"),
+                Some("repeated-sentence-openers"),
+                Some("repeated-paragraph-openers"),
+            ]
+        );
+
+        let result = lint(
+            data,
+            &config(
+                r#"{
+  "rules": {
+    "doubled-words": {"severity": "error"},
+    "filter-words": {"enabled": true, "severity": "error"},
+    "repeated-paragraph-openers": {"enabled": false}
+  },
+  "max-sentence-words": 6,
+  "max-adverb-uses": 2,
+  "max-repeated-sentence-openers": 3,
+  "filter-words": ["Sat"]
+}"#,
+            ),
+        )
+        .unwrap();
+        assert_eq!(
+            strings(&result.errors),
+            [
+                "row 1 col 12: Doubled word 'the'",
+                "row 1 col 44: Filter word 'sat'",
+            ]
+        );
+        assert_eq!(
+            strings(&result.report.warnings),
+            [
+                "row 1 col 41: Sentence is 7 words long, more than the maximum of 6",
+                "row 3 col 1: Sentence is 7 words long, more than the maximum of 6",
+                "row 3 col 30: Adverb 'quickly' is used 3 times, more than the maximum of 2",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_lint_fails() {
+        assert!(lint("Foo.", &config(r#"{"rules": {"foo": {}}}"#)).is_err());
+        assert!(from_json::<ProseLintConfig>(r#"{"max-sentence-words": 0}"#).is_err());
+        assert!(from_json::<ProseLintConfig>(r#"{"foo": 1}"#).is_err());
+    }
+}
@@ -0,0 +1,68 @@
+import os
+import os.path
+import subprocess
+from typing import Any
+
+from markdown.private.utils import test_utils
+
+DOC = """# Title
+
+He ran the the race. He won.
+
+Then he left.
+"""
+
+
+class TestProseLint(test_utils.ScriptTestCase):
+    def run_script(  # type: ignore[override]
+        self,
+        content: str,
+        config: dict[str, Any] | None = None,
+    ) -> str:
+        in_file = os.path.join(self.tmpdir(), "in.md")
+        self.dump_file(in_file, content)
+
+        out_file = os.path.join(self.tmpdir(), "out.txt")
+
+        config_args = []
+        if config is not None:
+            config_file = os.path.join(self.tmpdir(), "config.json")
+            self.dump_json(config_file, config)
+            config_args = ["--config", config_file]
+
+        super().run_script(
+            args=[
+                in_file,
+                out_file,
+                *config_args,
+            ],
+        )
+
+        return self.load_file(out_file)
+
+    def test_main(self) -> None:
+        self.assertEqual(
+            self.run_script(DOC),
+            """Words: 10
+Sentences: 3
+Paragraphs: 2
+Average sentence length: 3.3 words
+Longest sentence: 5 words, at row 3
+Adverbs: 0 (0.0 per 1000 words)
+Filter words: 0 (0.0 per 1000 words)
+
+Warnings:
+  row 3 col 12: Doubled word 'the'
+""",
+        )
+
+    def test_main_fails(self) -> None:
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(DOC, {"rules": {"doubled-words": {"severity": "error"}}})
+
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(DOC, {"max-sentence-words": 0})
+
+
+if __name__ == "__main__":
+    test_utils.ScriptTestCase.main()
@@ -0,0 +1,161 @@
+use chrono::naive::NaiveDate;
+use clap::Parser;
+use markdown::args;
+use markdown::json::{JsonSerializable, from_json};
+use markdown::metadata::{InputMetadata, ParsedDateSet, ParsedDates};
+use regex::{Captures, Regex};
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::read_to_string;
+
+const MONTH: &str = r"(?:jan(?:uary)?|feb(?:ruary)?|mar(?:ch)?|apr(?:il)?|may|june?|july?|aug(?:ust)?|sep(?:t(?:ember)?)?|oct(?:ober)?|nov(?:ember)?|dec(?:ember)?)\.?";
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+
+#[derive(Parser)]
+#[command(version, about, long_about = None)]
+struct Cli {
+    #[arg(value_parser = args::non_empty())]
+    in_file: String,
+
+    #[arg(value_parser = args::non_empty())]
+    out_file: String,
+}
+
+fn date_regex() -> Regex {
+    // Alternatives are tried in order at each position, so longer forms must
+    // come before the shorter forms they contain. Numeric dates are
+    // day-first, as in en-GB, unless they start with the year.
+    let alternatives = [
+        r"(?P<iso_y>\d{4})[-/.](?P<iso_m>\d{1,2})[-/.](?P<iso_d>\d{1,2})".to_string(),
+        r"(?P<num_d>\d{1,2})[-/.](?P<num_m>\d{1,2})[-/.](?P<num_y>\d{4})".to_string(),
+        format!(
+            r"(?P<dmy_d>\d{{1,2}})(?:st|nd|rd|th)?(?:\s+of)?\s+(?P<dmy_m>{MONTH}),?\s+(?P<dmy_y>\d{{4}})"
+        ),
+        format!(
+            r"(?P<mdy_m>{MONTH})\s+(?P<mdy_d>\d{{1,2}})(?:st|nd|rd|th)?,?\s+(?P<mdy_y>\d{{4}})"
+        ),
+        format!(r"(?P<my_m>{MONTH}),?\s+(?P<my_y>\d{{4}})"),
+        r"(?P<y_y>\d{4})".to_string(),
+    ];
+    Regex::new(&format!(r"(?i)\b(?:{})\b", alternatives.join("|"))).unwrap()
+}
+
+fn month_number(month: &str) -> Option<u32> {
+    let month = month.to_ascii_lowercase();
+    MONTHS
+        .iter()
+        .position(|m| month.starts_with(m))
+        .map(|i| i as u32 + 1)
+}
+
+fn parse_match(caps: &Captures) -> Option<String> {
+    fn get<'a>(caps: &'a Captures, name: &str) -> Option<&'a str> {
+        caps.name(name).map(|m| m.as_str())
+    }
+
+    fn day(year: &str, month: u32, day: &str) -> Option<String> {
+        let date = NaiveDate::from_ymd_opt(year.parse().ok()?, month, day.parse().ok()?)?;
+        Some(date.format("%Y/%m/%d").to_string())
+    }
+
+    fn month(year: &str, month: u32) -> Option<String> {
+        let date = NaiveDate::from_ymd_opt(year.parse().ok()?, month, 1)?;
+        Some(date.format("%Y/%m").to_string())
+    }
+
+    if let Some(y) = get(caps, "iso_y") {
+        return day(y, get(caps, "iso_m")?.parse().ok()?, get(caps, "iso_d")?);
+    }
+    if let Some(y) = get(caps, "num_y") {
+        return day(y, get(caps, "num_m")?.parse().ok()?, get(caps, "num_d")?);
+    }
+    if let Some(y) = get(caps, "dmy_y") {
+        return day(y, month_number(get(caps, "dmy_m")?)?, get(caps, "dmy_d")?);
+    }
+    if let Some(y) = get(caps, "mdy_y") {
+        return day(y, month_number(get(caps, "mdy_m")?)?, get(caps, "mdy_d")?);
+    }
+    if let Some(y) = get(caps, "my_y") {
+        return month(y, month_number(get(caps, "my_m")?)?);
+    }
+    get(caps, "y_y").map(String::from)
+}
+
+fn parse_date(date: &str) -> BTreeSet<String> {
+    date_regex()
+        .captures_iter(date)
+        .filter_map(|caps| parse_match(&caps))
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+
+    let metadata: InputMetadata = from_json(&read_to_string(args.in_file)?)?;
+    let dates = metadata.date().map(|d| parse_date(d)).unwrap_or_default();
+
+    ParsedDates::build(ParsedDateSet::build(Vec::from_iter(dates))?)?.write_json(args.out_file)
+}
+
+#[cfg(test)]
+mod parse_date_test {
+    use super::{BTreeSet, parse_date};
+
+    fn dates(ds: &[&str]) -> BTreeSet<String> {
+        ds.iter().map(|d| String::from(*d)).collect()
+    }
+
+    #[test]
+    fn test_parse_date_none() {
+        assert!(parse_date("").is_empty());
+        assert!(parse_date("no dates").is_empty());
+        assert!(parse_date("I may do it").is_empty());
+        assert!(parse_date("12345").is_empty());
+    }
+
+    #[test]
+    fn test_parse_date_year() {
+        assert_eq!(parse_date("2022"), dates(&["2022"]));
+        assert_eq!(parse_date("written in 1999"), dates(&["1999"]));
+    }
+
+    #[test]
+    fn test_parse_date_month() {
+        assert_eq!(parse_date("March 2019"), dates(&["2019/03"]));
+        assert_eq!(parse_date("sept. 2019"), dates(&["2019/09"]));
+        assert_eq!(parse_date("Dec, 2019"), dates(&["2019/12"]));
+    }
+
+    #[test]
+    fn test_parse_date_day() {
+        assert_eq!(parse_date("4th June 2021"), dates(&["2021/06/04"]));
+        assert_eq!(parse_date("4 of June, 2021"), dates(&["2021/06/04"]));
+        assert_eq!(parse_date("June 4th, 2021"), dates(&["2021/06/04"]));
+        assert_eq!(parse_date("04/06/2021"), dates(&["2021/06/04"]));
+        assert_eq!(parse_date("4.6.2021"), dates(&["2021/06/04"]));
+        assert_eq!(parse_date("2021-06-04"), dates(&["2021/06/04"]));
+        assert_eq!(parse_date("2021/06/04"), dates(&["2021/06/04"]));
+    }
+
+    #[test]
+    fn test_parse_date_invalid() {
+        assert!(parse_date("31/02/2021").is_empty());
+        assert!(parse_date("2021-13-01").is_empty());
+    }
+
+    #[test]
+    fn test_parse_date_multiple() {
+        assert_eq!(
+            parse_date("from August 2020 to 1 March 2023"),
+            dates(&["2020/08", "2023/03/01"])
+        );
+        assert_eq!(
+            parse_date("March 2019, revised 4th June 2021"),
+            dates(&["2019/03", "2021/06/04"])
+        );
+        assert_eq!(parse_date("2020, 2020 and again in 2020"), dates(&["2020"]));
+    }
+}
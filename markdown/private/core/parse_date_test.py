@@ -42,6 +42,11 @@ class TestParseDate(test_utils.ScriptTestCase):
             {"parsed-dates": ["2020/08", "2023/03/01"]},
         )
 
+        self.assertEqual(
+            self.run_script("March 2019, revised 4th June 2021"),
+            {"parsed-dates": ["2019/03", "2021/06/04"]},
+        )
+
 
 if __name__ == "__main__":
     test_utils.ScriptTestCase.main()
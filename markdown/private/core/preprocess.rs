@@ -1,7 +1,9 @@
 use clap::Parser;
 use markdown::args::{KeyValue, non_empty};
 use markdown::bazel::Label;
-use markdown::problems::{ColProblem, Problems, RowProblem};
+use markdown::include_graph::IncludeGraph;
+use markdown::json::{JsonSerializable, from_json};
+use markdown::problems::{ColProblem, Diagnostic, Format, Problems, RowProblem, Severity};
 use regex::Regex;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::error::Error;
@@ -13,9 +15,22 @@ const INCLUDE: &str = "!include";
 const INCLUDE_MSG: &str = "Incorrectly-formatted include. Must be '!include \
 <md_file label>' where label is in deps, e.g. '!include //foo:bar'.";
 
+const INCLUDE_OPTION_MSG: &str = "Incorrectly-formatted include option. Options \
+are 'shift=<0-5>', 'section=#<header id>' and 'lines=<first>-<last>', e.g. \
+'!include //foo:bar shift=1 section=#chapter-3 lines=10-40'.";
+
+// Rule ids for problems
+const INCLUDE_RULE: &str = "include";
+const INCLUDE_GRAPH_RULE: &str = "include-graph";
+const IMAGE_RULE: &str = "image";
+const STRICT_DEPS_RULE: &str = "strict-deps";
+
+// Headers only go down to level 6.
+const MAX_SHIFT: u8 = 5;
+
 const IMAGE_MSG: &str = "Incorrectly-formatted image. Must be \
-'![<text>](<label>[ \"text\"])' where label is in 'images', e.g. \
-'![foo](//foo:bar)'.";
+'![<text>](<label>[ \"text\"])', '![<text>][<ref>]' with '[<ref>]: <label>[ \"text\"]', \
+or '<img src=\"<label>\">', where label is in 'images', e.g. '![foo](//foo:bar)'.";
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -31,8 +46,152 @@ struct Cli {
     #[arg(long = "dep")]
     deps: Vec<KeyValue>,
 
+    /// Preprocessed source of a dep, which lines are selected from
+    #[arg(long = "dep-src")]
+    dep_srcs: Vec<KeyValue>,
+
     #[arg(long = "image")]
     images: Vec<KeyValue>,
+
+    /// Name of the target being preprocessed, used to identify it in
+    /// include graphs
+    #[arg(long)]
+    #[arg(value_parser = non_empty())]
+    name: Option<String>,
+
+    /// Include graph of a dep, as written by its --include-graph-out-file
+    #[arg(long = "dep-include-graph", requires = "name")]
+    dep_include_graphs: Vec<String>,
+
+    /// Write the transitive include graph of this file here
+    #[arg(long, requires = "name")]
+    #[arg(value_parser = non_empty())]
+    include_graph_out_file: Option<String>,
+
+    /// Maximum depth of nested includes, counting this file's includes as
+    /// depth 1
+    #[arg(long, requires = "name")]
+    max_include_depth: Option<usize>,
+
+    /// Format of any problems reported; defaults to $MARKDOWN_PROBLEMS_FORMAT,
+    /// or text
+    #[arg(long, value_enum)]
+    problems_format: Option<Format>,
+
+    /// Fail on warnings, as well as errors
+    #[arg(long)]
+    warnings_as_errors: bool,
+
+    /// Write warnings here, rather than to stderr
+    #[arg(long)]
+    #[arg(value_parser = non_empty())]
+    report_file: Option<String>,
+}
+
+struct IncludeWalk<'a> {
+    graph: &'a IncludeGraph,
+    cycles: Vec<String>,
+    occurrences: BTreeMap<&'a str, Vec<String>>,
+    deepest: Vec<&'a str>,
+}
+
+impl<'a> IncludeWalk<'a> {
+    fn walk(&mut self, path: &mut Vec<&'a str>) {
+        let label = *path.last().unwrap();
+        if path.len() > self.deepest.len() {
+            self.deepest = path.clone();
+        }
+        for child in self.graph.children(label) {
+            if let Some(start) = path.iter().position(|l| l == child) {
+                let mut cycle = path[start..].to_vec();
+                cycle.push(child);
+                self.cycles.push(cycle.join(" -> "));
+                continue;
+            }
+            path.push(child);
+            self.occurrences
+                .entry(child)
+                .or_default()
+                .push(path.join(" -> "));
+            self.walk(path);
+            path.pop();
+        }
+    }
+}
+
+// Problems in the include tree under each of this file's includes, reported
+// on the row of the include they're under.
+fn check_include_graph(
+    root: &str,
+    includes: &[(usize, String)],
+    graph: &IncludeGraph,
+    max_depth: Option<usize>,
+) -> Vec<RowProblem> {
+    let mut problems = Vec::new();
+    let mut occurrences: BTreeMap<&str, Vec<(usize, String)>> = BTreeMap::new();
+
+    for (row, label) in includes {
+        let mut walk = IncludeWalk {
+            graph,
+            cycles: Vec::new(),
+            occurrences: BTreeMap::from([(label.as_str(), vec![format!("{root} -> {label}")])]),
+            deepest: Vec::new(),
+        };
+        let mut path = vec![root, label.as_str()];
+        if label == root {
+            walk.cycles.push(path.join(" -> "));
+        } else {
+            walk.walk(&mut path);
+        }
+
+        problems.extend(
+            walk.cycles
+                .iter()
+                .map(|c| RowProblem::new(*row, &format!("Include cycle: {c}"))),
+        );
+
+        // The root isn't included, so doesn't count towards the depth.
+        let depth = walk.deepest.len().saturating_sub(1);
+        if let Some(max_depth) = max_depth {
+            if depth > max_depth {
+                problems.push(RowProblem::new(
+                    *row,
+                    &format!(
+                        "Includes are nested {depth} deep, more than the maximum of {max_depth}: {}",
+                        walk.deepest.join(" -> ")
+                    ),
+                ));
+            }
+        }
+
+        for (l, paths) in walk.occurrences {
+            occurrences
+                .entry(l)
+                .or_default()
+                .extend(paths.into_iter().map(|p| (*row, p)));
+        }
+    }
+
+    for (label, paths) in occurrences {
+        if paths.len() > 1 {
+            let (row, _) = paths[1];
+            problems.push(RowProblem::new(
+                row,
+                &format!(
+                    "{label} is included {} times: {}",
+                    paths.len(),
+                    paths
+                        .iter()
+                        .map(|(_, p)| p.as_str())
+                        .collect::<Vec<&str>>()
+                        .join(", ")
+                ),
+            ));
+        }
+    }
+
+    problems.sort_by_key(|p| p.row());
+    problems
 }
 
 struct ReplacementResult<T> {
@@ -40,9 +199,87 @@ struct ReplacementResult<T> {
     deps_used: HashSet<String>,
 }
 
+#[derive(Clone, Debug, Default, PartialEq)]
+struct IncludeOptions {
+    shift: Option<u8>,
+    section: Option<String>,
+    lines: Option<(usize, usize)>,
+}
+
+impl IncludeOptions {
+    fn parse(text: &str) -> Result<IncludeOptions, Vec<String>> {
+        let section_re = Regex::new(r"^#[\w.:-]+$").unwrap();
+        let lines_re = Regex::new(r"^([0-9]+)-([0-9]+)$").unwrap();
+
+        let mut options = IncludeOptions::default();
+        let mut problems = Vec::new();
+        for option in text.split_whitespace() {
+            let Some((key, value)) = option.split_once('=') else {
+                problems.push(format!("{INCLUDE_OPTION_MSG} {option}"));
+                continue;
+            };
+            let duplicate = match key {
+                "shift" => {
+                    let shift = value.parse().ok().filter(|s| *s <= MAX_SHIFT);
+                    if shift.is_none() {
+                        problems.push(format!("{INCLUDE_OPTION_MSG} {option}"));
+                    }
+                    options.shift.replace(shift.unwrap_or_default()).is_some()
+                }
+                "section" => {
+                    if !section_re.is_match(value) {
+                        problems.push(format!("{INCLUDE_OPTION_MSG} {option}"));
+                    }
+                    options.section.replace(String::from(value)).is_some()
+                }
+                "lines" => {
+                    let lines = lines_re
+                        .captures(value)
+                        .and_then(|c| Some((c[1].parse().ok()?, c[2].parse().ok()?)))
+                        .filter(|(first, last)| *first >= 1 && first <= last);
+                    if lines.is_none() {
+                        problems.push(format!("{INCLUDE_OPTION_MSG} {option}"));
+                    }
+                    options.lines.replace(lines.unwrap_or_default()).is_some()
+                }
+                _ => {
+                    problems.push(format!("{INCLUDE_OPTION_MSG} {option}"));
+                    false
+                }
+            };
+            if duplicate {
+                problems.push(format!("Include option '{key}' is set more than once"));
+            }
+        }
+
+        if !problems.is_empty() {
+            return Err(problems);
+        }
+        Ok(options)
+    }
+}
+
+impl Display for IncludeOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(shift) = self.shift {
+            write!(f, " shift={shift}")?;
+        }
+        if let Some(section) = &self.section {
+            write!(f, " section={section}")?;
+        }
+        if let Some((first, last)) = self.lines {
+            write!(f, " lines={first}-{last}")?;
+        }
+        Ok(())
+    }
+}
+
+// Includes are replaced with the compiled dep, or its preprocessed source if
+// lines are selected, since lines are counted in the source.
 fn process_include(
     line: &str,
     deps: &HashMap<String, String>,
+    dep_srcs: &HashMap<String, String>,
     current_package: &str,
 ) -> ReplacementResult<String> {
     let Some(raw_label) = line.strip_prefix(INCLUDE) else {
@@ -61,18 +298,38 @@ fn process_include(
         };
     }
     let raw_label = raw_label.trim_start_matches(' ');
+    let (raw_label, options) = raw_label.split_once(' ').unwrap_or((raw_label, ""));
 
     match Label::canonicalise(raw_label, current_package) {
         Ok(label) => {
             let label = format!("{}:{}", label.package(), label.target());
-            if let Some(replacement) = deps.get(&label) {
+            let options = IncludeOptions::parse(options);
+            let mut problems = Vec::new();
+            if !deps.contains_key(&label) {
+                problems.push(format!("{INCLUDE_MSG} {label}"));
+            }
+            if let Err(ps) = &options {
+                problems.extend(ps.iter().cloned());
+            }
+            let replacement = match &options {
+                Ok(IncludeOptions { lines: Some(_), .. }) => {
+                    if deps.contains_key(&label) && !dep_srcs.contains_key(&label) {
+                        problems.push(format!(
+                            "Include option 'lines' can't be used, as the source of {label} isn't available"
+                        ));
+                    }
+                    deps.get(&label).and(dep_srcs.get(&label))
+                }
+                _ => deps.get(&label),
+            };
+            if let (Some(replacement), Ok(options)) = (replacement, options) {
                 return ReplacementResult {
-                    result: Ok(Some(format!("!include {replacement}"))),
+                    result: Ok(Some(format!("!include {replacement}{options}"))),
                     deps_used: HashSet::from([label]),
                 };
             }
             ReplacementResult {
-                result: Err(vec![format!("{INCLUDE_MSG} {label}")]),
+                result: Err(problems),
                 deps_used: HashSet::from([label]),
             }
         }
@@ -83,30 +340,88 @@ fn process_include(
     }
 }
 
+// Reference names are case-insensitive and ignore differences in whitespace.
+fn normalise_ref(name: &str) -> String {
+    name.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// Names of the references used by reference-style images, e.g. 'foo' in
+/// '![text][foo]', '![foo][]' or '![foo]'.
+fn image_refs(data: &[String]) -> HashSet<String> {
+    let re = Regex::new(r"!\[([^\]]*)\](?:\[([^\]]*)\])?").unwrap();
+    let mut refs = HashSet::new();
+    for line in data {
+        for c in re.captures_iter(line) {
+            let name = match c.get(2) {
+                Some(name) if !name.as_str().is_empty() => name.as_str(),
+                Some(_) => &c[1],
+                None => {
+                    if line[c.get(0).unwrap().end()..].starts_with('(') {
+                        // An inline image
+                        continue;
+                    }
+                    &c[1]
+                }
+            };
+            refs.insert(normalise_ref(name));
+        }
+    }
+    refs
+}
+
+// The parts of a line that refer to an image: the destination of an inline
+// image, the destination of a reference definition used by an image, or the
+// src of an HTML img tag.
+fn image_sources<'a>(line: &'a str, image_refs: &HashSet<String>) -> Vec<regex::Match<'a>> {
+    let mut sources = Vec::new();
+
+    let re = Regex::new(r"!\[[^\]]*\]\(([^\)]+)\)").unwrap();
+    sources.extend(re.captures_iter(line).map(|c| c.get(1).unwrap()));
+
+    let re = Regex::new(r"^ {0,3}\[([^\]]+)\]:[ \t]*(\S.*?)[ \t]*$").unwrap();
+    if let Some(c) = re.captures(line) {
+        if image_refs.contains(&normalise_ref(&c[1])) {
+            sources.push(c.get(2).unwrap());
+        }
+    }
+
+    let re = Regex::new(r#"(?i)<img\b[^>]*?\ssrc\s*=\s*(?:"([^"]*)"|'([^']*)')"#).unwrap();
+    sources.extend(
+        re.captures_iter(line)
+            .map(|c| c.get(1).or_else(|| c.get(2)).unwrap()),
+    );
+
+    sources.sort_by_key(|m| m.start());
+    sources
+}
+
 fn process_images(
     line: &str,
     images: &HashMap<String, String>,
+    image_refs: &HashSet<String>,
     current_package: &str,
 ) -> ReplacementResult<ColProblem> {
     let char_indices: Vec<usize> = line.char_indices().map(|(i, _)| i).collect();
     let mut problems = Vec::new();
     let mut labels = HashSet::new();
-    let mut replacements = BTreeMap::new();
+    let mut replacements = Vec::new();
 
-    let re = Regex::new(r"!\[[^\]]*\]\(([^\)]+)\)").unwrap();
-    let captures: Vec<regex::Captures<'_>> = re.captures_iter(line).collect();
-    if captures.is_empty() {
+    let sources = image_sources(line, image_refs);
+    if sources.is_empty() {
         return ReplacementResult {
             result: Ok(None),
             deps_used: labels,
         };
     }
 
-    for capture in captures.iter().map(|c| c.get(1).unwrap()) {
-        let text = capture.as_str();
+    for source in sources {
+        let text = source.as_str();
         let col = char_indices
             .iter()
-            .position(|&i| i == capture.start())
+            .position(|&i| i == source.start())
             .unwrap();
 
         let (raw_label, title) = {
@@ -133,7 +448,7 @@ fn process_images(
                             replacement.to_string()
                         }
                     };
-                    replacements.insert(text, replacement);
+                    replacements.push((source.range(), replacement));
                 } else {
                     problems.push(ColProblem::new(col, &format!("{IMAGE_MSG} {label}")));
                 }
@@ -150,11 +465,9 @@ fn process_images(
     }
 
     let mut line = String::from(line);
-    for (text, replacement) in replacements {
-        let re = Regex::new(format!(r"!\[([^\]]*)\]\({}\)", regex::escape(text)).as_str()).unwrap();
-        line = re
-            .replace_all(&line, format!("![${{1}}]({replacement})"))
-            .to_string();
+    // Replace from the end so that earlier ranges are still valid.
+    for (range, replacement) in replacements.into_iter().rev() {
+        line.replace_range(range, &replacement);
     }
 
     ReplacementResult {
@@ -163,61 +476,76 @@ fn process_images(
     }
 }
 
+// Using undeclared deps is an error, but declaring unused ones is only a
+// warning.
 fn check_strict_deps(
     used: &BTreeSet<String>,
     declared: &BTreeSet<String>,
     name: &str,
-) -> Result<(), String> {
-    if used != declared {
-        let used_only = used - declared;
-        let declared_only = declared - used;
-        let mut msg = vec![format!("Used {name} do not match declared {name}")];
-        if !used_only.is_empty() {
-            msg.push(String::from("Used but not declared"));
-            msg.extend(used_only.iter().map(|d| format!("  //{d}")));
-        }
-        if !declared_only.is_empty() {
-            msg.push(String::from("Declared but not used"));
-            msg.extend(declared_only.iter().map(|d| format!("  //{d}")));
-        }
-        return Err(msg.join("\n"));
+) -> Vec<Diagnostic> {
+    let mut problems = Vec::new();
+    let used_only = used - declared;
+    if !used_only.is_empty() {
+        let mut msg = vec![format!("Used {name} not declared in {name}")];
+        msg.extend(used_only.iter().map(|d| format!("  //{d}")));
+        problems.push(Diagnostic::new(&msg.join("\n")).with_rule(STRICT_DEPS_RULE));
     }
-    Ok(())
+    let declared_only = declared - used;
+    if !declared_only.is_empty() {
+        let mut msg = vec![format!("Declared {name} not used")];
+        msg.extend(declared_only.iter().map(|d| format!("  //{d}")));
+        problems.push(
+            Diagnostic::new(&msg.join("\n"))
+                .with_rule(STRICT_DEPS_RULE)
+                .with_severity(Severity::Warning),
+        );
+    }
+    problems
+}
+
+struct PreprocessResult {
+    problems: Vec<Diagnostic>,
+    // Row and label of each include
+    includes: Vec<(usize, String)>,
 }
 
 fn preprocess(
     data: &mut [String],
     deps: &HashMap<String, String>,
+    dep_srcs: &HashMap<String, String>,
     images: &HashMap<String, String>,
     current_package: &str,
-) -> Vec<Box<dyn Display>> {
-    let mut problems: Vec<Box<dyn Display>> = Vec::new();
+) -> PreprocessResult {
+    let mut problems: Vec<Diagnostic> = Vec::new();
+    let mut includes = Vec::new();
     let mut used_deps = BTreeSet::new();
     let declared_deps = BTreeSet::from_iter(deps.keys().map(String::from));
     let mut used_images = BTreeSet::new();
     let declared_images = BTreeSet::from_iter(images.keys().map(String::from));
+    let image_refs = image_refs(data);
 
     for (row, line) in data.iter_mut().enumerate() {
-        let r = process_include(line, deps, current_package);
-        used_deps.extend(r.deps_used);
+        let r = process_include(line, deps, dep_srcs, current_package);
+        used_deps.extend(r.deps_used.iter().cloned());
         match r.result {
             Ok(new_line) => {
                 if let Some(new_line) = new_line {
                     // Since an include takes up a whole line, we don't need to
                     // check anything else if we found one.
                     *line = new_line;
+                    includes.extend(r.deps_used.into_iter().map(|d| (row, d)));
                     continue;
                 }
             }
             Err(ps) => {
                 problems.extend(
                     ps.into_iter()
-                        .map(|p| -> Box<dyn Display> { Box::new(RowProblem::new(row, &p)) }),
+                        .map(|p| RowProblem::new(row, &p).with_rule(INCLUDE_RULE).into()),
                 );
             }
         }
 
-        let r = process_images(line, images, current_package);
+        let r = process_images(line, images, &image_refs, current_package);
         used_images.extend(r.deps_used);
         match r.result {
             Ok(new_line) => {
@@ -228,38 +556,66 @@ fn preprocess(
             Err(ps) => {
                 problems.extend(
                     ps.into_iter()
-                        .map(|p| -> Box<dyn Display> { Box::new(p.add_row(row)) }),
+                        .map(|p| p.add_row(row).with_rule(IMAGE_RULE).into()),
                 );
             }
         }
     }
 
-    if let Err(problem) = check_strict_deps(&used_deps, &declared_deps, "deps") {
-        problems.push(Box::new(problem));
-    }
-
-    if let Err(problem) = check_strict_deps(&used_images, &declared_images, "images") {
-        problems.push(Box::new(problem));
-    }
+    problems.extend(check_strict_deps(&used_deps, &declared_deps, "deps"));
+    problems.extend(check_strict_deps(&used_images, &declared_images, "images"));
 
-    problems
+    PreprocessResult { problems, includes }
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Cli::parse();
 
-    let mut data: Vec<String> = read_to_string(args.in_file)?
+    let mut data: Vec<String> = read_to_string(&args.in_file)?
         .lines()
         .map(String::from)
         .collect();
 
     let deps: HashMap<String, String> =
         HashMap::from_iter(args.deps.into_iter().map(KeyValue::to_tuple));
+    let dep_srcs: HashMap<String, String> =
+        HashMap::from_iter(args.dep_srcs.into_iter().map(KeyValue::to_tuple));
     let images: HashMap<String, String> =
         HashMap::from_iter(args.images.into_iter().map(KeyValue::to_tuple));
 
-    let mut problems = Problems::new("markdown preprocessing failed");
-    problems.extend(preprocess(&mut data, &deps, &images, &args.current_package));
+    let mut problems = Problems::new("markdown preprocessing failed")
+        .with_file(&args.in_file)
+        .with_format(args.problems_format)
+        .with_warnings_as_errors(args.warnings_as_errors);
+    if let Some(report_file) = &args.report_file {
+        problems = problems.with_report_file(report_file);
+    }
+    let PreprocessResult {
+        problems: ps,
+        includes,
+    } = preprocess(&mut data, &deps, &dep_srcs, &images, &args.current_package);
+    problems.extend(ps);
+
+    if let Some(name) = &args.name {
+        let root = format!("{}:{name}", args.current_package);
+        let mut graph = IncludeGraph::default();
+        for path in args.dep_include_graphs {
+            graph.merge(from_json(&read_to_string(path)?)?);
+        }
+        graph.insert(
+            &root,
+            includes.iter().map(|(_, label)| label.clone()).collect(),
+        );
+
+        problems.extend(
+            check_include_graph(&root, &includes, &graph, args.max_include_depth)
+                .into_iter()
+                .map(|p| Diagnostic::from(p.with_rule(INCLUDE_GRAPH_RULE))),
+        );
+        if let Some(path) = args.include_graph_out_file {
+            graph.write_json(path)?;
+        }
+    }
     problems.check();
 
     write(args.out_file, data.join("\n") + "\n")?;
@@ -269,7 +625,8 @@ fn main() -> Result<(), Box<dyn Error>> {
 #[cfg(test)]
 mod test_preprocess {
     use super::{
-        BTreeSet, HashMap, HashSet, check_strict_deps, preprocess, process_images, process_include,
+        BTreeMap, HashMap, HashSet, IncludeGraph, IncludeOptions, Severity, check_include_graph,
+        check_strict_deps, image_refs, preprocess, process_images, process_include,
     };
 
     #[test]
@@ -278,36 +635,116 @@ mod test_preprocess {
             (String::from("foo:bar"), String::from("foo/bar.json")),
             (String::from("baz:quux"), String::from("baz/quux.json")),
         ]);
+        let srcs = HashMap::from([(String::from("foo:bar"), String::from("foo/bar.md"))]);
 
         // No include
-        let r = process_include("foo bar", &deps, "foo");
+        let r = process_include("foo bar", &deps, &srcs, "foo");
         assert!(r.result.unwrap().is_none());
         assert!(r.deps_used.is_empty());
 
         // Good include
-        let r = process_include("!include :bar", &deps, "foo");
+        let r = process_include("!include :bar", &deps, &srcs, "foo");
         assert_eq!(r.result.unwrap().unwrap(), "!include foo/bar.json");
         assert_eq!(r.deps_used, HashSet::from([String::from("foo:bar")]));
 
         // Good include with extra spaces
-        let r = process_include("!include     :bar", &deps, "foo");
+        let r = process_include("!include     :bar", &deps, &srcs, "foo");
         assert_eq!(r.result.unwrap().unwrap(), "!include foo/bar.json");
         assert_eq!(r.deps_used, HashSet::from([String::from("foo:bar")]));
 
         // Try to use unknown dependency
-        let r = process_include("!include :baz", &deps, "foo");
+        let r = process_include("!include :baz", &deps, &srcs, "foo");
         assert!(!r.result.unwrap_err().is_empty());
         assert_eq!(r.deps_used, HashSet::from([String::from("foo:baz")]));
 
         // Invalid include
-        let r = process_include("!include", &deps, "foo");
+        let r = process_include("!include", &deps, &srcs, "foo");
         assert!(!r.result.unwrap_err().is_empty());
         assert!(r.deps_used.is_empty());
 
         // Invalid label
-        let r = process_include("!include a:b:", &deps, "foo");
+        let r = process_include("!include a:b:", &deps, &srcs, "foo");
         assert!(!r.result.unwrap_err().is_empty());
         assert!(r.deps_used.is_empty());
+
+        // Good include with options, which are normalised
+        let r = process_include(
+            "!include //foo:bar  lines=10-40 shift=1   section=#chapter-3",
+            &deps,
+            &srcs,
+            "baz",
+        );
+        assert_eq!(
+            r.result.unwrap().unwrap(),
+            "!include foo/bar.md shift=1 section=#chapter-3 lines=10-40"
+        );
+        assert_eq!(r.deps_used, HashSet::from([String::from("foo:bar")]));
+
+        // Lines are selected from the source, so other options use the
+        // compiled dep
+        let r = process_include("!include :bar shift=1", &deps, &srcs, "foo");
+        assert_eq!(r.result.unwrap().unwrap(), "!include foo/bar.json shift=1");
+
+        // No source to select lines from
+        let r = process_include("!include //baz:quux lines=1-2", &deps, &srcs, "foo");
+        assert_eq!(r.result.unwrap_err().len(), 1);
+        assert_eq!(r.deps_used, HashSet::from([String::from("baz:quux")]));
+
+        // Bad options
+        let r = process_include("!include :bar shift=9 foo", &deps, &srcs, "foo");
+        assert_eq!(r.result.unwrap_err().len(), 2);
+        assert_eq!(r.deps_used, HashSet::from([String::from("foo:bar")]));
+
+        // Unknown dependency and bad options
+        let r = process_include("!include :baz lines=2-1", &deps, &srcs, "foo");
+        assert_eq!(r.result.unwrap_err().len(), 2);
+        assert_eq!(r.deps_used, HashSet::from([String::from("foo:baz")]));
+    }
+
+    #[test]
+    fn test_include_options() {
+        assert_eq!(
+            IncludeOptions::parse("").unwrap(),
+            IncludeOptions::default()
+        );
+        assert_eq!(
+            IncludeOptions::parse("shift=0 section=#foo.bar:baz_1 lines=1-1").unwrap(),
+            IncludeOptions {
+                shift: Some(0),
+                section: Some(String::from("#foo.bar:baz_1")),
+                lines: Some((1, 1)),
+            }
+        );
+        assert_eq!(
+            IncludeOptions::parse("lines=3-4 shift=5")
+                .unwrap()
+                .to_string(),
+            " shift=5 lines=3-4"
+        );
+
+        for bad in [
+            "shift",
+            "shift=",
+            "shift=6",
+            "shift=-1",
+            "shift=a",
+            "section=foo",
+            "section=#",
+            "section=#foo bar",
+            "lines=0-1",
+            "lines=2-1",
+            "lines=1",
+            "lines=1-",
+            "lines=a-b",
+            "shift=1 shift=2",
+            "foo=bar",
+        ] {
+            assert!(IncludeOptions::parse(bad).is_err(), "{bad}");
+        }
+        assert_eq!(
+            IncludeOptions::parse("shift=1 shift=2").unwrap_err(),
+            ["Include option 'shift' is set more than once"]
+        );
     }
 
     #[test]
@@ -319,19 +756,25 @@ mod test_preprocess {
                 String::from("baz/quux/quux.png"),
             ),
         ]);
+        let refs = HashSet::new();
 
         // No images
-        let r = process_images("Foo bar baz quux [link](foo)", &images, "foo");
+        let r = process_images("Foo bar baz quux [link](foo)", &images, &refs, "foo");
         assert!(r.result.unwrap().is_none());
         assert!(r.deps_used.is_empty());
 
         // One image
-        let r = process_images("Foo ![bar](//foo:bar)", &images, "foo");
+        let r = process_images("Foo ![bar](//foo:bar)", &images, &refs, "foo");
         assert_eq!(r.result.unwrap().unwrap(), "Foo ![bar](foo/bar.jpg)");
         assert_eq!(r.deps_used, HashSet::from([String::from("foo:bar")]));
 
         // One image, title and attributes
-        let r = process_images("Foo ![bar](//foo:bar \"baz\\quux\"){.quux}", &images, "foo");
+        let r = process_images(
+            "Foo ![bar](//foo:bar \"baz\\quux\"){.quux}",
+            &images,
+            &refs,
+            "foo",
+        );
         assert_eq!(
             r.result.unwrap().unwrap(),
             "Foo ![bar](foo/bar.jpg \"baz\\quux\"){.quux}"
@@ -342,6 +785,7 @@ mod test_preprocess {
         let r = process_images(
             "Foo ![bar](:bar) bar ![quux](//baz/quux) baz ![bar](:bar) ![bar](:bar \"baz\"){.quux}",
             &images,
+            &refs,
             "foo",
         );
         assert_eq!(
@@ -355,7 +799,7 @@ mod test_preprocess {
         );
 
         // Try to use unknown image
-        let r = process_images("Foo ![bar](:bar) bar ![quux](:quux)", &images, "foo");
+        let r = process_images("Foo ![bar](:bar) bar ![quux](:quux)", &images, &refs, "foo");
         assert_eq!(r.result.clone().unwrap_err().len(), 1);
         assert_eq!(r.result.unwrap_err()[0].col(), 29);
         assert_eq!(
@@ -364,52 +808,123 @@ mod test_preprocess {
         );
 
         // Invalid label
-        let r = process_images("Foo ![bar](:bar:)", &images, "foo");
+        let r = process_images("Foo ![bar](:bar:)", &images, &refs, "foo");
         assert_eq!(r.result.clone().unwrap_err().len(), 1);
         assert_eq!(r.result.unwrap_err()[0].col(), 11);
         assert!(r.deps_used.is_empty());
+
+        // Linked image
+        let r = process_images(
+            "Foo [![bar](//foo:bar \"baz\")](https://example.com)",
+            &images,
+            &refs,
+            "foo",
+        );
+        assert_eq!(
+            r.result.unwrap().unwrap(),
+            "Foo [![bar](foo/bar.jpg \"baz\")](https://example.com)"
+        );
+        assert_eq!(r.deps_used, HashSet::from([String::from("foo:bar")]));
+
+        // HTML images
+        let r = process_images(
+            "Foo <img alt=\"bar\" src=\"//foo:bar\"> <IMG SRC = ':quux' width=\"10\"/>",
+            &images,
+            &refs,
+            "baz/quux",
+        );
+        assert_eq!(
+            r.result.unwrap().unwrap(),
+            "Foo <img alt=\"bar\" src=\"foo/bar.jpg\"> <IMG SRC = 'baz/quux/quux.png' width=\"10\"/>"
+        );
+        assert_eq!(
+            r.deps_used,
+            HashSet::from([String::from("foo:bar"), String::from("baz/quux:quux")])
+        );
+
+        let r = process_images("Foo <img src=\":baz\">", &images, &refs, "foo");
+        assert_eq!(r.result.clone().unwrap_err().len(), 1);
+        assert_eq!(r.result.unwrap_err()[0].col(), 14);
+        assert_eq!(r.deps_used, HashSet::from([String::from("foo:baz")]));
+
+        // Reference definitions are only images if an image uses them
+        let refs = HashSet::from([String::from("bar baz")]);
+        let r = process_images("[Bar  Baz]: //foo:bar \"quux\"", &images, &refs, "foo");
+        assert_eq!(
+            r.result.unwrap().unwrap(),
+            "[Bar  Baz]: foo/bar.jpg \"quux\""
+        );
+        assert_eq!(r.deps_used, HashSet::from([String::from("foo:bar")]));
+
+        let r = process_images("[quux]: https://example.com", &images, &refs, "foo");
+        assert!(r.result.unwrap().is_none());
+        assert!(r.deps_used.is_empty());
+
+        let r = process_images("   [bar baz]:   :quux", &images, &refs, "foo");
+        assert_eq!(r.result.clone().unwrap_err().len(), 1);
+        assert_eq!(r.result.unwrap_err()[0].col(), 16);
+    }
+
+    #[test]
+    fn test_image_refs() {
+        let data = [
+            "![foo][Bar] ![Baz][] ![Quux  Quux] ![inline](//foo:bar)",
+            "[![linked][ref]](https://example.com) [link][notimage]",
+        ]
+        .map(String::from);
+        assert_eq!(
+            image_refs(&data),
+            HashSet::from(["bar", "baz", "quux quux", "ref"].map(String::from))
+        );
     }
 
     #[test]
     fn test_check_strict_deps() {
-        // OK
-        assert!(
+        fn check(used: &[&str], declared: &[&str]) -> Vec<(Severity, String)> {
             check_strict_deps(
-                &BTreeSet::from([String::from("a"), String::from("b")]),
-                &BTreeSet::from([String::from("a"), String::from("b")]),
+                &used.iter().map(|d| String::from(*d)).collect(),
+                &declared.iter().map(|d| String::from(*d)).collect(),
                 "foo",
             )
-            .is_ok()
-        );
+            .iter()
+            .map(|p| (p.severity(), p.to_string()))
+            .collect()
+        }
+
+        // OK
+        assert!(check(&["a", "b"], &["a", "b"]).is_empty());
 
         // Used but not declared
-        assert!(
-            check_strict_deps(
-                &BTreeSet::from([String::from("a"), String::from("b")]),
-                &BTreeSet::from([String::from("a")]),
-                "foo",
-            )
-            .is_err()
+        assert_eq!(
+            check(&["a", "b"], &["a"]),
+            [(
+                Severity::Error,
+                String::from("Used foo not declared in foo\n  //b")
+            )]
         );
 
         // Declared but not used
-        assert!(
-            check_strict_deps(
-                &BTreeSet::from([String::from("a")]),
-                &BTreeSet::from([String::from("a"), String::from("b")]),
-                "foo",
-            )
-            .is_err()
+        assert_eq!(
+            check(&["a"], &["a", "b"]),
+            [(
+                Severity::Warning,
+                String::from("Declared foo not used\n  //b")
+            )]
         );
 
         // Both
-        assert!(
-            check_strict_deps(
-                &BTreeSet::from([String::from("a"), String::from("c")]),
-                &BTreeSet::from([String::from("a"), String::from("d")]),
-                "foo",
-            )
-            .is_err()
+        assert_eq!(
+            check(&["a", "c"], &["a", "d"]),
+            [
+                (
+                    Severity::Error,
+                    String::from("Used foo not declared in foo\n  //c")
+                ),
+                (
+                    Severity::Warning,
+                    String::from("Declared foo not used\n  //d")
+                ),
+            ]
         );
     }
 
@@ -442,8 +957,12 @@ An image ![foo]({} \"bar\"){.baz} goes here.
                 .split("\n")
                 .map(String::from),
         );
-        let problems = preprocess(&mut data, &deps, &images, "a");
-        assert!(problems.is_empty());
+        let r = preprocess(&mut data, &deps, &HashMap::new(), &images, "a");
+        assert!(r.problems.is_empty());
+        assert_eq!(
+            r.includes,
+            [(2, String::from("foo:bar")), (4, String::from("baz:quux"))]
+        );
         assert_eq!(
             data.join("\n"),
             make_data("foo/bar.json", "baz/quux.json", "a/yay.jpg")
@@ -455,11 +974,179 @@ An image ![foo]({} \"bar\"){.baz} goes here.
                 .split("\n")
                 .map(String::from),
         );
-        let problems = preprocess(&mut data, &deps, &images, "a");
-        assert_eq!(problems.len(), 4);
+        let r = preprocess(&mut data, &deps, &HashMap::new(), &images, "a");
+        assert_eq!(
+            r.problems
+                .iter()
+                .map(|p| (p.rule(), p.severity()))
+                .collect::<Vec<_>>(),
+            [
+                (Some("include"), Severity::Error),
+                (Some("image"), Severity::Error),
+                (Some("strict-deps"), Severity::Error),
+                (Some("strict-deps"), Severity::Warning),
+                (Some("strict-deps"), Severity::Error),
+                (Some("strict-deps"), Severity::Warning),
+            ]
+        );
+        assert_eq!(r.includes, [(2, String::from("foo:bar"))]);
         assert_eq!(
             data.join("\n"),
             make_data("foo/bar.json", "//blah:yay", "//baz:quux")
         );
     }
+
+    #[test]
+    fn test_preprocess_image_forms() {
+        let images = HashMap::from([
+            (String::from("a:foo"), String::from("a/foo.jpg")),
+            (String::from("a:bar"), String::from("a/bar.jpg")),
+            (String::from("a:baz"), String::from("a/baz.jpg")),
+        ]);
+
+        let mut data: Vec<String> = Vec::from_iter(
+            "![Foo][foo] and [a link][quux].
+
+<img src=\"//a:bar\"> [![baz](:baz)](https://example.com)
+
+[foo]: :foo
+[quux]: https://example.com"
+                .split("\n")
+                .map(String::from),
+        );
+        let r = preprocess(&mut data, &HashMap::new(), &HashMap::new(), &images, "a");
+        assert!(r.problems.is_empty());
+        assert_eq!(
+            data.join("\n"),
+            "![Foo][foo] and [a link][quux].
+
+<img src=\"a/bar.jpg\"> [![baz](a/baz.jpg)](https://example.com)
+
+[foo]: a/foo.jpg
+[quux]: https://example.com"
+        );
+
+        // Images used only through references still count as used
+        let mut data = vec![String::from("![Foo][foo]"), String::from("[foo]: :foo")];
+        let r = preprocess(&mut data, &HashMap::new(), &HashMap::new(), &images, "a");
+        assert_eq!(r.problems.len(), 1);
+        assert_eq!(r.problems[0].severity(), Severity::Warning);
+        assert_eq!(
+            r.problems[0].to_string(),
+            "Declared images not used
+  //a:bar
+  //a:baz"
+        );
+    }
+
+    fn graph(edges: &[(&str, &[&str])]) -> IncludeGraph {
+        IncludeGraph::build(BTreeMap::from_iter(edges.iter().map(|(from, to)| {
+            (
+                String::from(*from),
+                to.iter().map(|t| String::from(*t)).collect(),
+            )
+        })))
+    }
+
+    fn includes(labels: &[(usize, &str)]) -> Vec<(usize, String)> {
+        labels.iter().map(|(r, l)| (*r, String::from(*l))).collect()
+    }
+
+    fn messages(problems: Vec<super::RowProblem>) -> Vec<String> {
+        problems.iter().map(|p| p.to_string()).collect()
+    }
+
+    #[test]
+    fn test_check_include_graph_ok() {
+        let g = graph(&[
+            ("r:a", &["r:b"]),
+            ("r:b", &["r:c"]),
+            ("r:c", &[]),
+            ("r:d", &[]),
+        ]);
+        assert!(
+            check_include_graph("r:root", &includes(&[(0, "r:a"), (2, "r:d")]), &g, Some(3))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_check_include_graph_diamond() {
+        let g = graph(&[("r:a", &["r:c"]), ("r:b", &["r:c"]), ("r:c", &[])]);
+        assert_eq!(
+            messages(check_include_graph(
+                "r:root",
+                &includes(&[(0, "r:a"), (2, "r:b")]),
+                &g,
+                None
+            )),
+            ["row 3: r:c is included 2 times: r:root -> r:a -> r:c, r:root -> r:b -> r:c"]
+        );
+
+        // Included directly twice
+        assert_eq!(
+            messages(check_include_graph(
+                "r:root",
+                &includes(&[(0, "r:c"), (4, "r:c")]),
+                &g,
+                None
+            )),
+            ["row 5: r:c is included 2 times: r:root -> r:c, r:root -> r:c"]
+        );
+    }
+
+    #[test]
+    fn test_check_include_graph_cycle() {
+        let g = graph(&[("r:a", &["r:b"]), ("r:b", &["r:a"])]);
+        assert_eq!(
+            messages(check_include_graph(
+                "r:root",
+                &includes(&[(1, "r:a")]),
+                &g,
+                None
+            )),
+            ["row 2: Include cycle: r:a -> r:b -> r:a"]
+        );
+
+        let g = graph(&[("r:a", &["r:root"])]);
+        assert_eq!(
+            messages(check_include_graph(
+                "r:root",
+                &includes(&[(1, "r:a")]),
+                &g,
+                None
+            )),
+            ["row 2: Include cycle: r:root -> r:a -> r:root"]
+        );
+
+        assert_eq!(
+            messages(check_include_graph(
+                "r:root",
+                &includes(&[(1, "r:root")]),
+                &g,
+                None
+            )),
+            ["row 2: Include cycle: r:root -> r:root"]
+        );
+    }
+
+    #[test]
+    fn test_check_include_graph_depth() {
+        let g = graph(&[
+            ("r:a", &["r:b"]),
+            ("r:b", &["r:c"]),
+            ("r:c", &[]),
+            ("r:d", &[]),
+        ]);
+        let i = includes(&[(1, "r:a"), (3, "r:d")]);
+        assert!(check_include_graph("r:root", &i, &g, None).is_empty());
+        assert!(check_include_graph("r:root", &i, &g, Some(3)).is_empty());
+        assert_eq!(
+            messages(check_include_graph("r:root", &i, &g, Some(2))),
+            [
+                "row 2: Includes are nested 3 deep, more than the maximum of 2: r:root -> r:a -> r:b \
+-> r:c"
+            ]
+        );
+    }
 }
@@ -2,6 +2,7 @@ import os
 import os.path
 import subprocess
 from collections.abc import Sequence
+from typing import Any
 
 from markdown.private.utils import test_utils
 
@@ -66,10 +67,116 @@ class TestPreprocess(test_utils.ScriptTestCase):
         )
         self.assertEqual(output, GOOD % ("bar.json", "baz/quux.json", "foo.jpg"))
 
+    def test_image_forms(self) -> None:
+        output = self.run_script(
+            '![foo][ref] <img src="//a:bar"> [![baz](:baz)](https://example.com)\n\n[ref]: :foo\n',
+            "a",
+            [],
+            [("a:foo", "a/foo.jpg"), ("a:bar", "a/bar.jpg"), ("a:baz", "a/baz.jpg")],
+        )
+        self.assertEqual(
+            output,
+            '![foo][ref] <img src="a/bar.jpg"> [![baz](a/baz.jpg)](https://example.com)\n\n'
+            "[ref]: a/foo.jpg\n",
+        )
+
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script("![foo][ref]\n\n[ref]: :foo\n", "a", [], [])
+
     def test_main_fails(self) -> None:
         with self.assertRaises(subprocess.CalledProcessError):
             self.run_script("!include", "a", [], [])
 
+    def run_warnings(self, *extra_args: str) -> str:
+        in_file = os.path.join(self.tmpdir(), "in.md")
+        self.dump_file(in_file, "Foo.\n")
+        out_file = os.path.join(self.tmpdir(), "out.md")
+        report_file = os.path.join(self.tmpdir(), "report.txt")
+
+        super().run_script(
+            args=[
+                in_file,
+                out_file,
+                "a",
+                "--dep=a:b=a/b.json",
+                "--report-file=" + report_file,
+                *extra_args,
+            ],
+        )
+
+        return self.load_file(report_file)
+
+    def test_warnings(self) -> None:
+        self.assertEqual(self.run_warnings(), "WARNING: Declared deps not used\n  //a:b\n")
+
+        with self.assertRaises(subprocess.CalledProcessError) as e:
+            self.run_warnings("--warnings-as-errors")
+        self.assertIn("Declared deps not used", e.exception.stderr)
+
+    def run_include_graph(
+        self, content: str, dep_graphs: Sequence[dict[str, list[str]]]
+    ) -> dict[str, Any]:
+        in_file = os.path.join(self.tmpdir(), "in.md")
+        self.dump_file(in_file, content)
+        out_file = os.path.join(self.tmpdir(), "out.md")
+        graph_file = os.path.join(self.tmpdir(), "graph.json")
+
+        args = [
+            in_file,
+            out_file,
+            "a",
+            "--name=root",
+            "--include-graph-out-file=" + graph_file,
+            "--max-include-depth=2",
+            "--dep=a:b=a/b.json",
+            "--dep=a:c=a/c.json",
+        ]
+        for i, g in enumerate(dep_graphs):
+            dep_graph_file = os.path.join(self.tmpdir(), f"dep_graph{i}.json")
+            self.dump_json(dep_graph_file, g)
+            args.append("--dep-include-graph=" + dep_graph_file)
+
+        super().run_script(args=args)
+
+        return self.load_json(graph_file)
+
+    def test_include_graph(self) -> None:
+        graph = self.run_include_graph(
+            "!include :b\n\n!include :c\n",
+            [{"a:b": ["a:d"], "a:d": []}, {"a:c": []}],
+        )
+        self.assertEqual(
+            graph,
+            {
+                "a:b": ["a:d"],
+                "a:c": [],
+                "a:d": [],
+                "a:root": ["a:b", "a:c"],
+            },
+        )
+
+    def test_include_graph_fails(self) -> None:
+        # Included twice
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_include_graph(
+                "!include :b\n\n!include :c\n",
+                [{"a:b": ["a:d"], "a:d": []}, {"a:c": ["a:d"], "a:d": []}],
+            )
+
+        # Cycle
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_include_graph(
+                "!include :b\n\n!include :c\n",
+                [{"a:b": ["a:root"]}, {"a:c": []}],
+            )
+
+        # Too deep
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_include_graph(
+                "!include :b\n\n!include :c\n",
+                [{"a:b": ["a:d"], "a:d": ["a:e"], "a:e": []}, {"a:c": []}],
+            )
+
 
 if __name__ == "__main__":
     test_utils.ScriptTestCase.main()
@@ -1,12 +1,14 @@
 use clap::{Args, Parser};
 use markdown::args;
 use markdown::json::{JsonSerializable, from_json};
-use markdown::metadata::Version;
+use markdown::metadata::{OverrideSource, Version, VersionInfo};
 use std::error::Error;
 use std::fs::read_to_string;
 
 const REPO_KEY: &str = "STABLE_WORKSPACE_PARENT_REPO";
 const VERSION_KEY: &str = "STABLE_WORKSPACE_PARENT_VERSION";
+const DIRTY_SUFFIX: &str = "-dirty";
+const UNVERSIONED: &str = "unversioned";
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -30,8 +32,41 @@ struct InFile {
     info_file: Option<String>,
 }
 
+// Versions are written by git_repo_version.py, as
+// "<commit>[-dirty], <timestamp>", or "unversioned".
+fn parse_version(version: &str, repo: &str) -> Result<Version, Box<dyn Error>> {
+    let info = if version == UNVERSIONED {
+        VersionInfo {
+            unversioned: true,
+            ..Default::default()
+        }
+    } else {
+        let commit = version.split_once(", ").map_or(version, |(c, _)| c);
+        let (commit, dirty) = match commit.strip_suffix(DIRTY_SUFFIX) {
+            Some(commit) => (commit, true),
+            None => (commit, false),
+        };
+        VersionInfo {
+            commit: Some(String::from(commit)),
+            dirty,
+            ..Default::default()
+        }
+    };
+    Ok(Version::build_with_info(version, repo, info)?)
+}
+
 fn from_version_file(path: &str) -> Result<Version, Box<dyn Error>> {
     let version: Version = from_json(&read_to_string(path)?)?;
+    // Version files without state are as written by git_repo_version.py
+    let mut version = if version.version_info == VersionInfo::default() {
+        parse_version(&version.version, &version.repo)?
+    } else {
+        version
+    };
+    version
+        .version_info
+        .override_source
+        .get_or_insert(OverrideSource::VersionFile);
     Ok(version)
 }
 
@@ -49,10 +84,10 @@ fn from_info_file(path: &str) -> Result<Version, Box<dyn Error>> {
     }
 
     if let (Some(version), Some(repo)) = (version, repo) {
-        return Ok(Version::build(version, repo)?);
+        return parse_version(version, repo);
     }
 
-    Ok(Version::build("unversioned", "unversioned")?)
+    parse_version(UNVERSIONED, UNVERSIONED)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -36,7 +36,27 @@ class TestRawVersion(test_utils.ScriptTestCase):
                 },
                 "",
             ),
-            {"repo": "a/b", "version": "1"},
+            {
+                "repo": "a/b",
+                "version": "1",
+                "version-info": {"commit": "1", "override-source": "version-file"},
+            },
+        )
+
+    def test_version_file_dirty(self) -> None:
+        self.assertEqual(
+            self.run_script(
+                {
+                    "version": "1-dirty, 2024-01-01 00:00:00+00:00",
+                    "repo": "a/b",
+                },
+                "",
+            ),
+            {
+                "repo": "a/b",
+                "version": "1-dirty, 2024-01-01 00:00:00+00:00",
+                "version-info": {"commit": "1", "dirty": True, "override-source": "version-file"},
+            },
         )
 
     def test_info_file_present(self) -> None:
@@ -48,7 +68,23 @@ STABLE_WORKSPACE_PARENT_REPO a/b
 STABLE_WORKSPACE_PARENT_VERSION 1
 """,
             ),
-            {"repo": "a/b", "version": "1"},
+            {"repo": "a/b", "version": "1", "version-info": {"commit": "1"}},
+        )
+
+    def test_info_file_dirty_tag(self) -> None:
+        self.assertEqual(
+            self.run_script(
+                {},
+                """
+STABLE_WORKSPACE_PARENT_REPO a/b
+STABLE_WORKSPACE_PARENT_VERSION dirty-laundry-0-g1234567, 2024-01-01 00:00:00+00:00
+""",
+            ),
+            {
+                "repo": "a/b",
+                "version": "dirty-laundry-0-g1234567, 2024-01-01 00:00:00+00:00",
+                "version-info": {"commit": "dirty-laundry-0-g1234567"},
+            },
         )
 
     def test_info_file_missing(self) -> None:
@@ -59,7 +95,11 @@ STABLE_WORKSPACE_PARENT_VERSION 1
 FOO 1
 """,
             ),
-            {"repo": "unversioned", "version": "unversioned"},
+            {
+                "repo": "unversioned",
+                "version": "unversioned",
+                "version-info": {"unversioned": True},
+            },
         )
 
     def test_neither(self) -> None:
@@ -1,10 +1,11 @@
 use clap::Parser;
-use markdown::args;
+use markdown::args::{self, KeyValue};
+use markdown::hashing::{DepHash, source_hash};
 use markdown::json::{JsonSerializable, from_json};
-use markdown::metadata::{MetadataMap, SourceHash};
+use markdown::metadata::{HashAlgorithm, MetadataMap};
 use std::collections::BTreeMap;
 use std::error::Error;
-use std::fs::read_to_string;
+use std::fs::{read, read_to_string};
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -17,6 +18,26 @@ struct Cli {
 
     #[arg(value_parser = args::non_empty())]
     out_file: String,
+
+    /// Other file the document depends on, such as an image or lint config,
+    /// as name=path; the name is hashed, so it should be stable
+    #[arg(long = "input")]
+    inputs: Vec<KeyValue>,
+
+    #[arg(long, value_enum, default_value_t = HashAlgorithm::default())]
+    algorithm: HashAlgorithm,
+
+    /// Path of src_file relative to the workspace root, recorded so the hash
+    /// can be recomputed from the working tree; defaults to src_file
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    src_name: Option<String>,
+
+    /// Name of the src or an input that isn't a source file in the
+    /// workspace, such as a generated file, so can't be reread from the
+    /// working tree
+    #[arg(long)]
+    generated: Vec<String>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -27,23 +48,29 @@ fn main() -> Result<(), Box<dyn Error>> {
         dep_metadata
             .data()
             .iter()
-            .map(|(target, metadata)| (target, metadata.source_hash())),
+            .map(|(target, metadata)| (target.clone(), DepHash::from(metadata))),
     );
 
-    let src = read_to_string(args.src_file)?;
-
-    let mut hash_input = vec![String::from("{")];
-    hash_input.extend(
-        dep_hashes
-            .iter()
-            .map(|(target, source_hash)| format!("{target} {source_hash}")),
-    );
-    hash_input.push(String::from("}"));
-    hash_input.push(src);
+    let src = read_to_string(&args.src_file)?;
+    let mut inputs = BTreeMap::new();
+    for input in args.inputs {
+        let contents = read(input.value())?;
+        if inputs.insert(String::from(input.key()), contents).is_some() {
+            return Err(format!("input '{}' given more than once", input.key()).into());
+        }
+    }
 
-    // We use md5 because the PDF trailer ID must be a 16-byte hex string -
-    // which is the size of md5.
-    let hash_output = format!("{:x}", md5::compute(hash_input.join("\n")));
+    let src_name = args.src_name.unwrap_or(args.src_file);
+    for name in &args.generated {
+        if *name != src_name && !inputs.contains_key(name) {
+            return Err(format!("generated file '{name}' is neither the src nor an input").into());
+        }
+    }
 
-    SourceHash::build(&hash_output)?.write_json(args.out_file)
+    let mut hash = source_hash(&dep_hashes, &src, &inputs, args.algorithm)?;
+    hash.hash_src = Some(src_name);
+    hash.hash_generated = args.generated;
+    hash.hash_generated.sort();
+    hash.hash_generated.dedup();
+    hash.write_json(args.out_file)
 }
@@ -1,16 +1,38 @@
 import os
 import os.path
-from collections.abc import Mapping
+from collections.abc import Mapping, Sequence
 from typing import Any
 
 from markdown.private.utils import test_utils
 
+DEPS_METADATA = {
+    "dep1": {
+        "wordcount": "10",
+        "poetry-lines": "0",
+        "lang": "en-GB",
+        "version": "foo",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": [],
+    },
+    "dep2": {
+        "wordcount": "20",
+        "poetry-lines": "10",
+        "lang": "en-US",
+        "version": "blah",
+        "repo": "yay",
+        "source-hash": "3",
+        "parsed-dates": [],
+    },
+}
+
 
 class TestSourceHash(test_utils.ScriptTestCase):
     def run_script(  # type: ignore[override]
         self,
         src: str,
         deps_metadata: Mapping[str, Mapping[str, str | list[str]]],
+        args: Sequence[str] = (),
     ) -> dict[str, Any]:
         src_file = os.path.join(self.tmpdir(), "src.md")
         self.dump_file(src_file, src)
@@ -22,6 +44,7 @@ class TestSourceHash(test_utils.ScriptTestCase):
 
         super().run_script(
             args=[
+                *args,
                 src_file,
                 deps_metadata_file,
                 out_file,
@@ -31,34 +54,49 @@ class TestSourceHash(test_utils.ScriptTestCase):
         return self.load_json(out_file)
 
     def test_script(self) -> None:
-        metadata_out = self.run_script(
-            "foo bar\n",
+        metadata_out = self.run_script("foo bar\n", DEPS_METADATA)
+
+        self.assertEqual(
+            metadata_out,
             {
-                "dep1": {
-                    "wordcount": "10",
-                    "poetry-lines": "0",
-                    "lang": "en-GB",
-                    "version": "foo",
-                    "repo": "bar",
-                    "source-hash": "1",
-                    "parsed-dates": [],
-                },
-                "dep2": {
-                    "wordcount": "20",
-                    "poetry-lines": "10",
-                    "lang": "en-US",
-                    "version": "blah",
-                    "repo": "yay",
-                    "source-hash": "3",
-                    "parsed-dates": [],
+                "source-hash": "f3fa81ca663a1ae5a7e971de9f1b8a60",
+                "content-hash": {
+                    "algorithm": "sha256",
+                    "digest": "38e2180846953a64b6c89d7bf13830f2c7899b77e78424e53562748447d59b4a",  # noqa: E501
                 },
+                "hash-src": os.path.join(self.tmpdir(), "src.md"),
+                "hash-deps": ["dep1", "dep2"],
             },
         )
 
+    def test_inputs(self) -> None:
+        image = os.path.join(self.tmpdir(), "img.jpg")
+        self.dump_file(image, "img")
+
+        metadata_out = self.run_script(
+            "foo bar\n",
+            DEPS_METADATA,
+            [
+                "--input",
+                "a/img.jpg=" + image,
+                "--algorithm=blake3",
+                "--src-name=a/doc.md",
+                "--generated=a/img.jpg",
+            ],
+        )
+
         self.assertEqual(
             metadata_out,
             {
-                "source-hash": "f3fa81ca663a1ae5a7e971de9f1b8a60",
+                "source-hash": "ed8d51be90d5bbac9a49697e80feef25",
+                "content-hash": {
+                    "algorithm": "blake3",
+                    "digest": "21f709982629cd6307222045ef5da0926935166241dfd246815d39e39c3d8951",  # noqa: E501
+                },
+                "hash-src": "a/doc.md",
+                "hash-deps": ["dep1", "dep2"],
+                "hash-inputs": ["a/img.jpg"],
+                "hash-generated": ["a/img.jpg"],
             },
         )
 
@@ -2,8 +2,12 @@ use clap::Parser;
 use markdown::args;
 use markdown::json::{JsonSerializable, from_json};
 use markdown::metadata::InputMetadata;
+use markdown::problems::Problems;
+use markdown::validation;
+use markdown::venues::VenueRegistry;
 use std::error::Error;
 use std::fs::read_to_string;
+use validator::Validate;
 
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
@@ -13,10 +17,36 @@ struct Cli {
 
     #[arg(value_parser = args::non_empty())]
     out_file: String,
+
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    venues_file: Option<String>,
+
+    /// Markdown source the metadata came from, used to report the positions
+    /// of invalid fields in its front matter
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    source_file: Option<String>,
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Cli::parse();
-    let metadata: InputMetadata = from_json(&read_to_string(args.in_file)?)?;
+    let mut metadata: InputMetadata = serde_json::from_str(&read_to_string(args.in_file)?)?;
+    if let Err(errors) = metadata.validate() {
+        let source = match &args.source_file {
+            Some(path) => Some(read_to_string(path)?),
+            None => None,
+        };
+        let mut problems = Problems::new("invalid metadata");
+        if let Some(path) = &args.source_file {
+            problems = problems.with_file(path);
+        }
+        problems.extend(validation::problems(&errors, source.as_deref()));
+        problems.check();
+    }
+    if let Some(path) = args.venues_file {
+        let venues: VenueRegistry = from_json(&read_to_string(path)?)?;
+        metadata = metadata.canonicalise_venues(&venues)?;
+    }
     metadata.write_json(args.out_file)
 }
@@ -1,7 +1,8 @@
+import json
 import os
 import os.path
 import subprocess
-from collections.abc import Mapping
+from collections.abc import Mapping, Sequence
 from typing import Any
 
 from markdown.private.utils import test_utils
@@ -56,18 +57,26 @@ class TestValidate(test_utils.ScriptTestCase):
     def run_script(  # type: ignore[override]
         self,
         content: Mapping[str, Any],
+        venues: Sequence[Mapping[str, Any]] | None = None,
+        source: str | None = None,
     ) -> dict[str, Any]:
         in_file = os.path.join(self.tmpdir(), "in.json")
         self.dump_json(in_file, content)
 
         out_file = os.path.join(self.tmpdir(), "out.json")
 
-        super().run_script(
-            args=[
-                in_file,
-                out_file,
-            ],
-        )
+        args = [in_file, out_file]
+        if venues is not None:
+            venues_file = os.path.join(self.tmpdir(), "venues.json")
+            with open(venues_file, "w", encoding="utf-8") as f:
+                json.dump(venues, f)
+            args += ["--venues-file", venues_file]
+        if source is not None:
+            source_file = os.path.join(self.tmpdir(), "source.md")
+            self.dump_file(source_file, source)
+            args += ["--source-file", source_file]
+
+        super().run_script(args=args)
 
         return self.load_json(out_file)
 
@@ -124,6 +133,75 @@ class TestValidate(test_utils.ScriptTestCase):
             {},
         )
 
+    def test_validate_venues(self) -> None:
+        content = {
+            "publications": [
+                {"venue": "Foo Magazine", "submitted": "2022-12-01"},
+                {"venue": "Bar", "submitted": "2022-12-02"},
+            ],
+        }
+        venues = [
+            {"name": "Foo", "aliases": ["Foo Magazine"]},
+            {"name": "Bar", "url": "http://example.com"},
+        ]
+
+        self.assertEqual(
+            self.run_script(content, venues),
+            {
+                "publications": [
+                    {"venue": "Foo", "submitted": "2022-12-01"},
+                    {"venue": "Bar", "submitted": "2022-12-02"},
+                ],
+            },
+        )
+
+        # Unknown venue
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(content, venues[1:])
+
+        # Duplicate venue name
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(content, [*venues, {"name": "Foo Magazine"}])
+
+    def test_validate_payment(self) -> None:
+        content = {
+            "publications": [
+                {
+                    "venue": "Foo",
+                    "submitted": "2022-12-01",
+                    "accepted": "2022-12-02",
+                    "paid": {"amount": 10, "currency": "GBP", "status": "pending"},
+                },
+            ],
+        }
+        self.assertEqual(
+            self.run_script(content),
+            {
+                "publications": [
+                    {
+                        "venue": "Foo",
+                        "submitted": "2022-12-01",
+                        "accepted": "2022-12-02",
+                        "paid": {"amount": 10.0, "currency": "GBP", "status": "pending"},
+                    },
+                ],
+            },
+        )
+
+        # Structured payments need an acceptance
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(
+                {
+                    "publications": [
+                        {
+                            "venue": "Foo",
+                            "submitted": "2022-12-01",
+                            "paid": {"amount": 10, "currency": "GBP", "status": "pending"},
+                        },
+                    ],
+                },
+            )
+
     def test_validate_fails(self) -> None:
         # Unknown key
         with self.assertRaises(subprocess.CalledProcessError):
@@ -365,6 +443,55 @@ class TestValidate(test_utils.ScriptTestCase):
                 {"finished": "foo"},
             )
 
+    def test_validate_fails_with_positions(self) -> None:
+        content = {
+            "title": "Foo",
+            "publications": [
+                {"venue": "Foo", "submitted": "2022-12-01"},
+                {"venue": "Bar", "submitted": "2022-12-03", "accepted": "2022-12-02"},
+            ],
+        }
+        source = """---
+title: Foo
+publications:
+  - venue: Foo
+    submitted: 2022-12-01
+  - venue: Bar
+    submitted: 2022-12-03
+    accepted: 2022-12-02
+---
+
+Text
+"""
+
+        try:
+            self.run_script(content, source=source)
+            self.fail()
+        except subprocess.CalledProcessError as e:
+            self.assertEqual(
+                e.stderr,
+                """ERROR: invalid metadata
+
+  row 6 col 3: publications[1]: dates must be in increasing order (suggested fix: check each date; they must follow the order submitted, shortlisted, accepted, contracted, then the end date)
+
+
+""",
+            )
+
+        try:
+            self.run_script(content)
+            self.fail()
+        except subprocess.CalledProcessError as e:
+            self.assertEqual(
+                e.stderr,
+                """ERROR: invalid metadata
+
+  publications[1]: dates must be in increasing order (suggested fix: check each date; they must follow the order submitted, shortlisted, accepted, contracted, then the end date)
+
+
+""",
+            )
+
 
 if __name__ == "__main__":
     test_utils.ScriptTestCase.main()
@@ -1,8 +1,8 @@
 use clap::Parser;
 use markdown::args;
 use markdown::json::{JsonSerializable, from_json};
-use markdown::metadata::{MetadataMap, Version};
-use std::collections::BTreeMap;
+use markdown::metadata::{MetadataMap, OverrideSource, Version, VersionInfo};
+use std::collections::{BTreeMap, BTreeSet};
 use std::error::Error;
 use std::fs::read_to_string;
 
@@ -29,19 +29,18 @@ struct Cli {
 
 fn version_string(
     raw_version: &str,
+    info: &VersionInfo,
     version_override: Option<String>,
-    dirty_deps: bool,
-    unversioned_deps: bool,
 ) -> String {
     if let Some(version) = version_override {
         return version;
     }
 
     let mut version = String::from(raw_version);
-    if dirty_deps {
+    if !info.dirty_deps.is_empty() {
         version.push_str(", dirty deps");
     }
-    if unversioned_deps {
+    if !info.unversioned_deps.is_empty() {
         version.push_str(", unversioned deps");
     }
     version
@@ -53,40 +52,34 @@ fn get_version(
     version_override: Option<String>,
     repo_override: Option<String>,
 ) -> Result<Version, Box<dyn Error>> {
-    let mut dirty_deps = Vec::new();
-    let mut unversioned_deps = Vec::new();
+    let mut info = raw_version.version_info.clone();
+    let mut dirty_deps = BTreeSet::new();
+    let mut unversioned_deps = BTreeSet::new();
+    let mut bad_dirty_deps = Vec::new();
+    let mut bad_unversioned_deps = Vec::new();
 
     for (target, version) in dep_versions {
-        if version.version.contains("dirty") {
-            dirty_deps.push((target, version));
+        let dep_info = &version.version_info;
+        if dep_info.dirty {
+            dirty_deps.insert(target.clone());
         }
-        if version.version.contains("unversioned") {
-            unversioned_deps.push((target, version));
+        dirty_deps.extend(dep_info.dirty_deps.iter().cloned());
+        if dep_info.unversioned {
+            unversioned_deps.insert(target.clone());
+        }
+        unversioned_deps.extend(dep_info.unversioned_deps.iter().cloned());
+
+        // Dirty or unversioned deps in the same repo are OK
+        if version.repo != raw_version.repo {
+            if dep_info.has_dirty() {
+                bad_dirty_deps.push(target);
+            }
+            if dep_info.has_unversioned() {
+                bad_unversioned_deps.push(target);
+            }
         }
     }
 
-    let version = Version::build(
-        &version_string(
-            &raw_version.version,
-            version_override,
-            !dirty_deps.is_empty(),
-            !unversioned_deps.is_empty(),
-        ),
-        &raw_version.repo,
-    )?;
-
-    // Dirty or unversioned deps in the same repo are OK
-    let bad_dirty_deps: Vec<&String> = dirty_deps
-        .iter()
-        .filter(|(_, v)| v.repo != version.repo)
-        .map(|(t, _)| *t)
-        .collect();
-    let bad_unversioned_deps: Vec<&String> = unversioned_deps
-        .iter()
-        .filter(|(_, v)| v.repo != version.repo)
-        .map(|(t, _)| *t)
-        .collect();
-
     if !bad_dirty_deps.is_empty() || !bad_unversioned_deps.is_empty() {
         let mut msg = vec![String::from("Target has dirty or unversioned deps")];
 
@@ -102,11 +95,19 @@ fn get_version(
         return Err(msg.join("\n").into());
     }
 
-    if let Some(repo) = repo_override {
-        return Ok(Version::build(&version.version, &repo)?);
+    info.dirty_deps = dirty_deps.into_iter().collect();
+    info.unversioned_deps = unversioned_deps.into_iter().collect();
+    if version_override.is_some() {
+        // Overrides are for reproducible output, so the state is dropped
+        info = VersionInfo {
+            override_source: Some(OverrideSource::VersionOverride),
+            ..Default::default()
+        };
     }
 
-    Ok(version)
+    let version = version_string(&raw_version.version, &info, version_override);
+    let repo = repo_override.unwrap_or_else(|| raw_version.repo.clone());
+    Ok(Version::build_with_info(version, repo, info)?)
 }
 
 fn main() -> Result<(), Box<dyn Error>> {
@@ -121,7 +122,11 @@ fn main() -> Result<(), Box<dyn Error>> {
         .map(|(target, metadata)| {
             Ok((
                 String::from(target),
-                Version::build(metadata.version(), metadata.repo())?,
+                Version::build_with_info(
+                    metadata.version(),
+                    metadata.repo(),
+                    metadata.version_info().clone(),
+                )?,
             ))
         })
         .collect::<Result<BTreeMap<String, Version>, Box<dyn Error>>>()?;
@@ -138,16 +143,32 @@ fn main() -> Result<(), Box<dyn Error>> {
 
 #[cfg(test)]
 mod version_test {
-    use super::{BTreeMap, Version, get_version};
+    use super::{BTreeMap, OverrideSource, Version, VersionInfo, get_version};
+
+    fn dirty(version: &str, repo: &str) -> Version {
+        let info = VersionInfo {
+            dirty: true,
+            ..Default::default()
+        };
+        Version::build_with_info(version, repo, info).unwrap()
+    }
+
+    fn unversioned(repo: &str) -> Version {
+        let info = VersionInfo {
+            unversioned: true,
+            ..Default::default()
+        };
+        Version::build_with_info("unversioned", repo, info).unwrap()
+    }
 
     #[test]
     fn test_version() {
         let base = Version::build("1", "foo").unwrap();
         let clean = Version::build("2", "bar").unwrap();
-        let dirty = Version::build("3-dirty", "baz").unwrap();
-        let unversioned = Version::build("unversioned", "quux").unwrap();
-        let dirty_same_repo = Version::build("4-dirty", "foo").unwrap();
-        let unversioned_same_repo = Version::build("unversioned", "foo").unwrap();
+        let dirty = dirty("3-dirty", "baz");
+        let unversioned = unversioned("quux");
+        let dirty_same_repo = self::dirty("4-dirty", "foo");
+        let unversioned_same_repo = self::unversioned("foo");
 
         let v = get_version(&base, &BTreeMap::new(), None, None).unwrap();
         assert_eq!(v.version, "1");
@@ -176,6 +197,9 @@ mod version_test {
         .unwrap();
         assert_eq!(v.version, "1, dirty deps, unversioned deps");
         assert_eq!(v.repo, "foo");
+        assert_eq!(v.version_info.dirty_deps, ["b"]);
+        assert_eq!(v.version_info.unversioned_deps, ["c"]);
+        assert!(!v.version_info.dirty);
 
         assert!(
             get_version(
@@ -205,6 +229,10 @@ mod version_test {
         .unwrap();
         assert_eq!(v.version, "OVERRIDE");
         assert_eq!(v.repo, "foo");
+        assert_eq!(
+            v.version_info.override_source,
+            Some(OverrideSource::VersionOverride)
+        );
 
         let v = get_version(
             &base,
@@ -259,4 +287,69 @@ mod version_test {
             .is_err()
         );
     }
+
+    #[test]
+    fn test_version_state() {
+        let base = Version::build("1", "foo").unwrap();
+
+        // Only the structured state matters, not the version string
+        let v = get_version(
+            &base,
+            &BTreeMap::from([(
+                String::from("a"),
+                Version::build("dirty-laundry", "bar").unwrap(),
+            )]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(v.version, "1");
+        assert_eq!(v.version_info, VersionInfo::default());
+
+        // Dirty deps are transitive
+        let info = VersionInfo {
+            dirty_deps: vec![String::from("c")],
+            ..Default::default()
+        };
+        let v = get_version(
+            &base,
+            &BTreeMap::from([
+                (
+                    String::from("a"),
+                    Version::build_with_info("2, dirty deps", "foo", info).unwrap(),
+                ),
+                (String::from("b"), dirty("3-dirty", "foo")),
+            ]),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(v.version, "1, dirty deps");
+        assert_eq!(v.version_info.dirty_deps, ["b", "c"]);
+
+        // A dep with dirty deps from another repo is as bad as a dirty dep
+        let info = VersionInfo {
+            dirty_deps: vec![String::from("c")],
+            ..Default::default()
+        };
+        assert_eq!(
+            get_version(
+                &base,
+                &BTreeMap::from([(
+                    String::from("a"),
+                    Version::build_with_info("2, dirty deps", "bar", info).unwrap(),
+                )]),
+                None,
+                None,
+            )
+            .unwrap_err()
+            .to_string(),
+            "Target has dirty or unversioned deps\nDirty deps:\n  a"
+        );
+
+        // The target's own state is kept
+        let v = get_version(&dirty("1-dirty", "foo"), &BTreeMap::new(), None, None).unwrap();
+        assert_eq!(v.version, "1-dirty");
+        assert!(v.version_info.dirty);
+    }
 }
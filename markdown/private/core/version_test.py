@@ -11,7 +11,7 @@ class TestVersion(test_utils.ScriptTestCase):
     def run_script(  # type: ignore[override]
         self,
         raw_version: Mapping[str, str],
-        deps_metadata: Mapping[str, Mapping[str, str | Sequence[str]]],
+        deps_metadata: Mapping[str, Mapping[str, Any]],
         args: Sequence[str],
     ) -> dict[str, Any]:
         raw_version_file = os.path.join(self.tmpdir(), "raw_version.json")
@@ -50,8 +50,9 @@ class TestVersion(test_utils.ScriptTestCase):
             {"version": "foo", "repo": "bar"},
             {
                 "dep1": {
-                    "version": "2, dirty",
+                    "version": "2-dirty",
                     "repo": "bar",
+                    "version-info": {"commit": "2", "dirty": True},
                     "wordcount": "0",
                     "poetry-lines": "0",
                     "lang": "a",
@@ -73,7 +74,11 @@ class TestVersion(test_utils.ScriptTestCase):
 
         self.assertEqual(
             metadata_out,
-            {"repo": "bar", "version": "foo, dirty deps"},
+            {
+                "repo": "bar",
+                "version": "foo, dirty deps",
+                "version-info": {"dirty-deps": ["dep1"]},
+            },
         )
 
     def test_main_version_override(self) -> None:
@@ -81,8 +86,9 @@ class TestVersion(test_utils.ScriptTestCase):
             {"version": "foo", "repo": "bar"},
             {
                 "dep1": {
-                    "version": "2, dirty",
+                    "version": "2-dirty",
                     "repo": "bar",
+                    "version-info": {"commit": "2", "dirty": True},
                     "wordcount": "0",
                     "poetry-lines": "0",
                     "lang": "a",
@@ -104,7 +110,11 @@ class TestVersion(test_utils.ScriptTestCase):
 
         self.assertEqual(
             metadata_out,
-            {"repo": "bar", "version": "override"},
+            {
+                "repo": "bar",
+                "version": "override",
+                "version-info": {"override-source": "version-override"},
+            },
         )
 
     def test_main_repo_override(self) -> None:
@@ -112,8 +122,9 @@ class TestVersion(test_utils.ScriptTestCase):
             {"version": "foo", "repo": "bar"},
             {
                 "dep1": {
-                    "version": "2, dirty",
+                    "version": "2-dirty",
                     "repo": "bar",
+                    "version-info": {"commit": "2", "dirty": True},
                     "wordcount": "0",
                     "poetry-lines": "0",
                     "lang": "a",
@@ -135,7 +146,35 @@ class TestVersion(test_utils.ScriptTestCase):
 
         self.assertEqual(
             metadata_out,
-            {"repo": "override", "version": "foo, dirty deps"},
+            {
+                "repo": "override",
+                "version": "foo, dirty deps",
+                "version-info": {"dirty-deps": ["dep1"]},
+            },
+        )
+
+    def test_main_version_string_ignored(self) -> None:
+        # Only the version info says whether a dep is dirty
+        metadata_out = self.run_script(
+            {"version": "foo", "repo": "bar"},
+            {
+                "dep1": {
+                    "version": "dirty-laundry-0-g1234567",
+                    "repo": "baz",
+                    "wordcount": "0",
+                    "poetry-lines": "0",
+                    "lang": "a",
+                    "source-hash": "b",
+                    "parsed-dates": [],
+                    "version-info": {"commit": "dirty-laundry-0-g1234567"},
+                },
+            },
+            [],
+        )
+
+        self.assertEqual(
+            metadata_out,
+            {"repo": "bar", "version": "foo"},
         )
 
     def test_main_fails(self) -> None:
@@ -144,8 +183,9 @@ class TestVersion(test_utils.ScriptTestCase):
                 {"version": "foo", "repo": "bar"},
                 {
                     "dep1": {
-                        "version": "2, dirty",
+                        "version": "2-dirty",
                         "repo": "baz",
+                        "version-info": {"commit": "2", "dirty": True},
                         "wordcount": "0",
                         "poetry-lines": "0",
                         "lang": "a",
@@ -37,7 +37,12 @@ load(
 )
 load(
     "//markdown/private/group:defs.bzl",
+    _md_group_earnings = "md_group_earnings",
+    _md_group_publication_stats = "md_group_publication_stats",
     _md_group_publications = "md_group_publications",
+    _md_group_reproducibility = "md_group_reproducibility",
+    _md_group_source_drift = "md_group_source_drift",
+    _md_group_submission_checks = "md_group_submission_checks",
     _md_group_summary = "md_group_summary",
 )
 load(
@@ -83,9 +88,16 @@ def md_file(
         data = None,
         images = None,
         increment_included_headers = False,
+        max_include_depth = 0,
+        lint_config = None,
+        prose_lint_config = None,
+        warnings_as_errors = False,
         version_file = None,
         version_override = "",
-        repo_override = ""):
+        repo_override = "",
+        venues = None,
+        hash_algorithm = "sha256",
+        cache_dir = None):
     """md_file represents a markdown source file.
 
     Args:
@@ -98,11 +110,26 @@ def md_file(
         increment_included_headers: if true, header level in included files is
             incremented, e.g. level 1 headers become level 2 headers. If false,
             headers are unchanged.
+        max_include_depth: maximum depth of nested includes, or 0 for no
+            limit. Include cycles and files included more than once are
+            always errors.
+        lint_config: lint rule configuration json file, which can enable,
+            disable and set the severity of rules, and add custom rules.
+        prose_lint_config: prose lint configuration json file, which can set
+            thresholds and the severity of rules. Prose warnings are written to
+            the prose_report output group.
+        warnings_as_errors: if true, warnings from preprocessing and linting,
+            such as unused deps, fail the build. Otherwise, preprocessing
+            warnings are written to the warnings output group.
         version_file: file with version info.
         version_override: set the document version to this value, rather than
            the computed value. Should only be used for testing.
         repo_override: set the document repo to this value, rather than
            the computed value. Should only be used for testing.
+        venues: venue registry json file; if set, publication venues must be
+           in the registry.
+        hash_algorithm: algorithm for the content hash, "sha256" or "blake3".
+        cache_dir: persistent writable cache dir.
     """
     if not src:
         src = name + ".md"
@@ -111,6 +138,7 @@ def md_file(
     md_group(
         name = name + "_deps",
         deps = deps,
+        cache_dir = cache_dir,
     )
 
     _md_file(
@@ -121,9 +149,15 @@ def md_file(
         data = data,
         images = images,
         increment_included_headers = increment_included_headers,
+        max_include_depth = max_include_depth,
+        lint_config = lint_config,
+        prose_lint_config = prose_lint_config,
+        warnings_as_errors = warnings_as_errors,
         version_file = version_file,
         version_override = version_override,
         repo_override = repo_override,
+        venues = venues,
+        hash_algorithm = hash_algorithm,
         visibility = ["//visibility:public"],
     )
 
@@ -140,11 +174,17 @@ def md_document(
         data = None,
         images = None,
         increment_included_headers = False,
+        max_include_depth = None,
+        lint_config = None,
+        prose_lint_config = None,
+        warnings_as_errors = None,
         extra_pandoc_flags = None,
         extra_latex_flags = None,
         version_file = None,
         version_override = None,
         repo_override = None,
+        venues = None,
+        hash_algorithm = None,
         timestamp_override = None,
         cache_dir = None,
         existing_file = None,
@@ -162,6 +202,17 @@ def md_document(
         increment_included_headers: if true, header level in included files is
             incremented, e.g. level 1 headers become level 2 headers. If false,
             headers are unchanged.
+        max_include_depth: maximum depth of nested includes, or 0 for no
+            limit. Include cycles and files included more than once are
+            always errors.
+        lint_config: lint rule configuration json file, which can enable,
+            disable and set the severity of rules, and add custom rules.
+        prose_lint_config: prose lint configuration json file, which can set
+            thresholds and the severity of rules. Prose warnings are written to
+            the prose_report output group.
+        warnings_as_errors: if true, warnings from preprocessing and linting,
+            such as unused deps, fail the build. Otherwise, preprocessing
+            warnings are written to the warnings output group.
         extra_pandoc_flags: extra flags to pass to pandoc.
         extra_latex_flags: extra flags to pass to pandoc for latex-based
             formats.
@@ -170,6 +221,9 @@ def md_document(
             the computed value. Should only be used for testing.
         repo_override: set the document repo to this value, rather than
             the computed value. Should only be used for testing.
+        venues: venue registry json file; if set, publication venues must be
+            in the registry.
+        hash_algorithm: algorithm for the content hash, "sha256" or "blake3".
         timestamp_override: set the build timestamp to this value, rather than
             the current value. Should only be used for testing.
         cache_dir: persistent writable cache dir.
@@ -180,7 +234,7 @@ def md_document(
         output_visibility: visibilty of the generated outputs.
     """
     if existing_file:
-        if src or deps or extra_dictionaries or data or images or increment_included_headers or version_override or repo_override:
+        if src or deps or extra_dictionaries or data or images or increment_included_headers or version_override or repo_override or venues or max_include_depth or lint_config or prose_lint_config or warnings_as_errors or hash_algorithm:
             native.fail("Other args must not be set when existing_file is set")
         file = existing_file
     else:
@@ -192,9 +246,16 @@ def md_document(
             data = data,
             images = images,
             increment_included_headers = increment_included_headers,
+            max_include_depth = max_include_depth,
+            lint_config = lint_config,
+            prose_lint_config = prose_lint_config,
+            warnings_as_errors = warnings_as_errors,
             version_file = version_file,
             version_override = version_override,
             repo_override = repo_override,
+            venues = venues,
+            hash_algorithm = hash_algorithm,
+            cache_dir = cache_dir,
         )
         file = name
 
@@ -367,6 +428,12 @@ def md_collection(
         author,
         deps,
         date = None,
+        contributors_list = False,
+        dedication = None,
+        acknowledgements = False,
+        generated_contents = False,
+        order = None,
+        manifest = None,
         extra_metadata = None,
         version_file = None,
         version_override = None,
@@ -380,9 +447,23 @@ def md_collection(
     Args:
         name: the name of the document.
         title: the title of the collection.
-        author: the author of the collection.
+        author: the author of the collection, or a list of its authors or
+            editors.
         date: the date of the collection.
         deps: md_file targets to include in the collection.
+        contributors_list: whether to list the authors, translators, and
+            illustrators of the deps at the end of the collection.
+        dedication: a dedication to put at the start of the collection.
+        acknowledgements: whether to add a page saying where each dep was first
+            published, based on its publications.
+        generated_contents: whether to generate a table of contents listing
+            each dep's authors and wordcount, instead of pandoc's.
+        order: how to order deps: "deps" (the order given), "date", "title",
+            "wordcount", or "published" (first publication date).
+        manifest: a JSON file giving the order of deps, grouped into sections,
+            e.g. {"sections": [{"heading": "Part One", "deps": [":foo"]}]}.
+            Every dep must be listed exactly once. Deps in sections without a
+            heading are at the same level as the headings of other sections.
         extra_metadata: a metadata file to include.
         version_file: file with version info.
         version_override: set the document version to this value, rather than
@@ -399,7 +480,13 @@ def md_collection(
     _md_collection_src(
         name = name + "_src",
         title = title,
-        author = author,
+        authors = [author] if type(author) == "string" else author,
+        contributors = contributors_list,
+        dedication = dedication,
+        acknowledgements = acknowledgements,
+        contents = generated_contents,
+        order = order,
+        manifest = manifest,
         date = date,
         deps = name + "_deps",
     )
@@ -413,10 +500,11 @@ def md_collection(
             Label("//markdown/private/collection:collection_before.tex"),
         ] + ([extra_metadata] if extra_metadata else []),
         increment_included_headers = True,
-        extra_pandoc_flags = [
+        extra_pandoc_flags = ([] if generated_contents else [
             "--table-of-contents",
-            "--toc-depth=1",
-        ] + (["--metadata-file=$(execpath %s)" % extra_metadata] if extra_metadata else []),
+            # Sections in the manifest are one level above the deps
+            "--toc-depth=%d" % (2 if manifest else 1),
+        ]) + (["--metadata-file=$(execpath %s)" % extra_metadata] if extra_metadata else []),
         extra_latex_flags = [
             "--variable=section-page-break",
             "--include-in-header=$(execpath %s)" % Label("//markdown/private/collection:collection_header.tex"),
@@ -431,16 +519,26 @@ def md_collection(
         output_visibility = output_visibility,
     )
 
-def md_group(name, deps):
+def md_group(name, deps, venues = None, check_submissions = False, max_submissions_per_venue = 0, cache_dir = None):
     """md_group is a group of md_file targets.
 
     Args:
         name: the name of the group.
         deps: md_file targets to include in the group.
+        venues: venue registry json file, used to canonicalise venue names in
+            the publications table, publication stats and earnings.
+        check_submissions: if true, building the group fails if any piece
+            breaks a venue's simultaneous submission policy, or is resubmitted
+            to a venue that already rejected it.
+        max_submissions_per_venue: if check_submissions is true and this is
+            non-zero, building the group fails if more than this many pieces
+            are pending at any one venue.
+        cache_dir: persistent writable cache dir.
     """
     _md_group(
         name = name,
         deps = deps,
+        cache_dir = cache_dir,
         visibility = ["//visibility:private"],
     )
 
@@ -453,9 +551,45 @@ def md_group(name, deps):
     _md_group_publications(
         name = name + "_publications",
         deps = name,
+        venues = venues,
+        visibility = ["//visibility:private"],
+    )
+
+    _md_group_publication_stats(
+        name = name + "_publication_stats",
+        deps = name,
+        venues = venues,
+        visibility = ["//visibility:private"],
+    )
+
+    _md_group_earnings(
+        name = name + "_earnings",
+        deps = name,
+        venues = venues,
         visibility = ["//visibility:private"],
     )
 
+    _md_group_reproducibility(
+        name = name + "_reproducibility",
+        deps = name,
+        visibility = ["//visibility:private"],
+    )
+
+    _md_group_source_drift(
+        name = name + "_source_drift",
+        deps = name,
+        visibility = ["//visibility:private"],
+    )
+
+    if check_submissions:
+        _md_group_submission_checks(
+            name = name + "_submission_checks",
+            deps = name,
+            venues = venues,
+            max_per_venue = max_submissions_per_venue,
+            visibility = ["//visibility:private"],
+        )
+
 md_summary = _md_summary
 md_git_repo = _md_git_repo
 md_workspace = _md_workspace
@@ -17,6 +17,24 @@ function Meta(meta) -- luacheck: ignore 131
     if meta["repo"] ~= nil then
         meta["repo"] = nil
     end
+    if meta["version-info"] ~= nil then
+        meta["version-info"] = nil
+    end
+    if meta["content-hash"] ~= nil then
+        meta["content-hash"] = nil
+    end
+    if meta["hash-src"] ~= nil then
+        meta["hash-src"] = nil
+    end
+    if meta["hash-deps"] ~= nil then
+        meta["hash-deps"] = nil
+    end
+    if meta["hash-inputs"] ~= nil then
+        meta["hash-inputs"] = nil
+    end
+    if meta["hash-generated"] ~= nil then
+        meta["hash-generated"] = nil
+    end
     if meta["parsed-dates"] ~= nil then
         meta["parsed-dates"] = nil
     end
@@ -12,6 +12,18 @@ publications: foo
 wordcount: 100
 poetry-lines: 10
 repo: foo
+version-info:
+  dirty: true
+content-hash:
+  algorithm: sha256
+  digest: foo
+hash-src: foo
+hash-deps:
+- foo
+hash-inputs:
+- foo
+hash-generated:
+- foo
 parsed-dates:
 - foo
 ---
@@ -29,6 +41,12 @@ publications: foo
 wordcount: 100
 poetry-lines: 10
 repo: foo
+version-info: {}
+content-hash: {}
+hash-src: ""
+hash-deps: []
+hash-inputs: []
+hash-generated: []
 parsed-dates: []
 ---
 """,
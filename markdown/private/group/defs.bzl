@@ -1,6 +1,6 @@
 """Aggregation rules."""
 
-load("//markdown/private/core:defs.bzl", "MdGroupInfo")
+load("//markdown/private/core:defs.bzl", "MdFileInfo", "MdGroupInfo")
 
 visibility(["//markdown/private", "//markdown/private/summary"])
 
@@ -53,14 +53,32 @@ md_group_summary = rule(
 
 def _md_group_publications_impl(ctx):
     publications = ctx.actions.declare_file(ctx.label.name + ".html")
+    inputs = [ctx.attr.deps[MdGroupInfo].metadata]
+    args = []
+    if ctx.file.venues:
+        inputs.append(ctx.file.venues)
+        args += ["--venues-file", ctx.file.venues.path]
     ctx.actions.run(
         outputs = [publications],
-        inputs = [ctx.attr.deps[MdGroupInfo].metadata],
+        inputs = inputs,
         executable = ctx.executable._group_publications,
-        arguments = [ctx.attr.deps[MdGroupInfo].metadata.path, publications.path],
+        arguments = args + [ctx.attr.deps[MdGroupInfo].metadata.path, publications.path],
         progress_message = "%{label}: generating publications",
     )
 
+    timeline = ctx.actions.declare_file(ctx.label.name + "_timeline.html")
+    ctx.actions.run(
+        outputs = [timeline],
+        inputs = inputs,
+        executable = ctx.executable._group_publications,
+        arguments = args + [
+            "--format=timeline",
+            ctx.attr.deps[MdGroupInfo].metadata.path,
+            timeline.path,
+        ],
+        progress_message = "%{label}: generating publications timeline",
+    )
+
     script = ctx.actions.declare_file(ctx.label.name + ".sh")
     ctx.actions.run(
         outputs = [script],
@@ -72,7 +90,7 @@ def _md_group_publications_impl(ctx):
 
     return [
         DefaultInfo(
-            files = depset([publications, script]),
+            files = depset([publications, timeline, script]),
             runfiles = ctx.runfiles(files = [publications]),
             executable = script,
         ),
@@ -81,12 +99,16 @@ def _md_group_publications_impl(ctx):
 md_group_publications = rule(
     implementation = _md_group_publications_impl,
     executable = True,
-    doc = "md_group_publications displays the publications of an md_group.",
+    doc = "md_group_publications displays the publications of an md_group, as a table and as a timeline.",
     attrs = {
         "deps": attr.label(
             providers = [MdGroupInfo],
             doc = "md_group to process.",
         ),
+        "venues": attr.label(
+            allow_single_file = [".json"],
+            doc = "Venue registry, used to canonicalise venue names and show venue details.",
+        ),
         "_group_publications": attr.label(
             default = "//markdown/private/group:group_publications",
             executable = True,
@@ -99,3 +121,244 @@ md_group_publications = rule(
         ),
     },
 )
+
+def _md_group_publication_stats_impl(ctx):
+    script = ctx.actions.declare_file(ctx.label.name + ".sh")
+    files = [ctx.attr.deps[MdGroupInfo].metadata]
+    runfile_args = []
+    if ctx.file.venues:
+        files.append(ctx.file.venues)
+        runfile_args.append("--venues-file=" + ctx.file.venues.short_path)
+    ctx.actions.run(
+        outputs = [script],
+        executable = ctx.executable._write_group_summary_script,
+        arguments = [
+            ctx.workspace_name,
+            ctx.attr.deps[MdGroupInfo].metadata.short_path,
+            ctx.executable._group_publication_stats.short_path,
+            script.path,
+        ] + runfile_args,
+        progress_message = "%{label}: generating publication stats script",
+    )
+
+    return [
+        DefaultInfo(
+            files = depset([script]),
+            runfiles = ctx.runfiles(
+                files = files,
+                transitive_files = ctx.attr._group_publication_stats[DefaultInfo].default_runfiles.files,
+            ),
+            executable = script,
+        ),
+    ]
+
+md_group_publication_stats = rule(
+    implementation = _md_group_publication_stats_impl,
+    executable = True,
+    doc = "md_group_publication_stats summarises the submission history of an md_group.",
+    attrs = {
+        "deps": attr.label(
+            providers = [MdGroupInfo],
+            doc = "md_group to process.",
+        ),
+        "venues": attr.label(
+            allow_single_file = [".json"],
+            doc = "Venue registry, used to canonicalise venue names.",
+        ),
+        "_group_publication_stats": attr.label(
+            default = "//markdown/private/group:group_publication_stats",
+            executable = True,
+            cfg = "exec",
+        ),
+        "_write_group_summary_script": attr.label(
+            default = "//markdown/private/group:write_group_summary_script",
+            executable = True,
+            cfg = "exec",
+        ),
+    },
+)
+
+def _md_group_submission_checks_impl(ctx):
+    ok = ctx.actions.declare_file(ctx.label.name + "_ok.txt")
+    inputs = [ctx.attr.deps[MdGroupInfo].metadata]
+    args = []
+    if ctx.file.venues:
+        inputs.append(ctx.file.venues)
+        args += ["--venues-file", ctx.file.venues.path]
+    if ctx.attr.max_per_venue:
+        args += ["--max-per-venue", str(ctx.attr.max_per_venue)]
+    ctx.actions.run(
+        outputs = [ok],
+        inputs = inputs,
+        executable = ctx.executable._group_submission_checks,
+        arguments = args + [ctx.attr.deps[MdGroupInfo].metadata.path, ok.path],
+        progress_message = "%{label}: checking submissions",
+    )
+
+    return [DefaultInfo(files = depset([ok]))]
+
+md_group_submission_checks = rule(
+    implementation = _md_group_submission_checks_impl,
+    doc = "md_group_submission_checks checks submission policies across an md_group.",
+    attrs = {
+        "deps": attr.label(
+            providers = [MdGroupInfo],
+            doc = "md_group to check.",
+        ),
+        "venues": attr.label(
+            allow_single_file = [".json"],
+            doc = "Venue registry, used to check simultaneous submission policies.",
+        ),
+        "max_per_venue": attr.int(
+            default = 0,
+            doc = "Maximum number of pieces pending at a single venue, or 0 for no limit.",
+        ),
+        "_group_submission_checks": attr.label(
+            default = "//markdown/private/group:group_submission_checks",
+            executable = True,
+            cfg = "exec",
+        ),
+    },
+)
+
+def _md_group_earnings_impl(ctx):
+    script = ctx.actions.declare_file(ctx.label.name + ".sh")
+    files = [ctx.attr.deps[MdGroupInfo].metadata]
+    runfile_args = []
+    if ctx.file.venues:
+        files.append(ctx.file.venues)
+        runfile_args.append("--venues-file=" + ctx.file.venues.short_path)
+    ctx.actions.run(
+        outputs = [script],
+        executable = ctx.executable._write_group_summary_script,
+        arguments = [
+            ctx.workspace_name,
+            ctx.attr.deps[MdGroupInfo].metadata.short_path,
+            ctx.executable._group_earnings.short_path,
+            script.path,
+        ] + runfile_args,
+        progress_message = "%{label}: generating earnings script",
+    )
+
+    return [
+        DefaultInfo(
+            files = depset([script]),
+            runfiles = ctx.runfiles(
+                files = files,
+                transitive_files = ctx.attr._group_earnings[DefaultInfo].default_runfiles.files,
+            ),
+            executable = script,
+        ),
+    ]
+
+md_group_earnings = rule(
+    implementation = _md_group_earnings_impl,
+    executable = True,
+    doc = "md_group_earnings totals the payments received by an md_group.",
+    attrs = {
+        "deps": attr.label(
+            providers = [MdGroupInfo],
+            doc = "md_group to process.",
+        ),
+        "venues": attr.label(
+            allow_single_file = [".json"],
+            doc = "Venue registry, used to canonicalise venue names.",
+        ),
+        "_group_earnings": attr.label(
+            default = "//markdown/private/group:group_earnings",
+            executable = True,
+            cfg = "exec",
+        ),
+        "_write_group_summary_script": attr.label(
+            default = "//markdown/private/group:write_group_summary_script",
+            executable = True,
+            cfg = "exec",
+        ),
+    },
+)
+
+def _md_group_reproducibility_impl(ctx):
+    group = ctx.attr.deps[MdGroupInfo]
+    include_graphs = [dep[MdFileInfo].include_graph for dep in group.deps]
+    args = []
+    for include_graph in include_graphs:
+        args += ["--include-graph", include_graph.path]
+
+    outputs = []
+    for fmt, ext in [("table", "txt"), ("json", "json")]:
+        out = ctx.actions.declare_file(ctx.label.name + "." + ext)
+        ctx.actions.run(
+            outputs = [out],
+            inputs = [group.metadata] + include_graphs,
+            executable = ctx.executable._group_reproducibility,
+            arguments = args + ["--format", fmt, group.metadata.path, out.path],
+            progress_message = "%{label}: generating reproducibility report",
+        )
+        outputs.append(out)
+
+    return [DefaultInfo(files = depset(outputs))]
+
+md_group_reproducibility = rule(
+    implementation = _md_group_reproducibility_impl,
+    doc = "md_group_reproducibility reports which deps make the targets in an md_group dirty or unversioned.",
+    attrs = {
+        "deps": attr.label(
+            providers = [MdGroupInfo],
+            doc = "md_group to process.",
+        ),
+        "_group_reproducibility": attr.label(
+            default = "//markdown/private/group:group_reproducibility",
+            executable = True,
+            cfg = "exec",
+        ),
+    },
+)
+
+def _md_group_source_drift_impl(ctx):
+    script = ctx.actions.declare_file(ctx.label.name + ".sh")
+    group = ctx.attr.deps[MdGroupInfo]
+    deps_metadata = [f for f in group.transitive_metadata.to_list() if f != group.metadata]
+    ctx.actions.run(
+        outputs = [script],
+        executable = ctx.executable._write_group_summary_script,
+        arguments = [
+            ctx.workspace_name,
+            group.metadata.short_path,
+            ctx.executable._group_source_drift.short_path,
+            script.path,
+        ] + ["--deps-metadata-file=" + f.short_path for f in deps_metadata],
+        progress_message = "%{label}: generating source drift script",
+    )
+
+    return [
+        DefaultInfo(
+            files = depset([script]),
+            runfiles = ctx.runfiles(
+                files = [group.metadata] + deps_metadata,
+                transitive_files = ctx.attr._group_source_drift[DefaultInfo].default_runfiles.files,
+            ),
+            executable = script,
+        ),
+    ]
+
+md_group_source_drift = rule(
+    implementation = _md_group_source_drift_impl,
+    executable = True,
+    doc = "md_group_source_drift checks whether the sources of an md_group have changed since it was built.",
+    attrs = {
+        "deps": attr.label(
+            providers = [MdGroupInfo],
+            doc = "md_group to check.",
+        ),
+        "_group_source_drift": attr.label(
+            default = "//markdown/private/group:group_source_drift",
+            executable = True,
+            cfg = "exec",
+        ),
+        "_write_group_summary_script": attr.label(
+            default = "//markdown/private/group:write_group_summary_script",
+            executable = True,
+            cfg = "exec",
+        ),
+    },
+)
@@ -0,0 +1,481 @@
+use chrono::{Datelike, NaiveDate};
+use clap::{Parser, ValueEnum};
+use markdown::args;
+use markdown::json::{JsonSerializable, from_json};
+use markdown::metadata::MetadataMap;
+use markdown::payments::{Money, Payment, PaymentStatus};
+use markdown::publications::Publication;
+use markdown::report::{self, Format};
+use markdown::venues::VenueRegistry;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::read_to_string;
+use tabled::Tabled;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Section {
+    Years,
+    Venues,
+    Currencies,
+    Unparsed,
+}
+
+#[derive(Parser)]
+#[command(version, about = "Total the earnings of the group", long_about = None)]
+struct Cli {
+    #[arg(value_parser = args::non_empty())]
+    metadata_file: String,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Only output one section of the report; csv output always contains
+    /// exactly one section, and defaults to years
+    #[arg(long, value_enum)]
+    section: Option<Section>,
+
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    venues_file: Option<String>,
+}
+
+fn display_amount(v: &Money) -> String {
+    format!("{v:.2}")
+}
+
+fn display_rate(o: &Option<Money>) -> String {
+    o.map(|v| format!("{v:.3}")).unwrap_or_default()
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Totals {
+    payments: u32,
+    received: Money,
+    pending: Money,
+}
+
+impl Totals {
+    fn add(&mut self, p: &Payment, wordcount: u32) {
+        self.payments += 1;
+        match p.status() {
+            PaymentStatus::Received => self.received += p.amount_for(wordcount),
+            PaymentStatus::Pending => self.pending += p.amount_for(wordcount),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Tabled)]
+struct YearEarnings {
+    year: i32,
+    currency: String,
+    payments: u32,
+    #[tabled(display = "display_amount")]
+    received: Money,
+    #[tabled(display = "display_amount")]
+    pending: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Tabled)]
+struct VenueEarnings {
+    venue: String,
+    currency: String,
+    payments: u32,
+    #[tabled(display = "display_amount")]
+    received: Money,
+    #[tabled(display = "display_amount")]
+    pending: Money,
+    #[tabled(rename = "mean rate per word", display = "display_rate")]
+    mean_rate_per_word: Option<Money>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Tabled)]
+struct CurrencyEarnings {
+    currency: String,
+    payments: u32,
+    #[tabled(display = "display_amount")]
+    received: Money,
+    #[tabled(display = "display_amount")]
+    pending: Money,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Tabled)]
+struct Unparsed {
+    target: String,
+    venue: String,
+    paid: String,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    years: Vec<YearEarnings>,
+    venues: Vec<VenueEarnings>,
+    currencies: Vec<CurrencyEarnings>,
+    unparsed: Vec<Unparsed>,
+}
+
+impl JsonSerializable for Report {}
+
+struct Entry<'a> {
+    publication: &'a Publication,
+    payment: Payment,
+    wordcount: u32,
+}
+
+fn entries(metadata: &MetadataMap) -> Vec<Entry<'_>> {
+    let mut out = Vec::new();
+    for m in metadata.data().values() {
+        for p in m.publications().publications() {
+            if let Some(payment) = p.paid().and_then(|paid| paid.payment()) {
+                out.push(Entry {
+                    publication: p,
+                    payment,
+                    wordcount: m.wordcount(),
+                });
+            }
+        }
+    }
+    out
+}
+
+fn payment_date(e: &Entry) -> NaiveDate {
+    // Without a paid-date, fall back to the closest date we have for when
+    // the money arrived.
+    let p = e.publication;
+    *p.paid_date()
+        .or(p.published())
+        .or(p.reprinted())
+        .or(p.accepted())
+        .unwrap_or(&p.latest().date)
+}
+
+fn year_earnings(entries: &[Entry]) -> Vec<YearEarnings> {
+    let mut years: BTreeMap<(i32, &str), Totals> = BTreeMap::new();
+    for e in entries {
+        years
+            .entry((payment_date(e).year(), e.payment.currency()))
+            .or_default()
+            .add(&e.payment, e.wordcount);
+    }
+    years
+        .into_iter()
+        .map(|((year, currency), totals)| YearEarnings {
+            year,
+            currency: String::from(currency),
+            payments: totals.payments,
+            received: totals.received,
+            pending: totals.pending,
+        })
+        .collect()
+}
+
+fn venue_earnings(entries: &[Entry]) -> Vec<VenueEarnings> {
+    let mut venues: BTreeMap<(&str, &str), (Totals, Vec<Money>)> = BTreeMap::new();
+    for e in entries {
+        let (totals, rates) = venues
+            .entry((e.publication.venue(), e.payment.currency()))
+            .or_default();
+        totals.add(&e.payment, e.wordcount);
+        if let Some(rate) = e.payment.rate_for(e.wordcount) {
+            rates.push(rate);
+        }
+    }
+    venues
+        .into_iter()
+        .map(|((venue, currency), (totals, rates))| VenueEarnings {
+            venue: String::from(venue),
+            currency: String::from(currency),
+            payments: totals.payments,
+            received: totals.received,
+            pending: totals.pending,
+            mean_rate_per_word: rates
+                .iter()
+                .copied()
+                .sum::<Money>()
+                .divided_by(rates.len() as u32),
+        })
+        .collect()
+}
+
+fn currency_earnings(entries: &[Entry]) -> Vec<CurrencyEarnings> {
+    let mut currencies: BTreeMap<&str, Totals> = BTreeMap::new();
+    for e in entries {
+        currencies
+            .entry(e.payment.currency())
+            .or_default()
+            .add(&e.payment, e.wordcount);
+    }
+    currencies
+        .into_iter()
+        .map(|(currency, totals)| CurrencyEarnings {
+            currency: String::from(currency),
+            payments: totals.payments,
+            received: totals.received,
+            pending: totals.pending,
+        })
+        .collect()
+}
+
+fn unparsed(metadata: &MetadataMap) -> Vec<Unparsed> {
+    let mut out = Vec::new();
+    for (target, m) in metadata.data() {
+        for p in m.publications().publications() {
+            if let Some(paid) = p.paid() {
+                if paid.payment().is_none() {
+                    out.push(Unparsed {
+                        target: target.clone(),
+                        venue: String::from(p.venue()),
+                        paid: paid.to_string(),
+                    });
+                }
+            }
+        }
+    }
+    out
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+
+    let mut metadata = from_json::<MetadataMap>(&read_to_string(args.metadata_file)?)?;
+    if let Some(path) = &args.venues_file {
+        metadata =
+            metadata.canonicalise_venues(&from_json::<VenueRegistry>(&read_to_string(path)?)?)?;
+    }
+    let es = entries(&metadata);
+    let report = Report {
+        years: year_earnings(&es),
+        venues: venue_earnings(&es),
+        currencies: currency_earnings(&es),
+        unparsed: unparsed(&metadata),
+    };
+
+    let show = |s: Section| args.section.is_none_or(|section| section == s);
+
+    match args.format {
+        Format::Table => {
+            let mut tables = Vec::new();
+            if show(Section::Years) {
+                tables.push(report::titled_table("Years", &report.years));
+            }
+            if show(Section::Venues) {
+                tables.push(report::titled_table("Venues", &report.venues));
+            }
+            if show(Section::Currencies) {
+                tables.push(report::titled_table("Currencies", &report.currencies));
+            }
+            if show(Section::Unparsed) {
+                tables.push(report::titled_table(
+                    "Unrecognised payments",
+                    &report.unparsed,
+                ));
+            }
+            println!("{}", tables.join("\n\n"));
+        }
+        Format::Csv => print!(
+            "{}",
+            match args.section.unwrap_or(Section::Years) {
+                Section::Years => report::csv(&report.years)?,
+                Section::Venues => report::csv(&report.venues)?,
+                Section::Currencies => report::csv(&report.currencies)?,
+                Section::Unparsed => report::csv(&report.unparsed)?,
+            }
+        ),
+        Format::Json => {
+            let report = Report {
+                years: report::shown(show(Section::Years), report.years),
+                venues: report::shown(show(Section::Venues), report.venues),
+                currencies: report::shown(show(Section::Currencies), report.currencies),
+                unparsed: report::shown(show(Section::Unparsed), report.unparsed),
+            };
+            println!("{}", report.to_json()?);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod group_earnings_test {
+    use super::{
+        BTreeMap, CurrencyEarnings, MetadataMap, Money, Payment, PaymentStatus, Publication,
+        Unparsed, YearEarnings, currency_earnings, entries, unparsed, venue_earnings,
+        year_earnings,
+    };
+    use markdown::metadata::OutputMetadata;
+    use markdown::publications::{PublicationBuilder, Publications};
+    use markdown_testing::{output_metadata, ymd};
+
+    fn money(s: &str) -> Money {
+        Money::parse(s).unwrap()
+    }
+
+    fn metadata(wordcount: u32, ps: Vec<Publication>) -> OutputMetadata {
+        output_metadata()
+            .publications(Publications::build(ps).unwrap())
+            .wordcount(wordcount)
+            .build()
+            .unwrap()
+    }
+
+    fn test_data() -> MetadataMap {
+        MetadataMap::build(BTreeMap::from([
+            (
+                String::from("a:a"),
+                metadata(
+                    1000,
+                    vec![
+                        PublicationBuilder::default()
+                            .venue("Foo")
+                            .paid("£10")
+                            .submitted(ymd(2022, 1, 1))
+                            .accepted(ymd(2022, 2, 1))
+                            .published(ymd(2022, 3, 1))
+                            .build()
+                            .unwrap(),
+                        PublicationBuilder::default()
+                            .venue("Bar")
+                            .paid("contributor copy")
+                            .submitted(ymd(2022, 1, 1))
+                            .accepted(ymd(2022, 2, 1))
+                            .build()
+                            .unwrap(),
+                    ],
+                ),
+            ),
+            (
+                String::from("a:b"),
+                metadata(
+                    500,
+                    vec![
+                        PublicationBuilder::default()
+                            .venue("Foo")
+                            .paid(
+                                Payment::build(
+                                    None,
+                                    "GBP",
+                                    Money::parse("0.02"),
+                                    PaymentStatus::Received,
+                                )
+                                .unwrap(),
+                            )
+                            .submitted(ymd(2022, 12, 1))
+                            .accepted(ymd(2023, 1, 1))
+                            .paid_date(ymd(2023, 1, 2))
+                            .build()
+                            .unwrap(),
+                    ],
+                ),
+            ),
+            (
+                String::from("a:c"),
+                metadata(
+                    100,
+                    vec![
+                        PublicationBuilder::default()
+                            .venue("Baz")
+                            .paid(
+                                Payment::build(
+                                    Money::parse("50"),
+                                    "USD",
+                                    None,
+                                    PaymentStatus::Pending,
+                                )
+                                .unwrap(),
+                            )
+                            .submitted(ymd(2023, 1, 1))
+                            .accepted(ymd(2023, 2, 1))
+                            .build()
+                            .unwrap(),
+                    ],
+                ),
+            ),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_year_earnings() {
+        let m = test_data();
+        assert_eq!(
+            year_earnings(&entries(&m)),
+            [
+                YearEarnings {
+                    year: 2022,
+                    currency: String::from("GBP"),
+                    payments: 1,
+                    received: money("10"),
+                    pending: money("0"),
+                },
+                YearEarnings {
+                    year: 2023,
+                    currency: String::from("GBP"),
+                    payments: 1,
+                    received: money("10"),
+                    pending: money("0"),
+                },
+                YearEarnings {
+                    year: 2023,
+                    currency: String::from("USD"),
+                    payments: 1,
+                    received: money("0"),
+                    pending: money("50"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_venue_earnings() {
+        let m = test_data();
+        let venues = venue_earnings(&entries(&m));
+        assert_eq!(venues.len(), 2);
+
+        let baz = &venues[0];
+        assert_eq!(baz.venue, "Baz");
+        assert_eq!(baz.payments, 1);
+        assert_eq!(baz.pending, money("50"));
+        assert_eq!(baz.mean_rate_per_word, Money::parse("0.5"));
+
+        let foo = &venues[1];
+        assert_eq!(foo.venue, "Foo");
+        assert_eq!(foo.currency, "GBP");
+        assert_eq!(foo.payments, 2);
+        assert_eq!(foo.received, money("20"));
+        assert_eq!(foo.mean_rate_per_word, Money::parse("0.015"));
+    }
+
+    #[test]
+    fn test_currency_earnings() {
+        let m = test_data();
+        assert_eq!(
+            currency_earnings(&entries(&m)),
+            [
+                CurrencyEarnings {
+                    currency: String::from("GBP"),
+                    payments: 2,
+                    received: money("20"),
+                    pending: money("0"),
+                },
+                CurrencyEarnings {
+                    currency: String::from("USD"),
+                    payments: 1,
+                    received: money("0"),
+                    pending: money("50"),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unparsed() {
+        assert_eq!(
+            unparsed(&test_data()),
+            [Unparsed {
+                target: String::from("a:a"),
+                venue: String::from("Bar"),
+                paid: String::from("contributor copy"),
+            }]
+        );
+    }
+}
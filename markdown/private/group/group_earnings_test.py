@@ -0,0 +1,147 @@
+import os
+import os.path
+from collections.abc import Sequence
+
+from markdown.private.utils import test_utils
+
+DATA = {
+    "test1:foo": {
+        "title": "Foo",
+        "author": "A",
+        "wordcount": "1000",
+        "poetry-lines": "0",
+        "version": "bar",
+        "publications": [
+            {
+                "venue": "foo",
+                "submitted": "2022-05-18",
+                "accepted": "2022-05-19",
+                "published": "2022-05-20",
+                "paid": "£10",
+            },
+            {
+                "venue": "bar",
+                "submitted": "2023-05-18",
+                "accepted": "2023-05-19",
+                "reprinted": "2023-05-20",
+                "paid": {
+                    "currency": "USD",
+                    "rate-per-word": 0.01,
+                    "status": "pending",
+                },
+            },
+        ],
+        "lang": "en-GB",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": [],
+    },
+    "test2:bar": {
+        "title": "Bar",
+        "author": "A",
+        "wordcount": "500",
+        "poetry-lines": "0",
+        "version": "bar",
+        "publications": [
+            {
+                "venue": "foo",
+                "submitted": "2023-01-01",
+                "accepted": "2023-02-01",
+                "paid-date": "2023-03-01",
+                "paid": {
+                    "amount": 20,
+                    "currency": "GBP",
+                    "status": "received",
+                },
+            },
+            {
+                "venue": "baz",
+                "submitted": "2022-01-01",
+                "accepted": "2022-01-02",
+                "paid": "contributor copy",
+            },
+        ],
+        "lang": "en-GB",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": [],
+    },
+}
+
+
+class TestEarnings(test_utils.ScriptTestCase):
+    maxDiff = None
+
+    def run_script(self, args: Sequence[str]) -> str:  # type: ignore[override]
+        filename = os.path.join(self.tmpdir(), "in.json")
+        self.dump_json(filename, DATA)
+
+        return (
+            super()
+            .run_script(
+                args=[
+                    filename,
+                    *args,
+                ],
+            )
+            .stdout
+        )
+
+    def test_earnings_pretty(self) -> None:
+        out = self.run_script([])
+        self.assertIn("Years", out)
+        self.assertIn("Venues", out)
+        self.assertIn("Currencies", out)
+        self.assertIn("Unrecognised payments", out)
+
+    def test_earnings_csv(self) -> None:
+        self.assertEqual(
+            self.run_script(["--format=csv"]),
+            """year,currency,payments,received,pending
+2022,GBP,1,10.0,0.0
+2023,GBP,1,20.0,0.0
+2023,USD,1,0.0,10.0
+""",
+        )
+
+        self.assertEqual(
+            self.run_script(["--format=csv", "--section=venues"]),
+            """venue,currency,payments,received,pending,mean_rate_per_word
+bar,USD,1,0.0,10.0,0.01
+foo,GBP,2,30.0,0.0,0.025
+""",
+        )
+
+    def test_earnings_venues_file(self) -> None:
+        venues_file = os.path.join(self.tmpdir(), "venues.json")
+        self.dump_json(venues_file, [{"name": "Foo", "aliases": ["foo", "bar"]}, {"name": "baz"}])
+
+        self.assertEqual(
+            self.run_script(["--format=csv", "--section=venues", f"--venues-file={venues_file}"]),
+            """venue,currency,payments,received,pending,mean_rate_per_word
+Foo,GBP,2,30.0,0.0,0.025
+Foo,USD,1,0.0,10.0,0.01
+""",
+        )
+
+    def test_earnings_json(self) -> None:
+        self.assertEqual(
+            self.run_script(["--format=json", "--section=unparsed"]),
+            """{
+  "currencies": [],
+  "unparsed": [
+    {
+      "paid": "contributor copy",
+      "target": "test2:bar",
+      "venue": "baz"
+    }
+  ],
+  "venues": [],
+  "years": []
+}
+""",
+        )
+
+
+if __name__ == "__main__":
+    test_utils.ScriptTestCase.main()
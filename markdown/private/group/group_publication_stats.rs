@@ -0,0 +1,416 @@
+use chrono::{Datelike, NaiveDate};
+use clap::{Parser, ValueEnum};
+use markdown::args;
+use markdown::json::{JsonSerializable, from_json};
+use markdown::metadata::MetadataMap;
+use markdown::publications::Publication;
+use markdown::report::{self, Format};
+use markdown::venues::VenueRegistry;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::read_to_string;
+use tabled::Tabled;
+
+const ALL_VENUES: &str = "(all)";
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Section {
+    Venues,
+    Years,
+    Unsubmitted,
+}
+
+#[derive(Parser)]
+#[command(version, about = "Summarise the submission history of the group", long_about = None)]
+struct Cli {
+    #[arg(value_parser = args::non_empty())]
+    metadata_file: String,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Only output one section of the report; csv output always contains
+    /// exactly one section, and defaults to venues
+    #[arg(long, value_enum)]
+    section: Option<Section>,
+
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    venues_file: Option<String>,
+}
+
+fn display_option(o: &Option<f64>) -> String {
+    o.map(|v| format!("{v:.1}")).unwrap_or_default()
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Tabled)]
+struct VenueStats {
+    venue: String,
+    submissions: u32,
+    pending: u32,
+    accepted: u32,
+    rejected: u32,
+    withdrawn: u32,
+    abandoned: u32,
+    #[tabled(rename = "acceptance rate %", display = "display_option")]
+    acceptance_rate: Option<f64>,
+    #[tabled(rename = "median response days", display = "display_option")]
+    median_response_days: Option<f64>,
+    #[tabled(rename = "median days to publication", display = "display_option")]
+    median_days_to_publication: Option<f64>,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Tabled)]
+struct YearStats {
+    year: i32,
+    submissions: u32,
+    accepted: u32,
+    rejected: u32,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Tabled)]
+struct Unsubmitted {
+    target: String,
+    title: String,
+    wordcount: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct Report {
+    venues: Vec<VenueStats>,
+    years: Vec<YearStats>,
+    unsubmitted: Vec<Unsubmitted>,
+}
+
+impl JsonSerializable for Report {}
+
+fn days(from: &NaiveDate, to: &NaiveDate) -> i64 {
+    (*to - *from).num_days()
+}
+
+fn median(mut values: Vec<i64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+    values.sort();
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) {
+        Some((values[mid - 1] + values[mid]) as f64 / 2.0)
+    } else {
+        Some(values[mid] as f64)
+    }
+}
+
+fn venue_stats(venue: &str, ps: &[&Publication]) -> VenueStats {
+    let submitted: Vec<&&Publication> = ps.iter().filter(|p| p.submitted().is_some()).collect();
+    let count = |f: fn(&Publication) -> bool| submitted.iter().filter(|p| f(p)).count() as u32;
+
+    let accepted = count(|p| p.accepted().is_some());
+    let rejected = count(|p| p.rejected().is_some());
+    let decided = accepted + rejected;
+
+    let mut response_days = Vec::new();
+    let mut publication_days = Vec::new();
+    for p in &submitted {
+        let Some(s) = p.submitted() else {
+            continue;
+        };
+        if let Some(d) = p.accepted().or(p.rejected()) {
+            response_days.push(days(s, d));
+        }
+        if let (Some(a), Some(d)) = (p.accepted(), p.published().or(p.reprinted())) {
+            publication_days.push(days(a, d));
+        }
+    }
+
+    VenueStats {
+        venue: String::from(venue),
+        submissions: submitted.len() as u32,
+        pending: count(|p| p.active() && p.accepted().is_none() && p.published().is_none()),
+        accepted,
+        rejected,
+        withdrawn: count(|p| p.withdrawn().is_some()),
+        abandoned: count(|p| p.abandoned().is_some()),
+        acceptance_rate: if decided > 0 {
+            Some(100.0 * accepted as f64 / decided as f64)
+        } else {
+            None
+        },
+        median_response_days: median(response_days),
+        median_days_to_publication: median(publication_days),
+    }
+}
+
+fn all_venue_stats(metadata: &MetadataMap) -> Vec<VenueStats> {
+    let mut venues: BTreeMap<&str, Vec<&Publication>> = BTreeMap::new();
+    for m in metadata.data().values() {
+        for p in m.publications().publications() {
+            venues.entry(p.venue()).or_default().push(p);
+        }
+    }
+
+    let mut out: Vec<VenueStats> = venues
+        .iter()
+        .map(|(venue, ps)| venue_stats(venue, ps))
+        .collect();
+    out.push(venue_stats(
+        ALL_VENUES,
+        &Vec::from_iter(venues.into_values().flatten()),
+    ));
+    out
+}
+
+fn year_stats(metadata: &MetadataMap) -> Vec<YearStats> {
+    let mut years: BTreeMap<i32, YearStats> = BTreeMap::new();
+    fn entry(years: &mut BTreeMap<i32, YearStats>, year: i32) -> &mut YearStats {
+        years.entry(year).or_insert(YearStats {
+            year,
+            submissions: 0,
+            accepted: 0,
+            rejected: 0,
+        })
+    }
+
+    for m in metadata.data().values() {
+        for p in m.publications().publications() {
+            if let Some(d) = p.submitted() {
+                entry(&mut years, d.year()).submissions += 1;
+            }
+            if let Some(d) = p.accepted() {
+                entry(&mut years, d.year()).accepted += 1;
+            }
+            if let Some(d) = p.rejected() {
+                entry(&mut years, d.year()).rejected += 1;
+            }
+        }
+    }
+
+    years.into_values().collect()
+}
+
+fn unsubmitted(metadata: &MetadataMap) -> Vec<Unsubmitted> {
+    // A piece with no active publication is available to be sent out; we only
+    // care about finished pieces, since unfinished ones can't be submitted.
+    metadata
+        .data()
+        .iter()
+        .filter(|(_, m)| m.finished() && !m.publications().active())
+        .map(|(target, m)| Unsubmitted {
+            target: target.clone(),
+            title: m.title().cloned().unwrap_or_default(),
+            wordcount: m.wordcount(),
+        })
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+
+    let mut metadata = from_json::<MetadataMap>(&read_to_string(args.metadata_file)?)?;
+    if let Some(path) = &args.venues_file {
+        metadata =
+            metadata.canonicalise_venues(&from_json::<VenueRegistry>(&read_to_string(path)?)?)?;
+    }
+    let report = Report {
+        venues: all_venue_stats(&metadata),
+        years: year_stats(&metadata),
+        unsubmitted: unsubmitted(&metadata),
+    };
+
+    let show = |s: Section| args.section.is_none_or(|section| section == s);
+
+    match args.format {
+        Format::Table => {
+            let mut tables = Vec::new();
+            if show(Section::Venues) {
+                tables.push(report::titled_table("Venues", &report.venues));
+            }
+            if show(Section::Years) {
+                tables.push(report::titled_table("Years", &report.years));
+            }
+            if show(Section::Unsubmitted) {
+                tables.push(report::titled_table(
+                    "Finished pieces with no active submission",
+                    &report.unsubmitted,
+                ));
+            }
+            println!("{}", tables.join("\n\n"));
+        }
+        Format::Csv => print!(
+            "{}",
+            match args.section.unwrap_or(Section::Venues) {
+                Section::Venues => report::csv(&report.venues)?,
+                Section::Years => report::csv(&report.years)?,
+                Section::Unsubmitted => report::csv(&report.unsubmitted)?,
+            }
+        ),
+        Format::Json => {
+            let report = Report {
+                venues: report::shown(show(Section::Venues), report.venues),
+                years: report::shown(show(Section::Years), report.years),
+                unsubmitted: report::shown(show(Section::Unsubmitted), report.unsubmitted),
+            };
+            println!("{}", report.to_json()?);
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod group_publication_stats_test {
+    use super::{
+        ALL_VENUES, BTreeMap, MetadataMap, Unsubmitted, YearStats, all_venue_stats, median,
+        unsubmitted, year_stats,
+    };
+    use markdown::publications::{Publication, PublicationBuilder, Publications};
+    use markdown_testing::{output_metadata, ymd};
+
+    fn metadata(
+        finished: bool,
+        title: &str,
+        ps: Vec<Publication>,
+    ) -> markdown::metadata::OutputMetadata {
+        output_metadata()
+            .title(title)
+            .finished(finished)
+            .publications(Publications::build(ps).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    fn test_data() -> MetadataMap {
+        MetadataMap::build(BTreeMap::from([
+            (
+                String::from("a:a"),
+                metadata(
+                    true,
+                    "A",
+                    vec![
+                        PublicationBuilder::default()
+                            .venue("Foo")
+                            .submitted(ymd(2020, 1, 1))
+                            .rejected(ymd(2020, 1, 11))
+                            .build()
+                            .unwrap(),
+                        PublicationBuilder::default()
+                            .venue("Bar")
+                            .submitted(ymd(2020, 2, 1))
+                            .accepted(ymd(2020, 2, 21))
+                            .published(ymd(2021, 2, 20))
+                            .build()
+                            .unwrap(),
+                    ],
+                ),
+            ),
+            (
+                String::from("b:b"),
+                metadata(
+                    true,
+                    "B",
+                    vec![
+                        PublicationBuilder::default()
+                            .venue("Foo")
+                            .submitted(ymd(2021, 1, 1))
+                            .rejected(ymd(2021, 1, 31))
+                            .build()
+                            .unwrap(),
+                    ],
+                ),
+            ),
+            (
+                String::from("c:c"),
+                metadata(
+                    true,
+                    "C",
+                    vec![
+                        PublicationBuilder::default()
+                            .venue("Foo")
+                            .submitted(ymd(2021, 3, 1))
+                            .build()
+                            .unwrap(),
+                    ],
+                ),
+            ),
+            (String::from("d:d"), metadata(false, "D", Vec::new())),
+        ]))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_median() {
+        assert!(median(Vec::new()).is_none());
+        assert_eq!(median(vec![3, 1, 2]), Some(2.0));
+        assert_eq!(median(vec![4, 1, 2, 3]), Some(2.5));
+    }
+
+    #[test]
+    fn test_venue_stats() {
+        let stats = all_venue_stats(&test_data());
+        assert_eq!(stats.len(), 3);
+
+        let bar = &stats[0];
+        assert_eq!(bar.venue, "Bar");
+        assert_eq!(bar.submissions, 1);
+        assert_eq!(bar.pending, 0);
+        assert_eq!(bar.accepted, 1);
+        assert_eq!(bar.rejected, 0);
+        assert_eq!(bar.acceptance_rate, Some(100.0));
+        assert_eq!(bar.median_response_days, Some(20.0));
+        assert_eq!(bar.median_days_to_publication, Some(365.0));
+
+        let foo = &stats[1];
+        assert_eq!(foo.venue, "Foo");
+        assert_eq!(foo.submissions, 3);
+        assert_eq!(foo.pending, 1);
+        assert_eq!(foo.accepted, 0);
+        assert_eq!(foo.rejected, 2);
+        assert_eq!(foo.acceptance_rate, Some(0.0));
+        assert_eq!(foo.median_response_days, Some(20.0));
+        assert!(foo.median_days_to_publication.is_none());
+
+        let all = &stats[2];
+        assert_eq!(all.venue, ALL_VENUES);
+        assert_eq!(all.submissions, 4);
+        assert_eq!(all.pending, 1);
+        assert_eq!(all.accepted, 1);
+        assert_eq!(all.rejected, 2);
+        assert!((all.acceptance_rate.unwrap() - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(all.median_response_days, Some(20.0));
+        assert_eq!(all.median_days_to_publication, Some(365.0));
+    }
+
+    #[test]
+    fn test_year_stats() {
+        assert_eq!(
+            year_stats(&test_data()),
+            vec![
+                YearStats {
+                    year: 2020,
+                    submissions: 2,
+                    accepted: 1,
+                    rejected: 1,
+                },
+                YearStats {
+                    year: 2021,
+                    submissions: 2,
+                    accepted: 0,
+                    rejected: 1,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_unsubmitted() {
+        assert_eq!(
+            unsubmitted(&test_data()),
+            vec![Unsubmitted {
+                target: String::from("b:b"),
+                title: String::from("B"),
+                wordcount: 10,
+            }]
+        );
+    }
+}
@@ -0,0 +1,138 @@
+import os
+import os.path
+from collections.abc import Sequence
+
+from markdown.private.utils import test_utils
+
+DATA = {
+    "test1:foo": {
+        "title": "Foo",
+        "author": ["A", "B"],
+        "date": "2022",
+        "wordcount": "10",
+        "poetry-lines": "3",
+        "finished": True,
+        "version": "bar",
+        "publications": [
+            {
+                "venue": "foo",
+                "submitted": "2023-05-18",
+                "accepted": "2023-05-18",
+                "published": "2023-05-18",
+            },
+        ],
+        "lang": "en-GB",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": ["2022"],
+    },
+    "test1:bar": {
+        "title": "Bar\nbaz",
+        "author": "A",
+        "wordcount": "5",
+        "poetry-lines": "0",
+        "version": "quux, dirty",
+        "lang": "en-GB",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": [],
+    },
+    "test2:baz": {
+        "title": "baz",
+        "author": "B",
+        "date": "from August 2020 to 1 March 2023",
+        "wordcount": "20",
+        "poetry-lines": "5",
+        "finished": False,
+        "version": "baz",
+        "publications": [
+            {
+                "venue": "baz",
+                "submitted": "2023-05-18",
+                "rejected": "2023-05-18",
+            },
+        ],
+        "lang": "en-GB",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": ["2020/08", "2023/03/01"],
+    },
+}
+
+
+class TestPublicationStats(test_utils.ScriptTestCase):
+    maxDiff = None
+
+    def run_script(self, args: Sequence[str]) -> str:  # type: ignore[override]
+        filename = os.path.join(self.tmpdir(), "in.json")
+        self.dump_json(filename, DATA)
+
+        return (
+            super()
+            .run_script(
+                args=[
+                    filename,
+                    *args,
+                ],
+            )
+            .stdout
+        )
+
+    def test_stats_pretty(self) -> None:
+        out = self.run_script([])
+        self.assertIn("Venues", out)
+        self.assertIn("Years", out)
+        self.assertIn("Finished pieces with no active submission", out)
+
+    def test_stats_csv(self) -> None:
+        self.assertEqual(
+            self.run_script(["--format=csv"]),
+            """venue,submissions,pending,accepted,rejected,withdrawn,abandoned,\
+acceptance_rate,median_response_days,median_days_to_publication
+baz,1,0,0,1,0,0,0.0,0.0,
+foo,1,0,1,0,0,0,100.0,0.0,0.0
+(all),2,0,1,1,0,0,50.0,0.0,0.0
+""",
+        )
+
+        self.assertEqual(
+            self.run_script(["--format=csv", "--section=years"]),
+            """year,submissions,accepted,rejected
+2023,2,1,1
+""",
+        )
+
+    def test_stats_json(self) -> None:
+        self.assertEqual(
+            self.run_script(["--format=json", "--section=years"]),
+            """{
+  "unsubmitted": [],
+  "venues": [],
+  "years": [
+    {
+      "accepted": 1,
+      "rejected": 1,
+      "submissions": 2,
+      "year": 2023
+    }
+  ]
+}
+""",
+        )
+
+    def test_stats_venues_file(self) -> None:
+        venues_file = os.path.join(self.tmpdir(), "venues.json")
+        self.dump_json(venues_file, [{"name": "Foo", "aliases": ["foo", "baz"]}])
+
+        self.assertEqual(
+            self.run_script(["--format=csv", f"--venues-file={venues_file}"]),
+            """venue,submissions,pending,accepted,rejected,withdrawn,abandoned,\
+acceptance_rate,median_response_days,median_days_to_publication
+Foo,2,0,1,1,0,0,50.0,0.0,0.0
+(all),2,0,1,1,0,0,50.0,0.0,0.0
+""",
+        )
+
+
+if __name__ == "__main__":
+    test_utils.ScriptTestCase.main()
@@ -1,12 +1,35 @@
-use clap::Parser;
+use chrono::{Datelike, NaiveDate};
+use clap::{Parser, ValueEnum};
 use markdown::args;
 use markdown::json::{JsonSerializable, from_json};
 use markdown::metadata::{MetadataMap, OutputMetadata};
 use markdown::publications::{Publication, State};
+use markdown::venues::VenueRegistry;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::error::Error;
 use std::fs::{read_to_string, write};
 
+const STATE_COLOURS: [(State, &str); 11] = [
+    (State::Submitted, "#ffff00"),
+    (State::Shortlisted, "#ffbf00"),
+    (State::Rejected, "#ff6d6d"),
+    (State::Withdrawn, "#ff972f"),
+    (State::Abandoned, "#cccccc"),
+    (State::Accepted, "#729fcf"),
+    (State::Contracted, "#5983b0"),
+    (State::Paid, "#b2b2ff"),
+    (State::SelfPublished, "#158466"),
+    (State::Published, "#81d41a"),
+    (State::Reprinted, "#afd095"),
+];
+
+// Timeline layout, in pixels
+const LABEL_WIDTH: f64 = 300.0;
+const CHART_WIDTH: f64 = 900.0;
+const ROW_HEIGHT: f64 = 20.0;
+const BAR_HEIGHT: f64 = 14.0;
+const MIN_BAR_WIDTH: f64 = 2.0;
+
 fn capitalise(s: &str) -> String {
     let mut chars: Vec<char> = s.chars().collect();
     if let Some(c) = chars.first_mut() {
@@ -15,6 +38,14 @@ fn capitalise(s: &str) -> String {
     String::from_iter(chars)
 }
 
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    /// A grid of venues against targets
+    Table,
+    /// A bar for each publication across a time axis
+    Timeline,
+}
+
 #[derive(Parser)]
 #[command(version, about, long_about = None)]
 struct Cli {
@@ -23,9 +54,45 @@ struct Cli {
 
     #[arg(value_parser = args::non_empty())]
     out_file: String,
+
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    venues_file: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+
+    /// Only include this target
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    target: Option<String>,
+
+    /// End of the timeline, which publications still in progress extend to;
+    /// defaults to the latest date in the metadata
+    #[arg(long)]
+    end_date: Option<NaiveDate>,
 }
 
-fn generate_header(venues: &BTreeSet<&str>) -> Vec<String> {
+fn venue_title(venue: &str, registry: Option<&VenueRegistry>) -> String {
+    let mut out = vec![String::from(venue)];
+    if let Some(v) = registry.and_then(|r| r.get(venue)) {
+        out.extend(v.details());
+    }
+    out.join("\n")
+}
+
+fn venue_name(venue: &str, registry: Option<&VenueRegistry>) -> String {
+    if let Some(url) = registry.and_then(|r| r.get(venue)).and_then(|v| v.url()) {
+        return format!(
+            "<a href=\"{}\">{}</a>",
+            html_escape::encode_double_quoted_attribute(url),
+            html_escape::encode_text(venue)
+        );
+    }
+    html_escape::encode_text(venue).to_string()
+}
+
+fn generate_header(venues: &BTreeSet<&str>, registry: Option<&VenueRegistry>) -> Vec<String> {
     let mut out = vec![
         String::from("<thead>"),
         String::from("<tr>"),
@@ -37,8 +104,8 @@ fn generate_header(venues: &BTreeSet<&str>) -> Vec<String> {
     out.extend(venues.iter().map(|v| {
         format!(
             "<th title=\"{}\">{}</th>",
-            html_escape::encode_double_quoted_attribute(v),
-            html_escape::encode_text(v)
+            html_escape::encode_double_quoted_attribute(&venue_title(v, registry)),
+            venue_name(v, registry)
         )
     }));
     out.extend([String::from("</tr>"), String::from("</thead>")]);
@@ -110,14 +177,14 @@ fn generate_cell(target: &str, p: &Publication) -> String {
 
     format!(
         "<td class=\"{}\" title=\"{}\"><a href=\"#{}\">{}</a></td>",
-        p.latest().state,
+        p.state(),
         html_escape::encode_double_quoted_attribute(&format!("{target}, {}", p.venue())),
         html_escape::encode_double_quoted_attribute(target),
         Vec::from_iter(content.iter().map(html_escape::encode_text)).join("<br>")
     )
 }
 
-fn generate_table(metadata: &MetadataMap) -> Vec<String> {
+fn generate_table(metadata: &MetadataMap, registry: Option<&VenueRegistry>) -> Vec<String> {
     let mut out = vec![String::from("<table>")];
 
     let mut venues = BTreeSet::new();
@@ -127,7 +194,7 @@ fn generate_table(metadata: &MetadataMap) -> Vec<String> {
         }
     }
 
-    out.extend(generate_header(&venues));
+    out.extend(generate_header(&venues, registry));
 
     out.push(String::from("<tbody>"));
     for (target, m) in metadata.data() {
@@ -158,8 +225,8 @@ fn generate_details(metadata: &MetadataMap) -> Result<Vec<String>, Box<dyn Error
     Ok(out)
 }
 
-fn generate_head() -> Vec<String> {
-    vec![
+fn generate_head(format: Format) -> Vec<String> {
+    let mut out = vec![
         String::from("<head>"),
         String::from("<meta charset=\"utf-8\">"),
         String::from("<title>Publications</title>"),
@@ -168,57 +235,223 @@ fn generate_head() -> Vec<String> {
         String::from("th, td { border: 1px solid; padding: 5px; }"),
         String::from("a:link { color: black; }"),
         String::from("a:visited { color: black; }"),
-        format!(
-            ".{} {{ background-color: #ffff00; }}",
-            State::Submitted.to_string()
-        ),
-        format!(
-            ".{} {{ background-color: #ff6d6d; }}",
-            State::Rejected.to_string()
-        ),
-        format!(
-            ".{} {{ background-color: #ff972f; }}",
-            State::Withdrawn.to_string()
-        ),
-        format!(
-            ".{} {{ background-color: #cccccc; }}",
-            State::Abandoned.to_string()
-        ),
-        format!(
-            ".{} {{ background-color: #729fcf; }}",
-            State::Accepted.to_string()
-        ),
-        format!(
-            ".{} {{ background-color: #158466; }}",
-            State::SelfPublished.to_string()
-        ),
-        format!(
-            ".{} {{ background-color: #81d41a; }}",
-            State::Published.to_string()
-        ),
-        String::from("</style>"),
-        String::from("</head>"),
-    ]
+    ];
+    out.extend(
+        STATE_COLOURS
+            .iter()
+            .map(|(state, colour)| format!(".{state} {{ background-color: {colour}; }}")),
+    );
+    if format == Format::Timeline {
+        out.extend(
+            STATE_COLOURS
+                .iter()
+                .map(|(state, colour)| format!("rect.{state} {{ fill: {colour}; }}")),
+        );
+    }
+    out.extend([String::from("</style>"), String::from("</head>")]);
+    out
 }
 
-fn generate_body(metadata: &MetadataMap) -> Result<Vec<String>, Box<dyn Error>> {
+fn generate_body(
+    metadata: &MetadataMap,
+    registry: Option<&VenueRegistry>,
+) -> Result<Vec<String>, Box<dyn Error>> {
     let mut out = vec![
         String::from("<body>"),
         String::from("<h1>Publications</h1>"),
     ];
-    out.extend(generate_table(metadata));
+    out.extend(generate_table(metadata, registry));
     out.extend(generate_details(metadata)?);
     out.push(String::from("</body>"));
     Ok(out)
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Bar {
+    state: State,
+    start: NaiveDate,
+    end: NaiveDate,
+}
+
+fn in_progress(p: &Publication) -> bool {
+    matches!(
+        p.state(),
+        State::Submitted | State::Shortlisted | State::Accepted | State::Contracted | State::Paid
+    )
+}
+
+// A bar for each state the publication has been in, lasting until the next
+// state. The last state lasts until end_date if the publication is still in
+// progress, and is a single day otherwise.
+fn bars(p: &Publication, end_date: NaiveDate) -> Vec<Bar> {
+    let dates = p.dates();
+    dates
+        .iter()
+        .enumerate()
+        .map(|(i, d)| Bar {
+            state: d.state,
+            start: d.date,
+            end: match dates.get(i + 1) {
+                Some(next) => next.date,
+                None if in_progress(p) => end_date.max(d.date),
+                None => d.date,
+            },
+        })
+        .collect()
+}
+
+struct Axis {
+    start: NaiveDate,
+    end: NaiveDate,
+    last: NaiveDate,
+}
+
+impl Axis {
+    // Whole years, covering all dates; last is the latest date
+    fn build(metadata: &MetadataMap, end_date: Option<NaiveDate>) -> Option<Axis> {
+        let dates: Vec<NaiveDate> = metadata
+            .data()
+            .values()
+            .flat_map(|m| m.publications().publications())
+            .flat_map(|p| p.dates())
+            .map(|d| d.date)
+            .chain(end_date)
+            .collect();
+        let first = dates.iter().min()?;
+        let last = dates.iter().max()?;
+        Some(Axis {
+            start: NaiveDate::from_ymd_opt(first.year(), 1, 1)?,
+            end: NaiveDate::from_ymd_opt(last.year() + 1, 1, 1)?,
+            last: *last,
+        })
+    }
+
+    fn x(&self, date: NaiveDate) -> f64 {
+        let days = (self.end - self.start).num_days() as f64;
+        LABEL_WIDTH + (date - self.start).num_days() as f64 * CHART_WIDTH / days
+    }
+
+    fn years(&self) -> impl Iterator<Item = NaiveDate> + '_ {
+        (self.start.year()..=self.end.year()).filter_map(|y| NaiveDate::from_ymd_opt(y, 1, 1))
+    }
+}
+
+fn generate_bar(target: &str, p: &Publication, bar: &Bar, axis: &Axis, y: f64) -> String {
+    let x = axis.x(bar.start);
+    let width = (axis.x(bar.end) - x).max(MIN_BAR_WIDTH);
+    let state = capitalise(&bar.state.to_string().replace("_", "-"));
+    let dates = if bar.start == bar.end {
+        bar.start.to_string()
+    } else {
+        format!("{} to {}", bar.start, bar.end)
+    };
+    format!(
+        "<rect class=\"{}\" x=\"{x:.1}\" y=\"{:.1}\" width=\"{width:.1}\" height=\"{BAR_HEIGHT:.1}\">\
+<title>{}</title></rect>",
+        bar.state,
+        y + (ROW_HEIGHT - BAR_HEIGHT) / 2.0,
+        html_escape::encode_text(&format!("{target}, {}: {state} {dates}", p.venue())),
+    )
+}
+
+fn generate_text(x: f64, y: f64, attrs: &str, text: &str) -> String {
+    format!(
+        "<text x=\"{x:.1}\" y=\"{:.1}\"{attrs}>{}</text>",
+        y + ROW_HEIGHT * 0.75,
+        html_escape::encode_text(text)
+    )
+}
+
+fn generate_timeline(metadata: &MetadataMap, end_date: Option<NaiveDate>) -> Vec<String> {
+    let Some(axis) = Axis::build(metadata, end_date) else {
+        return vec![String::from("<p>No publications.</p>")];
+    };
+    let end_date = end_date.unwrap_or(axis.last);
+
+    let mut rows = Vec::new();
+    let mut y = ROW_HEIGHT;
+    for (target, m) in metadata.data() {
+        rows.push(generate_text(0.0, y, " font-weight=\"bold\"", target));
+        y += ROW_HEIGHT;
+
+        let mut ps: Vec<&Publication> = m.publications().publications().iter().collect();
+        ps.sort_by_key(|p| p.dates().first().map(|d| d.date));
+        for p in ps {
+            rows.push(generate_text(10.0, y, "", p.venue()));
+            rows.extend(
+                bars(p, end_date)
+                    .iter()
+                    .map(|bar| generate_bar(target, p, bar, &axis, y)),
+            );
+            y += ROW_HEIGHT;
+        }
+    }
+
+    let height = y + ROW_HEIGHT * 2.0;
+    let mut out = vec![format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{:.0}\" height=\"{height:.0}\" \
+font-family=\"sans-serif\" font-size=\"12\">",
+        LABEL_WIDTH + CHART_WIDTH + 10.0
+    )];
+
+    // Year gridlines, with labels at the top
+    for year in axis.years() {
+        let x = axis.x(year);
+        out.push(format!(
+            "<line x1=\"{x:.1}\" y1=\"0\" x2=\"{x:.1}\" y2=\"{y:.1}\" stroke=\"#cccccc\"/>"
+        ));
+        if year < axis.end {
+            out.push(generate_text(x + 2.0, 0.0, "", &year.year().to_string()));
+        }
+    }
+
+    out.extend(rows);
+
+    // Legend
+    let legend_y = y + ROW_HEIGHT;
+    for (i, (state, _)) in STATE_COLOURS.iter().enumerate() {
+        let x = i as f64 * (LABEL_WIDTH + CHART_WIDTH) / STATE_COLOURS.len() as f64;
+        out.push(format!(
+            "<rect class=\"{state}\" x=\"{x:.1}\" y=\"{:.1}\" width=\"{BAR_HEIGHT:.1}\" \
+height=\"{BAR_HEIGHT:.1}\"/>",
+            legend_y + (ROW_HEIGHT - BAR_HEIGHT) / 2.0
+        ));
+        out.push(generate_text(
+            x + BAR_HEIGHT + 4.0,
+            legend_y,
+            "",
+            &capitalise(&state.to_string().replace("_", "-")),
+        ));
+    }
+
+    out.push(String::from("</svg>"));
+    out
+}
+
 fn main() -> Result<(), Box<dyn Error>> {
     let args = Cli::parse();
 
+    let registry = match args.venues_file {
+        Some(path) => Some(from_json::<VenueRegistry>(&read_to_string(path)?)?),
+        None => None,
+    };
+
+    let mut metadata = from_json::<MetadataMap>(&read_to_string(args.metadata_file)?)?;
+    if let Some(registry) = &registry {
+        metadata = metadata.canonicalise_venues(registry)?;
+    }
+
+    if let Some(target) = &args.target {
+        if !metadata.data().contains_key(target) {
+            return Err(format!("unknown target '{target}'").into());
+        }
+    }
+
     let metadata = MetadataMap::build(BTreeMap::from_iter(
-        from_json::<MetadataMap>(&read_to_string(args.metadata_file)?)?
+        metadata
             .data()
             .iter()
+            .filter(|(target, _)| args.target.as_ref().is_none_or(|t| t == *target))
             .filter(|(_, m)| !m.publications().is_empty())
             .map(|(target, m)| (target.clone(), m.clone())),
     ))?;
@@ -228,11 +461,127 @@ fn main() -> Result<(), Box<dyn Error>> {
         String::from("<html lang=\"en-GB\">"),
     ];
 
-    out.extend(generate_head());
-    out.extend(generate_body(&metadata)?);
+    out.extend(generate_head(args.format));
+    match args.format {
+        Format::Table => out.extend(generate_body(&metadata, registry.as_ref())?),
+        Format::Timeline => {
+            out.extend([
+                String::from("<body>"),
+                String::from("<h1>Publications</h1>"),
+            ]);
+            out.extend(generate_timeline(&metadata, args.end_date));
+            out.push(String::from("</body>"));
+        }
+    }
 
     out.push(String::from("</html>"));
 
     write(args.out_file, out.join("\n") + "\n")?;
     Ok(())
 }
+
+#[cfg(test)]
+mod group_publications_test {
+    use super::{Axis, Bar, State, bars};
+    use markdown::metadata::{MetadataMap, OutputMetadata};
+    use markdown::publications::{Publication, PublicationBuilder, Publications};
+    use markdown_testing::{output_metadata, ymd};
+    use std::collections::BTreeMap;
+
+    fn metadata(ps: Vec<Publication>) -> OutputMetadata {
+        output_metadata()
+            .publications(Publications::build(ps).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_bars_finished() {
+        let p = PublicationBuilder::default()
+            .venue("Foo")
+            .submitted(ymd(2023, 1, 1))
+            .accepted(ymd(2023, 2, 1))
+            .published(ymd(2023, 3, 1))
+            .build()
+            .unwrap();
+        assert_eq!(
+            bars(&p, ymd(2024, 1, 1)),
+            [
+                Bar {
+                    state: State::Submitted,
+                    start: ymd(2023, 1, 1),
+                    end: ymd(2023, 2, 1)
+                },
+                Bar {
+                    state: State::Accepted,
+                    start: ymd(2023, 2, 1),
+                    end: ymd(2023, 3, 1)
+                },
+                Bar {
+                    state: State::Published,
+                    start: ymd(2023, 3, 1),
+                    end: ymd(2023, 3, 1)
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_bars_in_progress() {
+        let p = PublicationBuilder::default()
+            .venue("Foo")
+            .submitted(ymd(2023, 1, 1))
+            .build()
+            .unwrap();
+        assert_eq!(
+            bars(&p, ymd(2024, 1, 1)),
+            [Bar {
+                state: State::Submitted,
+                start: ymd(2023, 1, 1),
+                end: ymd(2024, 1, 1)
+            }]
+        );
+
+        // The end date never makes a bar go backwards
+        assert_eq!(bars(&p, ymd(2022, 1, 1))[0].end, ymd(2023, 1, 1));
+    }
+
+    #[test]
+    fn test_axis() {
+        let m = MetadataMap::build(BTreeMap::from([(
+            String::from("a"),
+            metadata(vec![
+                PublicationBuilder::default()
+                    .venue("Foo")
+                    .submitted(ymd(2022, 6, 1))
+                    .build()
+                    .unwrap(),
+                PublicationBuilder::default()
+                    .venue("Bar")
+                    .submitted(ymd(2023, 3, 1))
+                    .rejected(ymd(2023, 4, 1))
+                    .build()
+                    .unwrap(),
+            ]),
+        )]))
+        .unwrap();
+
+        let axis = Axis::build(&m, None).unwrap();
+        assert_eq!(axis.start, ymd(2022, 1, 1));
+        assert_eq!(axis.end, ymd(2024, 1, 1));
+        assert_eq!(axis.last, ymd(2023, 4, 1));
+        assert_eq!(axis.x(ymd(2022, 1, 1)), 300.0);
+        assert_eq!(axis.x(ymd(2024, 1, 1)), 1200.0);
+        assert_eq!(
+            axis.years().collect::<Vec<_>>(),
+            [ymd(2022, 1, 1), ymd(2023, 1, 1), ymd(2024, 1, 1)]
+        );
+
+        let axis = Axis::build(&m, Some(ymd(2025, 2, 1))).unwrap();
+        assert_eq!(axis.end, ymd(2026, 1, 1));
+        assert_eq!(axis.last, ymd(2025, 2, 1));
+
+        let empty = MetadataMap::build(BTreeMap::new()).unwrap();
+        assert!(Axis::build(&empty, None).is_none());
+    }
+}
@@ -1,9 +1,25 @@
 import os
 import os.path
+import subprocess
+from typing import Any
 
 from markdown.private.utils import test_utils
 
 
+def metadata(publications: list[dict[str, str]]) -> dict[str, Any]:
+    return {
+        "title": "Foo",
+        "wordcount": "10",
+        "version": "bar",
+        "publications": publications,
+        "poetry-lines": "0",
+        "lang": "en-GB",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": ["2020"],
+    }
+
+
 class TestPublications(test_utils.ScriptTestCase):
     def test_publications(self) -> None:
         metadata = os.path.join(self.tmpdir(), "metadata.json")
@@ -74,12 +90,16 @@ th, td { border: 1px solid; padding: 5px; }
 a:link { color: black; }
 a:visited { color: black; }
 .submitted { background-color: #ffff00; }
+.shortlisted { background-color: #ffbf00; }
 .rejected { background-color: #ff6d6d; }
 .withdrawn { background-color: #ff972f; }
 .abandoned { background-color: #cccccc; }
 .accepted { background-color: #729fcf; }
+.contracted { background-color: #5983b0; }
+.paid { background-color: #b2b2ff; }
 .self_published { background-color: #158466; }
 .published { background-color: #81d41a; }
+.reprinted { background-color: #afd095; }
 </style>
 </head>
 <body>
@@ -165,6 +185,85 @@ a:visited { color: black; }
 """,  # noqa: E501
         )
 
+    def test_timeline(self) -> None:
+        metadata_file = os.path.join(self.tmpdir(), "metadata.json")
+        self.dump_json(
+            metadata_file,
+            {
+                "//foo:bar": metadata(
+                    [
+                        {
+                            "venue": "Foo",
+                            "submitted": "2022-07-02",
+                            "rejected": "2022-10-01",
+                        },
+                        {
+                            "venue": "Bar <Baz>",
+                            "submitted": "2023-01-01",
+                        },
+                    ]
+                ),
+                "//baz:quux": metadata(
+                    [
+                        {
+                            "venue": "Foo",
+                            "self-published": "2022-11-14",
+                        },
+                    ]
+                ),
+            },
+        )
+
+        outfile = os.path.join(self.tmpdir(), "out.html")
+
+        self.run_script(
+            args=[
+                metadata_file,
+                outfile,
+                "--format=timeline",
+                "--target=//foo:bar",
+                "--end-date=2023-07-02",
+            ],
+        )
+
+        out = self.load_file(outfile)
+        self.assertIn(".submitted { background-color: #ffff00; }\n", out)
+        self.assertIn("rect.submitted { fill: #ffff00; }\n", out)
+        self.assertIn('<text x="302.0" y="15.0">2022</text>\n', out)
+        self.assertIn('<text x="752.0" y="15.0">2023</text>\n', out)
+        self.assertIn('<text x="0.0" y="35.0" font-weight="bold">//foo:bar</text>\n', out)
+        self.assertIn(
+            '<rect class="submitted" x="524.4" y="43.0" width="112.2" height="14.0">'
+            "<title>//foo:bar, Foo: Submitted 2022-07-02 to 2022-10-01</title></rect>\n",
+            out,
+        )
+        self.assertIn(
+            '<rect class="rejected" x="636.6" y="43.0" width="2.0" height="14.0">'
+            "<title>//foo:bar, Foo: Rejected 2022-10-01</title></rect>\n",
+            out,
+        )
+        self.assertIn('<text x="10.0" y="75.0">Bar &lt;Baz&gt;</text>\n', out)
+        self.assertIn(
+            '<rect class="submitted" x="750.0" y="63.0" width="224.4" height="14.0">'
+            "<title>//foo:bar, Bar &lt;Baz&gt;: Submitted 2023-01-01 to 2023-07-02</title>"
+            "</rect>\n",
+            out,
+        )
+        self.assertNotIn("//baz:quux", out)
+
+    def test_unknown_target(self) -> None:
+        metadata_file = os.path.join(self.tmpdir(), "metadata.json")
+        self.dump_json(metadata_file, {})
+
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(
+                args=[
+                    metadata_file,
+                    os.path.join(self.tmpdir(), "out.html"),
+                    "--target=//foo:bar",
+                ],
+            )
+
 
 if __name__ == "__main__":
     test_utils.ScriptTestCase.main()
@@ -0,0 +1,301 @@
+use clap::{Parser, ValueEnum};
+use markdown::args;
+use markdown::include_graph::IncludeGraph;
+use markdown::json::{JsonSerializable, from_json};
+use markdown::metadata::{MetadataMap, OverrideSource};
+use markdown::report;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt::{Display, Formatter};
+use std::fs::{read_to_string, write};
+use tabled::Tabled;
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+enum Format {
+    Table,
+    Json,
+}
+
+#[derive(Parser)]
+#[command(version, about = "Report why targets in the group aren't reproducible", long_about = None)]
+struct Cli {
+    #[arg(value_parser = args::non_empty())]
+    metadata_file: String,
+
+    #[arg(value_parser = args::non_empty())]
+    out_file: String,
+
+    /// Include graph of a target in the group, used to find the chain of
+    /// includes that leads to each dirty or unversioned dep
+    #[arg(long = "include-graph")]
+    include_graphs: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum State {
+    Dirty,
+    Unversioned,
+}
+
+impl Display for State {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            State::Dirty => write!(f, "dirty"),
+            State::Unversioned => write!(f, "unversioned"),
+        }
+    }
+}
+
+/// A dep that makes a target dirty or unversioned.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+struct Cause {
+    dep: String,
+    state: State,
+    /// Includes from the target to the dep; just the two of them if the chain
+    /// isn't known
+    chain: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TargetReport {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    states: Vec<State>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    override_source: Option<OverrideSource>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    causes: Vec<Cause>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+struct Report {
+    targets: BTreeMap<String, TargetReport>,
+}
+
+impl JsonSerializable for Report {}
+
+#[derive(Debug, PartialEq, Tabled)]
+struct Row {
+    target: String,
+    state: String,
+    dep: String,
+    #[tabled(rename = "include chain")]
+    chain: String,
+}
+
+impl Report {
+    fn rows(&self) -> Vec<Row> {
+        let mut rows = Vec::new();
+        for (target, r) in &self.targets {
+            let row = |state: String, dep: &str, chain: &[String]| Row {
+                target: target.clone(),
+                state,
+                dep: String::from(dep),
+                chain: chain.join(" -> "),
+            };
+            for state in &r.states {
+                rows.push(row(state.to_string(), "", &[]));
+            }
+            if let Some(source) = r.override_source {
+                let source = match source {
+                    OverrideSource::VersionFile => "version file",
+                    OverrideSource::VersionOverride => "version override",
+                };
+                rows.push(row(format!("overridden by {source}"), "", &[]));
+            }
+            for c in &r.causes {
+                rows.push(row(format!("{} dep", c.state), &c.dep, &c.chain));
+            }
+        }
+        rows
+    }
+}
+
+fn cause(target: &str, dep: &str, state: State, graph: &IncludeGraph) -> Cause {
+    Cause {
+        dep: String::from(dep),
+        state,
+        chain: graph
+            .chain(target, dep)
+            .unwrap_or_else(|| vec![String::from(target), String::from(dep)]),
+    }
+}
+
+fn report(metadata: &MetadataMap, graph: &IncludeGraph) -> Report {
+    let mut report = Report::default();
+    for (target, m) in metadata.data() {
+        let info = m.version_info();
+        let mut r = TargetReport {
+            override_source: info.override_source,
+            ..Default::default()
+        };
+        if info.dirty {
+            r.states.push(State::Dirty);
+        }
+        if info.unversioned {
+            r.states.push(State::Unversioned);
+        }
+        r.causes.extend(
+            info.dirty_deps
+                .iter()
+                .map(|dep| cause(target, dep, State::Dirty, graph)),
+        );
+        r.causes.extend(
+            info.unversioned_deps
+                .iter()
+                .map(|dep| cause(target, dep, State::Unversioned, graph)),
+        );
+
+        if r != TargetReport::default() {
+            report.targets.insert(target.clone(), r);
+        }
+    }
+    report
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+
+    let metadata = from_json::<MetadataMap>(&read_to_string(args.metadata_file)?)?;
+    let mut graph = IncludeGraph::default();
+    for path in args.include_graphs {
+        graph.merge(from_json(&read_to_string(path)?)?);
+    }
+
+    let report = report(&metadata, &graph);
+    let out = match args.format {
+        Format::Table => {
+            if report.targets.is_empty() {
+                String::from("All targets are versioned and clean\n")
+            } else {
+                format!("{}\n", report::table(&report.rows()))
+            }
+        }
+        Format::Json => report.to_json()? + "\n",
+    };
+    write(args.out_file, out)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod group_reproducibility_test {
+    use super::{Cause, IncludeGraph, MetadataMap, OverrideSource, State, TargetReport, report};
+    use markdown::metadata::{OutputMetadata, VersionInfo};
+    use markdown_testing::{output_metadata, strings};
+    use std::collections::BTreeMap;
+
+    fn metadata(info: VersionInfo) -> OutputMetadata {
+        output_metadata().version_info(info).build().unwrap()
+    }
+
+    fn metadata_map() -> MetadataMap {
+        MetadataMap::build(BTreeMap::from([
+            (
+                String::from("a:top"),
+                metadata(VersionInfo {
+                    dirty_deps: strings(&["a:leaf"]),
+                    unversioned_deps: strings(&["b:other"]),
+                    ..Default::default()
+                }),
+            ),
+            (
+                String::from("a:middle"),
+                metadata(VersionInfo {
+                    dirty_deps: strings(&["a:leaf"]),
+                    ..Default::default()
+                }),
+            ),
+            (
+                String::from("a:leaf"),
+                metadata(VersionInfo {
+                    dirty: true,
+                    ..Default::default()
+                }),
+            ),
+            (String::from("a:clean"), metadata(VersionInfo::default())),
+            (
+                String::from("a:overridden"),
+                metadata(VersionInfo {
+                    override_source: Some(OverrideSource::VersionOverride),
+                    ..Default::default()
+                }),
+            ),
+        ]))
+        .unwrap()
+    }
+
+    fn graph() -> IncludeGraph {
+        IncludeGraph::build(BTreeMap::from([
+            (String::from("a:top"), strings(&["a:clean", "a:middle"])),
+            (String::from("a:middle"), strings(&["a:leaf"])),
+            (String::from("a:leaf"), Vec::new()),
+            (String::from("a:clean"), Vec::new()),
+        ]))
+    }
+
+    #[test]
+    fn test_report() {
+        let r = report(&metadata_map(), &graph());
+        assert_eq!(
+            r.targets.keys().collect::<Vec<_>>(),
+            ["a:leaf", "a:middle", "a:overridden", "a:top"]
+        );
+        assert_eq!(
+            r.targets["a:leaf"],
+            TargetReport {
+                states: vec![State::Dirty],
+                ..Default::default()
+            }
+        );
+        assert_eq!(
+            r.targets["a:top"].causes,
+            [
+                Cause {
+                    dep: String::from("a:leaf"),
+                    state: State::Dirty,
+                    chain: strings(&["a:top", "a:middle", "a:leaf"]),
+                },
+                // Not in the include graph
+                Cause {
+                    dep: String::from("b:other"),
+                    state: State::Unversioned,
+                    chain: strings(&["a:top", "b:other"]),
+                },
+            ]
+        );
+        assert_eq!(
+            r.targets["a:overridden"].override_source,
+            Some(OverrideSource::VersionOverride)
+        );
+    }
+
+    #[test]
+    fn test_rows() {
+        let rows: Vec<[String; 4]> = report(&metadata_map(), &graph())
+            .rows()
+            .into_iter()
+            .map(|r| [r.target, r.state, r.dep, r.chain])
+            .collect();
+        let expected: Vec<[String; 4]> = [
+            ["a:leaf", "dirty", "", ""],
+            ["a:middle", "dirty dep", "a:leaf", "a:middle -> a:leaf"],
+            ["a:overridden", "overridden by version override", "", ""],
+            [
+                "a:top",
+                "dirty dep",
+                "a:leaf",
+                "a:top -> a:middle -> a:leaf",
+            ],
+            ["a:top", "unversioned dep", "b:other", "a:top -> b:other"],
+        ]
+        .map(|r| r.map(String::from))
+        .into();
+        assert_eq!(rows, expected);
+    }
+}
@@ -0,0 +1,102 @@
+import os
+import os.path
+from collections.abc import Sequence
+from typing import Any
+
+from markdown.private.utils import test_utils
+
+
+def metadata(version_info: dict[str, Any]) -> dict[str, Any]:
+    m: dict[str, Any] = {
+        "wordcount": "10",
+        "poetry-lines": "0",
+        "version": "foo",
+        "lang": "en-GB",
+        "repo": "foo",
+        "source-hash": "1",
+        "parsed-dates": [],
+    }
+    if version_info:
+        m["version-info"] = version_info
+    return m
+
+
+DATA = {
+    "a:top": metadata({"dirty-deps": ["a:leaf"]}),
+    "a:middle": metadata({}),
+    "a:leaf": metadata({"dirty": True}),
+    "b:other": metadata({"override-source": "version-override"}),
+}
+
+INCLUDE_GRAPH = {
+    "a:top": ["a:middle"],
+    "a:middle": ["a:leaf"],
+    "a:leaf": [],
+}
+
+
+class TestReproducibility(test_utils.ScriptTestCase):
+    maxDiff = None
+
+    def run_report(self, data: dict[str, Any], args: Sequence[str]) -> str:
+        metadata_file = os.path.join(self.tmpdir(), "metadata.json")
+        self.dump_json(metadata_file, data)
+        graph_file = os.path.join(self.tmpdir(), "include_graph.json")
+        self.dump_json(graph_file, INCLUDE_GRAPH)
+        out_file = os.path.join(self.tmpdir(), "out")
+
+        self.run_script(
+            args=[metadata_file, out_file, "--include-graph", graph_file, *args],
+        )
+        return self.load_file(out_file)
+
+    def test_table(self) -> None:
+        self.assertEqual(
+            self.run_report(DATA, []),
+            """\
+| target  | state                          | dep    | include chain               |
+|---------|--------------------------------|--------|-----------------------------|
+| a:leaf  | dirty                          |        |                             |
+| a:top   | dirty dep                      | a:leaf | a:top -> a:middle -> a:leaf |
+| b:other | overridden by version override |        |                             |
+""",
+        )
+
+    def test_clean(self) -> None:
+        self.assertEqual(
+            self.run_report({"a:middle": metadata({})}, []),
+            "All targets are versioned and clean\n",
+        )
+
+    def test_json(self) -> None:
+        self.assertEqual(
+            self.run_report(DATA, ["--format=json"]),
+            """{
+  "a:leaf": {
+    "states": [
+      "dirty"
+    ]
+  },
+  "a:top": {
+    "causes": [
+      {
+        "chain": [
+          "a:top",
+          "a:middle",
+          "a:leaf"
+        ],
+        "dep": "a:leaf",
+        "state": "dirty"
+      }
+    ]
+  },
+  "b:other": {
+    "override-source": "version-override"
+  }
+}
+""",
+        )
+
+
+if __name__ == "__main__":
+    test_utils.ScriptTestCase.main()
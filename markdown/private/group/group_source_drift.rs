@@ -0,0 +1,557 @@
+use clap::Parser;
+use markdown::args;
+use markdown::hashing::{DepHash, source_hash};
+use markdown::json::{JsonSerializable, from_json};
+use markdown::metadata::{HashAlgorithm, MetadataMap, OutputMetadata, SourceHash};
+use markdown::report::{self, Format};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::env;
+use std::error::Error;
+use std::fs::{read, read_to_string};
+use std::path::Path;
+use tabled::Tabled;
+
+#[derive(Parser)]
+#[command(version, about = "Check whether built targets still match their sources", long_about = None)]
+struct Cli {
+    #[arg(value_parser = args::non_empty())]
+    metadata_file: String,
+
+    /// Workspace root; defaults to the workspace `bazel run` was called from,
+    /// or the current directory
+    #[arg(long)]
+    root: Option<String>,
+
+    /// Read metadata_file as the metadata of this one target, rather than a
+    /// map of targets to metadata
+    #[arg(long)]
+    target: Option<String>,
+
+    /// Metadata map of targets outside the group that targets in it depend
+    /// on, used to rehash those deps
+    #[arg(long = "deps-metadata-file")]
+    deps_metadata_files: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t = Format::Table)]
+    format: Format,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+enum Status {
+    Unchanged,
+    Changed,
+    Error,
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+struct TargetDrift {
+    status: Status,
+    built: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    current: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+    /// Deps that changed, when the current hash can't be computed
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    changed_deps: Vec<String>,
+}
+
+#[derive(Debug, Default, PartialEq, Serialize)]
+#[serde(transparent)]
+struct Report {
+    targets: BTreeMap<String, TargetDrift>,
+}
+
+impl JsonSerializable for Report {}
+
+impl Report {
+    fn drifted(&self) -> bool {
+        self.targets.values().any(|t| t.status != Status::Unchanged)
+    }
+}
+
+#[derive(Serialize, Tabled)]
+struct Row {
+    target: String,
+    status: String,
+    details: String,
+}
+
+/// A target's current hash, or the deps that changed if it can't be
+/// recomputed because it has generated files.
+#[derive(Clone, Debug, PartialEq)]
+enum Rehash {
+    Hash(SourceHash),
+    DepsChanged(Vec<String>),
+}
+
+/// Whether a current hash matches the one a target was built with, comparing
+/// the stronger hash where the target was built with one.
+fn matches(built: &OutputMetadata, current: &SourceHash) -> bool {
+    match built.content_hash() {
+        Some(h) => *h == current.content_hash,
+        None => built.source_hash() == current.source_hash,
+    }
+}
+
+/// Recomputes source hashes from files under a root, the same way the
+/// source_hash tool does when building. Each target is rehashed from the src,
+/// deps and inputs recorded in its metadata, and each dep is hashed before the
+/// targets that depend on it.
+///
+/// Generated files aren't in the working tree, so a target with any is only
+/// checked through its deps: it has its built hash if none of them changed.
+/// A generated src, such as a collection's, is made from its deps, so this is
+/// exact for those; changes to the target's other files aren't seen.
+struct Rehasher<'a, R: Fn(&str) -> std::io::Result<Vec<u8>>> {
+    metadata: &'a BTreeMap<String, OutputMetadata>,
+    read: R,
+    hashes: BTreeMap<String, Result<Rehash, String>>,
+    in_progress: BTreeSet<String>,
+}
+
+impl<'a, R: Fn(&str) -> std::io::Result<Vec<u8>>> Rehasher<'a, R> {
+    fn new(metadata: &'a BTreeMap<String, OutputMetadata>, read: R) -> Self {
+        Rehasher {
+            metadata,
+            read,
+            hashes: BTreeMap::new(),
+            in_progress: BTreeSet::new(),
+        }
+    }
+
+    fn hash(&mut self, label: &str) -> Result<Rehash, String> {
+        if let Some(h) = self.hashes.get(label) {
+            return h.clone();
+        }
+        if !self.in_progress.insert(String::from(label)) {
+            return Err(format!("{label} depends on itself"));
+        }
+        let h = self.compute(label);
+        self.in_progress.remove(label);
+        self.hashes.insert(String::from(label), h.clone());
+        h
+    }
+
+    fn compute(&mut self, label: &str) -> Result<Rehash, String> {
+        let metadata = self.metadata;
+        let built = metadata
+            .get(label)
+            .ok_or_else(|| format!("no metadata for {label}"))?;
+        let path = built
+            .hash_src()
+            .ok_or_else(|| format!("{label} was built without recording its source"))?;
+
+        let mut deps = BTreeMap::new();
+        let mut changed_deps = Vec::new();
+        for dep in built.hash_deps() {
+            match self
+                .hash(dep)
+                .map_err(|e| format!("dep {dep} of {label}: {e}"))?
+            {
+                Rehash::Hash(h) => {
+                    if !metadata.get(dep).is_some_and(|m| matches(m, &h)) {
+                        changed_deps.push(dep.clone());
+                    }
+                    deps.insert(dep.clone(), DepHash::from(h));
+                }
+                Rehash::DepsChanged(_) => changed_deps.push(dep.clone()),
+            }
+        }
+
+        if !built.hash_generated().is_empty() {
+            if !changed_deps.is_empty() {
+                return Ok(Rehash::DepsChanged(changed_deps));
+            }
+            let content_hash = built
+                .content_hash()
+                .cloned()
+                .ok_or_else(|| format!("{label} was built without a content hash"))?;
+            return SourceHash::build(
+                built.source_hash(),
+                content_hash,
+                built.hash_deps().clone(),
+                built.hash_inputs().clone(),
+            )
+            .map(Rehash::Hash)
+            .map_err(|e| e.to_string());
+        }
+        // A dep whose hash can't be recomputed has changed, so this has too.
+        if deps.len() < built.hash_deps().len() {
+            return Ok(Rehash::DepsChanged(changed_deps));
+        }
+
+        let src = (self.read)(path)
+            .map_err(|e| format!("can't read {path}: {e}"))
+            .and_then(|b| String::from_utf8(b).map_err(|e| format!("can't read {path}: {e}")))?;
+        let mut inputs = BTreeMap::new();
+        for name in built.hash_inputs() {
+            let contents = (self.read)(name).map_err(|e| format!("can't read {name}: {e}"))?;
+            inputs.insert(name.clone(), contents);
+        }
+        let algorithm = built
+            .content_hash()
+            .map_or(HashAlgorithm::default(), |h| h.algorithm);
+
+        source_hash(&deps, &src, &inputs, algorithm)
+            .map(Rehash::Hash)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// Compares each target in metadata with its rehashed source. Targets in
+/// deps_metadata are only rehashed as deps, and not reported.
+fn drift<R: Fn(&str) -> std::io::Result<Vec<u8>>>(
+    metadata: &BTreeMap<String, OutputMetadata>,
+    deps_metadata: &BTreeMap<String, OutputMetadata>,
+    read: R,
+) -> Report {
+    let mut all = deps_metadata.clone();
+    all.extend(metadata.clone());
+    let mut rehasher = Rehasher::new(&all, read);
+    let mut report = Report::default();
+    for (target, m) in metadata {
+        let built = match m.content_hash() {
+            Some(h) => h.to_string(),
+            None => String::from(m.source_hash()),
+        };
+        let t = match rehasher.hash(target) {
+            Ok(Rehash::Hash(h)) => TargetDrift {
+                status: if matches(m, &h) {
+                    Status::Unchanged
+                } else {
+                    Status::Changed
+                },
+                built,
+                current: Some(match m.content_hash() {
+                    Some(_) => h.content_hash.to_string(),
+                    None => h.source_hash,
+                }),
+                error: None,
+                changed_deps: Vec::new(),
+            },
+            Ok(Rehash::DepsChanged(deps)) => TargetDrift {
+                status: Status::Changed,
+                built,
+                current: None,
+                error: None,
+                changed_deps: deps,
+            },
+            Err(e) => TargetDrift {
+                status: Status::Error,
+                built,
+                current: None,
+                error: Some(e),
+                changed_deps: Vec::new(),
+            },
+        };
+        report.targets.insert(target.clone(), t);
+    }
+    report
+}
+
+fn rows(report: &Report) -> Vec<Row> {
+    report
+        .targets
+        .iter()
+        .map(|(target, t)| Row {
+            target: target.clone(),
+            status: match t.status {
+                Status::Unchanged => String::from("unchanged"),
+                Status::Changed => String::from("CHANGED"),
+                Status::Error => String::from("ERROR"),
+            },
+            details: match t.status {
+                Status::Unchanged => String::new(),
+                Status::Changed => match &t.current {
+                    Some(current) => format!("built {}, now {current}", t.built),
+                    None => format!(
+                        "built {}, deps changed: {}",
+                        t.built,
+                        t.changed_deps.join(", ")
+                    ),
+                },
+                Status::Error => t.error.clone().unwrap_or_default(),
+            },
+        })
+        .collect()
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+
+    let contents = read_to_string(&args.metadata_file)?;
+    let metadata = match &args.target {
+        Some(target) => BTreeMap::from([(target.clone(), from_json::<OutputMetadata>(&contents)?)]),
+        None => from_json::<MetadataMap>(&contents)?.data().clone(),
+    };
+    let mut deps_metadata = BTreeMap::new();
+    for path in &args.deps_metadata_files {
+        deps_metadata.extend(
+            from_json::<MetadataMap>(&read_to_string(path)?)?
+                .data()
+                .clone(),
+        );
+    }
+    let root = args
+        .root
+        .or_else(|| env::var("BUILD_WORKSPACE_DIRECTORY").ok())
+        .unwrap_or_else(|| String::from("."));
+
+    let report = drift(&metadata, &deps_metadata, |path| {
+        read(Path::new(&root).join(path))
+    });
+    match args.format {
+        Format::Table => println!("{}", report::table(&rows(&report))),
+        Format::Csv => print!("{}", report::csv(&rows(&report))?),
+        Format::Json => println!("{}", report.to_json()?),
+    }
+
+    if report.drifted() {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod group_source_drift_test {
+    use super::{BTreeMap, OutputMetadata, Status, drift};
+    use markdown::hashing::{DepHash, source_hash};
+    use markdown::metadata::{HashAlgorithm, SourceHash};
+    use markdown_testing::output_metadata;
+
+    fn hash(
+        src: &str,
+        deps: &[(&str, &SourceHash)],
+        inputs: &[(&str, &str)],
+        algorithm: HashAlgorithm,
+    ) -> SourceHash {
+        source_hash(
+            &deps
+                .iter()
+                .map(|(l, h)| (String::from(*l), DepHash::from((*h).clone())))
+                .collect(),
+            src,
+            &inputs
+                .iter()
+                .map(|(n, c)| (String::from(*n), c.as_bytes().to_vec()))
+                .collect(),
+            algorithm,
+        )
+        .unwrap()
+    }
+
+    fn metadata(h: &SourceHash, src: &str, with_content_hash: bool) -> OutputMetadata {
+        let mut b = output_metadata();
+        b.source_hash(h.source_hash.clone())
+            .hash_src(String::from(src))
+            .hash_deps(h.hash_deps.clone())
+            .hash_inputs(h.hash_inputs.clone());
+        if with_content_hash {
+            b.content_hash(h.content_hash.clone());
+        }
+        b.build().unwrap()
+    }
+
+    fn generated(h: &SourceHash, src: &str, names: &[&str]) -> OutputMetadata {
+        let mut b = output_metadata();
+        b.source_hash(h.source_hash.clone())
+            .content_hash(h.content_hash.clone())
+            .hash_src(String::from(src))
+            .hash_deps(h.hash_deps.clone())
+            .hash_inputs(h.hash_inputs.clone())
+            .hash_generated(names.iter().map(|n| String::from(*n)).collect::<Vec<_>>());
+        b.build().unwrap()
+    }
+
+    fn files(f: &[(&str, &str)]) -> BTreeMap<String, String> {
+        f.iter()
+            .map(|(k, v)| (String::from(*k), String::from(*v)))
+            .collect()
+    }
+
+    fn read(tree: &BTreeMap<String, String>, path: &str) -> std::io::Result<Vec<u8>> {
+        tree.get(path)
+            .map(|s| s.as_bytes().to_vec())
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))
+    }
+
+    #[test]
+    fn test_drift() {
+        let sha256 = HashAlgorithm::Sha256;
+        let leaf = hash("leaf\n", &[], &[("a/img.jpg", "1")], sha256);
+        let top = hash("!include :leaf\n", &[("a:leaf", &leaf)], &[], sha256);
+        let other = hash("other\n", &[], &[], sha256);
+        let built = BTreeMap::from([
+            (String::from("a:leaf"), metadata(&leaf, "a/leaf.md", true)),
+            (String::from("a:top"), metadata(&top, "a/top.md", true)),
+            (
+                String::from("b:other"),
+                metadata(&other, "b/renamed.md", false),
+            ),
+            (String::from("b:gone"), metadata(&other, "b/gone.md", true)),
+        ]);
+        let none = BTreeMap::new();
+
+        let tree = files(&[
+            ("a/leaf.md", "leaf\n"),
+            ("a/img.jpg", "1"),
+            ("a/top.md", "!include :leaf\n"),
+            ("b/renamed.md", "other\n"),
+        ]);
+
+        let r = drift(&built, &none, |p| read(&tree, p));
+        assert_eq!(r.targets["a:leaf"].status, Status::Unchanged);
+        assert_eq!(r.targets["a:top"].status, Status::Unchanged);
+        assert_eq!(r.targets["b:other"].status, Status::Unchanged);
+        assert_eq!(
+            r.targets["b:other"].built, other.source_hash,
+            "compares md5 without a content hash"
+        );
+        assert_eq!(r.targets["b:gone"].status, Status::Error);
+        assert!(r.drifted());
+
+        // Changing the image changes the image's includer too.
+        let mut changed = tree.clone();
+        changed.insert(String::from("a/img.jpg"), String::from("2"));
+        let r = drift(&built, &none, |p| read(&changed, p));
+        assert_eq!(r.targets["a:leaf"].status, Status::Changed);
+        assert_eq!(r.targets["a:top"].status, Status::Changed);
+        assert_eq!(r.targets["b:other"].status, Status::Unchanged);
+
+        let clean = BTreeMap::from([(String::from("a:leaf"), metadata(&leaf, "a/leaf.md", true))]);
+        assert!(!drift(&clean, &none, |p| read(&tree, p)).drifted());
+    }
+
+    #[test]
+    fn test_drift_unused_dep() {
+        // Deps are hashed whether or not the src includes them.
+        let dep = hash("dep\n", &[], &[], HashAlgorithm::Sha256);
+        let top = hash("top\n", &[("a:dep", &dep)], &[], HashAlgorithm::Sha256);
+        let built = BTreeMap::from([
+            (String::from("a:dep"), metadata(&dep, "a/dep.md", true)),
+            (String::from("a:top"), metadata(&top, "a/top.md", true)),
+        ]);
+        let tree = files(&[("a/dep.md", "dep\n"), ("a/top.md", "top\n")]);
+
+        let r = drift(&built, &BTreeMap::new(), |p| read(&tree, p));
+        assert_eq!(r.targets["a:top"].status, Status::Unchanged);
+
+        let mut changed = tree.clone();
+        changed.insert(String::from("a/dep.md"), String::from("changed\n"));
+        let r = drift(&built, &BTreeMap::new(), |p| read(&changed, p));
+        assert_eq!(r.targets["a:top"].status, Status::Changed);
+    }
+
+    #[test]
+    fn test_drift_out_of_group_dep() {
+        let dep = hash("dep\n", &[], &[("b/img.jpg", "1")], HashAlgorithm::Blake3);
+        let top = hash(
+            "!include //b:dep\n",
+            &[("b:dep", &dep)],
+            &[],
+            HashAlgorithm::Sha256,
+        );
+        let built = BTreeMap::from([(String::from("a:top"), metadata(&top, "a/top.md", true))]);
+        let deps = BTreeMap::from([(String::from("b:dep"), metadata(&dep, "b/dep.md", true))]);
+        let tree = files(&[
+            ("a/top.md", "!include //b:dep\n"),
+            ("b/dep.md", "dep\n"),
+            ("b/img.jpg", "1"),
+        ]);
+
+        // The dep is rehashed with its own inputs and algorithm, but isn't
+        // reported.
+        let r = drift(&built, &deps, |p| read(&tree, p));
+        assert_eq!(r.targets.len(), 1);
+        assert_eq!(r.targets["a:top"].status, Status::Unchanged);
+
+        let mut changed = tree.clone();
+        changed.insert(String::from("b/img.jpg"), String::from("2"));
+        let r = drift(&built, &deps, |p| read(&changed, p));
+        assert_eq!(r.targets["a:top"].status, Status::Changed);
+
+        let r = drift(&built, &BTreeMap::new(), |p| read(&tree, p));
+        assert_eq!(r.targets["a:top"].status, Status::Error);
+        assert_eq!(
+            r.targets["a:top"].error.as_deref(),
+            Some("dep b:dep of a:top: no metadata for b:dep")
+        );
+    }
+
+    #[test]
+    fn test_drift_generated() {
+        let sha256 = HashAlgorithm::Sha256;
+        let piece = hash("piece\n", &[], &[], sha256);
+        let collection = hash("# Piece\n", &[("a:piece", &piece)], &[], sha256);
+        let cover = hash("cover\n", &[], &[("a/gen.jpg", "1")], sha256);
+        let top = hash("top\n", &[("a:collection", &collection)], &[], sha256);
+        let built = BTreeMap::from([
+            (
+                String::from("a:piece"),
+                metadata(&piece, "a/piece.md", true),
+            ),
+            (
+                String::from("a:collection"),
+                generated(&collection, "a/collection_src.md", &["a/collection_src.md"]),
+            ),
+            (
+                String::from("a:cover"),
+                generated(&cover, "a/cover.md", &["a/gen.jpg"]),
+            ),
+            (String::from("a:top"), metadata(&top, "a/top.md", true)),
+        ]);
+        // Generated files aren't in the working tree.
+        let tree = files(&[
+            ("a/piece.md", "piece\n"),
+            ("a/cover.md", "cover\n"),
+            ("a/top.md", "top\n"),
+        ]);
+
+        let r = drift(&built, &BTreeMap::new(), |p| read(&tree, p));
+        assert!(!r.drifted(), "{r:?}");
+
+        let mut changed = tree.clone();
+        changed.insert(String::from("a/piece.md"), String::from("changed\n"));
+        let r = drift(&built, &BTreeMap::new(), |p| read(&changed, p));
+        assert_eq!(r.targets["a:piece"].status, Status::Changed);
+        assert_eq!(r.targets["a:collection"].status, Status::Changed);
+        assert_eq!(r.targets["a:collection"].current, None);
+        assert_eq!(r.targets["a:collection"].changed_deps, ["a:piece"]);
+        // Includers of a collection that changed have changed too.
+        assert_eq!(r.targets["a:top"].status, Status::Changed);
+        assert_eq!(r.targets["a:top"].changed_deps, ["a:collection"]);
+        assert_eq!(r.targets["a:cover"].status, Status::Unchanged);
+    }
+
+    #[test]
+    fn test_drift_errors() {
+        let h = hash("foo\n", &[], &[], HashAlgorithm::Sha256);
+        let tree = files(&[("a/a.md", "foo\n")]);
+
+        let mut unrecorded = output_metadata();
+        unrecorded.source_hash(h.source_hash.clone());
+        let built = BTreeMap::from([(String::from("a:a"), unrecorded.build().unwrap())]);
+        let r = drift(&built, &BTreeMap::new(), |p| read(&tree, p));
+        assert_eq!(
+            r.targets["a:a"].error.as_deref(),
+            Some("a:a was built without recording its source")
+        );
+
+        // Only possible with hand-edited metadata
+        let mut cycle = output_metadata();
+        cycle
+            .source_hash(h.source_hash.clone())
+            .hash_src(String::from("a/a.md"))
+            .hash_deps(vec![String::from("a:a")]);
+        let built = BTreeMap::from([(String::from("a:a"), cycle.build().unwrap())]);
+        let r = drift(&built, &BTreeMap::new(), |p| read(&tree, p));
+        assert_eq!(
+            r.targets["a:a"].error.as_deref(),
+            Some("dep a:a of a:a: a:a depends on itself")
+        );
+    }
+}
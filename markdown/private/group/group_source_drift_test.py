@@ -0,0 +1,96 @@
+import json
+import os
+import os.path
+import subprocess
+from collections.abc import Sequence
+
+from markdown.private.utils import test_utils
+
+
+def metadata(
+    source_hash: str,
+    src: str = "a/doc.md",
+    deps: Sequence[str] = (),
+) -> dict[str, str | list[str]]:
+    return {
+        "wordcount": "1",
+        "poetry-lines": "0",
+        "lang": "en-GB",
+        "version": "1",
+        "repo": "foo",
+        "source-hash": source_hash,
+        "hash-src": src,
+        "hash-deps": list(deps),
+        "parsed-dates": [],
+    }
+
+
+class TestSourceDrift(test_utils.ScriptTestCase):
+    maxDiff = None
+
+    def run_drift(
+        self,
+        data: dict[str, dict[str, str | list[str]]],
+        args: Sequence[str],
+    ) -> str:
+        root = os.path.join(self.tmpdir(), "root")
+        os.makedirs(os.path.join(root, "a"), exist_ok=True)
+        self.dump_file(os.path.join(root, "a", "doc.md"), "foo\n")
+        os.makedirs(os.path.join(root, "b"), exist_ok=True)
+        self.dump_file(os.path.join(root, "b", "dep.md"), "bar\n")
+
+        metadata_file = os.path.join(self.tmpdir(), "metadata.json")
+        self.dump_json(metadata_file, data)
+
+        return self.run_script(args=[metadata_file, "--root", root, *args]).stdout
+
+    def test_unchanged(self) -> None:
+        # md5 of "{\n}\nfoo\n"
+        self.assertEqual(
+            self.run_drift(
+                {"a:doc": metadata("a68f5c567ab7e8d77ec145fd1dce2eff")},
+                [],
+            ),
+            """| target | status    | details |
+|--------|-----------|---------|
+| a:doc  | unchanged |         |
+""",
+        )
+
+    def test_changed(self) -> None:
+        with self.assertRaises(subprocess.CalledProcessError) as e:
+            self.run_drift({"a:doc": metadata("1")}, ["--format=json"])
+        self.assertEqual(e.exception.returncode, 1)
+        self.assertEqual(
+            json.loads(e.exception.stdout),
+            {
+                "a:doc": {
+                    "built": "1",
+                    "current": "a68f5c567ab7e8d77ec145fd1dce2eff",
+                    "status": "changed",
+                },
+            },
+        )
+
+    def test_deps_metadata_file(self) -> None:
+        deps_metadata_file = os.path.join(self.tmpdir(), "deps_metadata.json")
+        # md5 of "{\n}\nbar\n"
+        self.dump_json(
+            deps_metadata_file,
+            {"b:dep": metadata("386716ad56448b56b61fab198e2cc609", "b/dep.md")},
+        )
+        # md5 of "{\nb:dep 386716ad56448b56b61fab198e2cc609\n}\nfoo\n"
+        self.assertEqual(
+            self.run_drift(
+                {"a:doc": metadata("1dfc3b3d037df0ec762109f9bfc5818d", deps=["b:dep"])},
+                ["--deps-metadata-file=" + deps_metadata_file],
+            ),
+            """| target | status    | details |
+|--------|-----------|---------|
+| a:doc  | unchanged |         |
+""",
+        )
+
+
+if __name__ == "__main__":
+    test_utils.ScriptTestCase.main()
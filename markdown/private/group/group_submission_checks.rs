@@ -0,0 +1,366 @@
+use clap::Parser;
+use markdown::args;
+use markdown::json::from_json;
+use markdown::metadata::MetadataMap;
+use markdown::problems::{Diagnostic, Problems};
+use markdown::publications::Publication;
+use markdown::venues::VenueRegistry;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs::{read_to_string, write};
+
+const SIMULTANEOUS_RULE: &str = "simultaneous-submission";
+const RESUBMISSION_RULE: &str = "resubmission";
+const VENUE_LOAD_RULE: &str = "venue-load";
+
+#[derive(Parser)]
+#[command(version, about = "Check submission policies across the group", long_about = None)]
+struct Cli {
+    #[arg(value_parser = args::non_empty())]
+    metadata_file: String,
+
+    #[arg(value_parser = args::non_empty())]
+    out_file: String,
+
+    #[arg(long)]
+    #[arg(value_parser = args::non_empty())]
+    venues_file: Option<String>,
+
+    /// Maximum number of pieces that can be pending at a single venue
+    #[arg(long)]
+    max_per_venue: Option<usize>,
+}
+
+fn pending(p: &Publication) -> bool {
+    p.submitted().is_some() && p.active() && p.accepted().is_none() && p.published().is_none()
+}
+
+fn check_simultaneous(
+    target: &str,
+    ps: &[Publication],
+    registry: &VenueRegistry,
+) -> Vec<Diagnostic> {
+    let pending: Vec<&Publication> = ps.iter().filter(|p| pending(p)).collect();
+    if pending.len() < 2 {
+        return Vec::new();
+    }
+
+    let mut problems = Vec::new();
+    for p in &pending {
+        let Some(venue) = registry.get(p.venue()) else {
+            continue;
+        };
+        if venue.simultaneous_submissions() == Some(false) {
+            let others: Vec<String> = pending
+                .iter()
+                .filter(|o| !std::ptr::eq(**o, *p))
+                .map(|o| format!("'{}'", o.venue()))
+                .collect();
+            problems.push(
+                Diagnostic::new(&format!(
+                    "{target}: submitted to '{}', which doesn't accept simultaneous \
+submissions, while also submitted to {}",
+                    p.venue(),
+                    others.join(", ")
+                ))
+                .with_file(target)
+                .with_rule(SIMULTANEOUS_RULE),
+            );
+        }
+    }
+    problems
+}
+
+fn check_resubmissions(target: &str, ps: &[Publication]) -> Vec<Diagnostic> {
+    let mut problems = Vec::new();
+    for p in ps {
+        let Some(submitted) = p.submitted() else {
+            continue;
+        };
+        if let Some(rejected) = ps
+            .iter()
+            .filter(|o| !std::ptr::eq(*o, p) && o.venue() == p.venue())
+            .filter_map(|o| o.rejected())
+            .filter(|r| *r <= submitted)
+            .min()
+        {
+            problems.push(
+                Diagnostic::new(&format!(
+                    "{target}: resubmitted to '{}' on {submitted}, which already rejected it \
+on {rejected}",
+                    p.venue()
+                ))
+                .with_file(target)
+                .with_rule(RESUBMISSION_RULE),
+            );
+        }
+    }
+    problems
+}
+
+fn check_venue_load(metadata: &MetadataMap, max_per_venue: usize) -> Vec<Diagnostic> {
+    let mut venues: BTreeMap<&str, Vec<&str>> = BTreeMap::new();
+    for (target, m) in metadata.data() {
+        for p in m.publications().publications() {
+            if pending(p) {
+                venues.entry(p.venue()).or_default().push(target);
+            }
+        }
+    }
+
+    venues
+        .into_iter()
+        .filter(|(_, targets)| targets.len() > max_per_venue)
+        .map(|(venue, targets)| {
+            Diagnostic::new(&format!(
+                "'{venue}' has {} pieces pending, more than the maximum of {max_per_venue}: {}",
+                targets.len(),
+                targets.join(", ")
+            ))
+            .with_rule(VENUE_LOAD_RULE)
+        })
+        .collect()
+}
+
+fn check(
+    metadata: &MetadataMap,
+    registry: Option<&VenueRegistry>,
+    max_per_venue: Option<usize>,
+) -> Vec<Diagnostic> {
+    let mut problems = Vec::new();
+    for (target, m) in metadata.data() {
+        let ps = m.publications().publications();
+        if let Some(registry) = registry {
+            problems.extend(check_simultaneous(target, ps, registry));
+        }
+        problems.extend(check_resubmissions(target, ps));
+    }
+    if let Some(max_per_venue) = max_per_venue {
+        problems.extend(check_venue_load(metadata, max_per_venue));
+    }
+    problems
+}
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let args = Cli::parse();
+
+    let mut metadata = from_json::<MetadataMap>(&read_to_string(args.metadata_file)?)?;
+    let registry = match args.venues_file {
+        Some(path) => Some(from_json::<VenueRegistry>(&read_to_string(path)?)?),
+        None => None,
+    };
+    if let Some(registry) = &registry {
+        metadata = metadata.canonicalise_venues(registry)?;
+    }
+
+    let mut problems = Problems::new("submission checks failed");
+    for p in check(&metadata, registry.as_ref(), args.max_per_venue) {
+        problems.push_diagnostic(p);
+    }
+    problems.check();
+
+    write(args.out_file, "OK\n")?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod group_submission_checks_test {
+    use super::{
+        BTreeMap, Diagnostic, MetadataMap, Publication, VenueRegistry, check, check_resubmissions,
+        check_simultaneous, check_venue_load,
+    };
+    use chrono::NaiveDate;
+    use markdown::metadata::OutputMetadata;
+    use markdown::publications::{PublicationBuilder, Publications};
+    use markdown::venues::Venue;
+    use markdown_testing::{output_metadata, ymd};
+
+    fn submitted(venue: &str, date: NaiveDate) -> Publication {
+        PublicationBuilder::default()
+            .venue(venue)
+            .submitted(date)
+            .build()
+            .unwrap()
+    }
+
+    fn rejected(venue: &str, s: NaiveDate, r: NaiveDate) -> Publication {
+        PublicationBuilder::default()
+            .venue(venue)
+            .submitted(s)
+            .rejected(r)
+            .build()
+            .unwrap()
+    }
+
+    fn metadata(ps: Vec<Publication>) -> OutputMetadata {
+        output_metadata()
+            .publications(Publications::build(ps).unwrap())
+            .build()
+            .unwrap()
+    }
+
+    fn rules(ds: &[Diagnostic]) -> Vec<(Option<&str>, Option<&str>, &str)> {
+        ds.iter().map(|d| (d.rule(), d.file(), d.msg())).collect()
+    }
+
+    fn registry() -> VenueRegistry {
+        VenueRegistry::build([
+            Venue::build("Foo", Vec::new(), None, None, Some(false), None).unwrap(),
+            Venue::build("Bar", Vec::new(), None, None, Some(true), None).unwrap(),
+            Venue::build("Baz", Vec::new(), None, None, None, None).unwrap(),
+        ])
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_simultaneous() {
+        let r = registry();
+
+        // Only one pending
+        assert!(
+            check_simultaneous(
+                "a",
+                &[
+                    submitted("Foo", ymd(2023, 1, 1)),
+                    rejected("Bar", ymd(2022, 1, 1), ymd(2022, 2, 1))
+                ],
+                &r
+            )
+            .is_empty()
+        );
+
+        // Both venues accept simultaneous submissions, or don't say
+        assert!(
+            check_simultaneous(
+                "a",
+                &[
+                    submitted("Bar", ymd(2023, 1, 1)),
+                    submitted("Baz", ymd(2023, 1, 2))
+                ],
+                &r
+            )
+            .is_empty()
+        );
+
+        assert_eq!(
+            rules(&check_simultaneous(
+                "a",
+                &[
+                    submitted("Foo", ymd(2023, 1, 1)),
+                    submitted("Bar", ymd(2023, 1, 2)),
+                    submitted("Baz", ymd(2023, 1, 3))
+                ],
+                &r
+            )),
+            [(
+                Some("simultaneous-submission"),
+                Some("a"),
+                "a: submitted to 'Foo', which doesn't accept simultaneous submissions, while \
+also submitted to 'Bar', 'Baz'"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_check_resubmissions() {
+        assert!(
+            check_resubmissions(
+                "a",
+                &[
+                    rejected("Foo", ymd(2022, 1, 1), ymd(2022, 2, 1)),
+                    submitted("Bar", ymd(2023, 1, 1))
+                ]
+            )
+            .is_empty()
+        );
+
+        assert_eq!(
+            rules(&check_resubmissions(
+                "a",
+                &[
+                    rejected("Foo", ymd(2022, 1, 1), ymd(2022, 2, 1)),
+                    submitted("Foo", ymd(2023, 1, 1))
+                ]
+            )),
+            [(
+                Some("resubmission"),
+                Some("a"),
+                "a: resubmitted to 'Foo' on 2023-01-01, which already rejected it on 2022-02-01"
+            )]
+        );
+
+        // A rejection on the day of submission isn't a resubmission
+        assert!(
+            check_resubmissions("a", &[rejected("Foo", ymd(2022, 1, 1), ymd(2022, 1, 1))])
+                .is_empty()
+        );
+        assert_eq!(
+            check_resubmissions(
+                "a",
+                &[
+                    rejected("Foo", ymd(2022, 1, 1), ymd(2022, 1, 1)),
+                    rejected("Foo", ymd(2022, 1, 1), ymd(2022, 1, 2))
+                ]
+            )
+            .iter()
+            .map(Diagnostic::msg)
+            .collect::<Vec<_>>(),
+            ["a: resubmitted to 'Foo' on 2022-01-01, which already rejected it on 2022-01-01"]
+        );
+    }
+
+    #[test]
+    fn test_check_venue_load() {
+        let m = MetadataMap::build(BTreeMap::from([
+            (
+                String::from("a"),
+                metadata(vec![submitted("Foo", ymd(2023, 1, 1))]),
+            ),
+            (
+                String::from("b"),
+                metadata(vec![submitted("Foo", ymd(2023, 1, 2))]),
+            ),
+            (
+                String::from("c"),
+                metadata(vec![rejected("Foo", ymd(2023, 1, 1), ymd(2023, 1, 3))]),
+            ),
+        ]))
+        .unwrap();
+
+        assert!(check_venue_load(&m, 2).is_empty());
+        assert_eq!(
+            rules(&check_venue_load(&m, 1)),
+            [(
+                Some("venue-load"),
+                None,
+                "'Foo' has 2 pieces pending, more than the maximum of 1: a, b"
+            )]
+        );
+    }
+
+    #[test]
+    fn test_check() {
+        let m = MetadataMap::build(BTreeMap::from([
+            (
+                String::from("a"),
+                metadata(vec![
+                    submitted("Foo", ymd(2023, 1, 1)),
+                    submitted("Bar", ymd(2023, 1, 2)),
+                ]),
+            ),
+            (
+                String::from("b"),
+                metadata(vec![
+                    rejected("Bar", ymd(2022, 1, 1), ymd(2022, 2, 1)),
+                    submitted("Bar", ymd(2023, 1, 2)),
+                ]),
+            ),
+        ]))
+        .unwrap();
+
+        assert_eq!(check(&m, None, None).len(), 1);
+        assert_eq!(check(&m, Some(&registry()), None).len(), 2);
+        assert_eq!(check(&m, Some(&registry()), Some(1)).len(), 3);
+        assert_eq!(check(&m, Some(&registry()), Some(2)).len(), 2);
+    }
+}
@@ -0,0 +1,108 @@
+import os
+import os.path
+import subprocess
+from collections.abc import Mapping, Sequence
+from typing import Any
+
+from markdown.private.utils import test_utils
+
+VENUES = [
+    {"name": "Foo", "aliases": ["Foo Magazine"], "simultaneous-submissions": False},
+    {"name": "Bar", "simultaneous-submissions": True},
+]
+
+
+def metadata(publications: Sequence[Mapping[str, str]]) -> dict[str, Any]:
+    return {
+        "title": "Title",
+        "author": "A",
+        "wordcount": "10",
+        "poetry-lines": "0",
+        "version": "1",
+        "publications": publications,
+        "lang": "en-GB",
+        "repo": "bar",
+        "source-hash": "1",
+        "parsed-dates": [],
+    }
+
+
+class TestSubmissionChecks(test_utils.ScriptTestCase):
+    def run_script(  # type: ignore[override]
+        self,
+        data: Mapping[str, Any],
+        args: Sequence[str] = (),
+    ) -> None:
+        in_file = os.path.join(self.tmpdir(), "in.json")
+        self.dump_json(in_file, data)
+
+        venues_file = os.path.join(self.tmpdir(), "venues.json")
+        self.dump_json(venues_file, VENUES)  # type: ignore[arg-type]
+
+        out_file = os.path.join(self.tmpdir(), "out.txt")
+
+        super().run_script(
+            args=[
+                "--venues-file",
+                venues_file,
+                *args,
+                in_file,
+                out_file,
+            ],
+        )
+
+        self.assertEqual(self.load_file(out_file), "OK\n")
+
+    def test_ok(self) -> None:
+        self.run_script(
+            {
+                "a:a": metadata(
+                    [
+                        {"venue": "Foo", "submitted": "2023-01-01", "rejected": "2023-02-01"},
+                        {"venue": "Bar", "submitted": "2023-03-01"},
+                    ]
+                ),
+                "a:b": metadata([{"venue": "Bar", "submitted": "2023-03-01"}]),
+            },
+            ["--max-per-venue=2"],
+        )
+
+    def test_simultaneous(self) -> None:
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(
+                {
+                    "a:a": metadata(
+                        [
+                            {"venue": "Foo Magazine", "submitted": "2023-01-01"},
+                            {"venue": "Bar", "submitted": "2023-03-01"},
+                        ]
+                    ),
+                }
+            )
+
+    def test_resubmitted(self) -> None:
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(
+                {
+                    "a:a": metadata(
+                        [
+                            {"venue": "Bar", "submitted": "2023-01-01", "rejected": "2023-02-01"},
+                            {"venue": "Bar", "submitted": "2023-03-01"},
+                        ]
+                    ),
+                }
+            )
+
+    def test_max_per_venue(self) -> None:
+        with self.assertRaises(subprocess.CalledProcessError):
+            self.run_script(
+                {
+                    "a:a": metadata([{"venue": "Bar", "submitted": "2023-03-01"}]),
+                    "a:b": metadata([{"venue": "Bar", "submitted": "2023-03-01"}]),
+                },
+                ["--max-per-venue=1"],
+            )
+
+
+if __name__ == "__main__":
+    test_utils.ScriptTestCase.main()
@@ -382,11 +382,7 @@ fn main() -> Result<(), Box<dyn Error>> {
             },
             publication,
             version: m.version().to_string(),
-            status: if m.version().contains("dirty") {
-                String::from("DIRTY")
-            } else {
-                String::from("ok")
-            },
+            status: m.version_info().status(),
         };
 
         if should_include(&row, &args.includes, &args.excludes) {
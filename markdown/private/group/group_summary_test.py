@@ -32,6 +32,7 @@ DATA = {
         "wordcount": "5",
         "poetry-lines": "0",
         "version": "quux, dirty",
+        "version-info": {"dirty": True},
         "lang": "en-GB",
         "repo": "bar",
         "source-hash": "1",
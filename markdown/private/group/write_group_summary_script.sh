@@ -3,7 +3,7 @@
 set -eu
 
 function usage() {
-    echo "Usage: $(basename "${0}") workspace_name file_to_open output_tool out_file"
+    echo "Usage: $(basename "${0}") workspace_name file_to_open output_tool out_file [--flag=runfile ...]"
     exit 1
 }
 
@@ -15,6 +15,13 @@ test -z "${3:-}" && usage
 OUTPUT_TOOL="${3}"
 test -z "${4:-}" && usage
 OUT_FILE="${4}"
+shift 4
+
+# Extra flags to the output tool, whose values are runfiles
+EXTRA_ARGS=""
+for ARG in "${@}"; do
+    EXTRA_ARGS+=" \"${ARG%%=*}=\${0}.runfiles/${WORKSPACE_NAME}/${ARG#*=}\""
+done
 
 cat >"${OUT_FILE}" <<EOF
 #!/bin/bash
@@ -23,7 +30,7 @@ set -eu
 
 FILE_TO_OPEN="\${0}.runfiles/${WORKSPACE_NAME}/${FILE_TO_OPEN}"
 
-"\${0}.runfiles/${WORKSPACE_NAME}/${OUTPUT_TOOL}" "\${FILE_TO_OPEN}" "\${@}"
+"\${0}.runfiles/${WORKSPACE_NAME}/${OUTPUT_TOOL}" "\${FILE_TO_OPEN}"${EXTRA_ARGS} "\${@}"
 EOF
 
 chmod u+x "${OUT_FILE}"
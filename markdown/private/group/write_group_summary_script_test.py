@@ -26,6 +26,31 @@ set -eu
 FILE_TO_OPEN="${0}.runfiles/foo/bar"
 
 "${0}.runfiles/foo/baz" "${FILE_TO_OPEN}" "${@}"
+""",
+        )
+
+    def test_write_group_summary_script_runfile_args(self) -> None:
+        out_file = os.path.join(self.tmpdir(), "out.sh")
+
+        self.run_script(
+            args=[
+                "foo",
+                "bar",
+                "baz",
+                out_file,
+                "--venues-file=quux/venues.json",
+            ],
+        )
+
+        self.assertEqual(
+            self.load_file(out_file),
+            """#!/bin/bash
+
+set -eu
+
+FILE_TO_OPEN="${0}.runfiles/foo/bar"
+
+"${0}.runfiles/foo/baz" "${FILE_TO_OPEN}" "--venues-file=${0}.runfiles/foo/quux/venues.json" "${@}"
 """,
         )
 
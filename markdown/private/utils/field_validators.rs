@@ -1,8 +1,12 @@
+use crate::validation;
 use validator::ValidationError;
 
 pub fn non_empty(s: &str) -> Result<(), ValidationError> {
     if s.is_empty() {
-        return Err(ValidationError::new("must be non-empty"));
+        return Err(validation::error_with_fix(
+            "must be non-empty",
+            "remove the field, or give it a value",
+        ));
     }
     Ok(())
 }
@@ -10,7 +14,10 @@ pub fn non_empty(s: &str) -> Result<(), ValidationError> {
 pub fn each_non_empty(v: &Vec<String>) -> Result<(), ValidationError> {
     for s in v {
         if s.is_empty() {
-            return Err(ValidationError::new("each element must be non-empty"));
+            return Err(validation::error_with_fix(
+                "each element must be non-empty",
+                "remove the empty elements",
+            ));
         }
     }
     Ok(())
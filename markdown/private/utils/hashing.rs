@@ -0,0 +1,181 @@
+use crate::metadata::{ContentHash, HashAlgorithm, OutputMetadata, SourceHash};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use validator::ValidationErrors;
+
+/// The hashes of a dep that feed into the hashes of files that include it.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DepHash {
+    source_hash: String,
+    content_hash: Option<ContentHash>,
+}
+
+impl From<&OutputMetadata> for DepHash {
+    fn from(m: &OutputMetadata) -> DepHash {
+        DepHash {
+            source_hash: String::from(m.source_hash()),
+            content_hash: m.content_hash().cloned(),
+        }
+    }
+}
+
+impl From<SourceHash> for DepHash {
+    fn from(h: SourceHash) -> DepHash {
+        DepHash {
+            source_hash: h.source_hash,
+            content_hash: Some(h.content_hash),
+        }
+    }
+}
+
+pub fn digest(algorithm: HashAlgorithm, data: &[u8]) -> String {
+    match algorithm {
+        HashAlgorithm::Sha256 => format!("{:x}", Sha256::digest(data)),
+        HashAlgorithm::Blake3 => blake3::hash(data).to_hex().to_string(),
+    }
+}
+
+// The text that gets hashed, given how to hash a dep and an input file. With
+// no input files, this is the src with the deps' hashes in front.
+fn hash_input<D, F>(
+    deps: &BTreeMap<String, DepHash>,
+    src: &str,
+    inputs: &BTreeMap<String, Vec<u8>>,
+    dep_hash: D,
+    file_hash: F,
+) -> String
+where
+    D: Fn(&DepHash) -> String,
+    F: Fn(&[u8]) -> String,
+{
+    let mut hash_input = vec![String::from("{")];
+    hash_input.extend(
+        deps.iter()
+            .map(|(target, hash)| format!("{target} {}", dep_hash(hash))),
+    );
+    hash_input.push(String::from("}"));
+    hash_input.push(String::from(src));
+    hash_input.extend(
+        inputs
+            .iter()
+            .map(|(name, contents)| format!("{name} {}", file_hash(contents))),
+    );
+    hash_input.join("\n")
+}
+
+/// Hashes a source file, the hashes of its deps, keyed by label, and the
+/// contents of its other inputs, keyed by a stable name.
+pub fn source_hash(
+    deps: &BTreeMap<String, DepHash>,
+    src: &str,
+    inputs: &BTreeMap<String, Vec<u8>>,
+    algorithm: HashAlgorithm,
+) -> Result<SourceHash, ValidationErrors> {
+    // We use md5 because the PDF trailer ID must be a 16-byte hex string -
+    // which is the size of md5.
+    let md5_input = hash_input(
+        deps,
+        src,
+        inputs,
+        |h| h.source_hash.clone(),
+        |contents| format!("{:x}", md5::compute(contents)),
+    );
+    let md5_output = format!("{:x}", md5::compute(md5_input));
+
+    // md5 isn't collision resistant, so the content hash chains the deps'
+    // content hashes instead, where they have them.
+    let content_input = hash_input(
+        deps,
+        src,
+        inputs,
+        |h| match &h.content_hash {
+            Some(c) => c.to_string(),
+            None => h.source_hash.clone(),
+        },
+        |contents| digest(algorithm, contents),
+    );
+    let content_hash = ContentHash::build(algorithm, digest(algorithm, content_input.as_bytes()))?;
+
+    SourceHash::build(
+        md5_output,
+        content_hash,
+        deps.keys().cloned().collect(),
+        inputs.keys().cloned().collect(),
+    )
+}
+
+#[cfg(test)]
+mod hashing_test {
+    use super::{BTreeMap, DepHash, HashAlgorithm, source_hash};
+
+    fn inputs(i: &[(&str, &str)]) -> BTreeMap<String, Vec<u8>> {
+        i.iter()
+            .map(|(k, v)| (String::from(*k), v.as_bytes().to_vec()))
+            .collect()
+    }
+
+    #[test]
+    fn test_source_hash() {
+        let deps = BTreeMap::new();
+        let plain = source_hash(&deps, "foo", &inputs(&[]), HashAlgorithm::Sha256).unwrap();
+        // Unchanged from before input files were hashed
+        assert_eq!(
+            plain.source_hash,
+            format!("{:x}", md5::compute("{\n}\nfoo"))
+        );
+        assert_eq!(plain.content_hash.algorithm, HashAlgorithm::Sha256);
+        assert_eq!(plain.content_hash.digest.len(), 64);
+
+        let image = source_hash(
+            &deps,
+            "foo",
+            &inputs(&[("a/image.jpg", "1")]),
+            HashAlgorithm::Sha256,
+        )
+        .unwrap();
+        let changed_image = source_hash(
+            &deps,
+            "foo",
+            &inputs(&[("a/image.jpg", "2")]),
+            HashAlgorithm::Sha256,
+        )
+        .unwrap();
+        assert_ne!(image.source_hash, plain.source_hash);
+        assert_ne!(image.source_hash, changed_image.source_hash);
+        assert_ne!(image.content_hash, plain.content_hash);
+        assert_ne!(image.content_hash, changed_image.content_hash);
+        assert!(plain.hash_inputs.is_empty());
+        assert_eq!(image.hash_inputs, ["a/image.jpg"]);
+
+        let blake3 = source_hash(&deps, "foo", &inputs(&[]), HashAlgorithm::Blake3).unwrap();
+        assert_eq!(blake3.source_hash, plain.source_hash);
+        assert_eq!(blake3.content_hash.algorithm, HashAlgorithm::Blake3);
+        assert_ne!(blake3.content_hash.digest, plain.content_hash.digest);
+    }
+
+    #[test]
+    fn test_dep_hashes() {
+        let dep =
+            source_hash(&BTreeMap::new(), "bar", &inputs(&[]), HashAlgorithm::Sha256).unwrap();
+        let with_dep = |d: DepHash| {
+            source_hash(
+                &BTreeMap::from([(String::from("a:dep"), d)]),
+                "foo",
+                &inputs(&[]),
+                HashAlgorithm::Sha256,
+            )
+            .unwrap()
+        };
+
+        let full = with_dep(DepHash::from(dep.clone()));
+        let md5_only = with_dep(DepHash {
+            source_hash: dep.source_hash.clone(),
+            content_hash: None,
+        });
+        // The md5 only depends on the dep's md5
+        assert_eq!(full.source_hash, md5_only.source_hash);
+        assert_ne!(full.content_hash, md5_only.content_hash);
+        assert_eq!(full.hash_deps, ["a:dep"]);
+        assert!(dep.hash_deps.is_empty());
+    }
+}
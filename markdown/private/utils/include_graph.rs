@@ -0,0 +1,122 @@
+use crate::json::JsonSerializable;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet, VecDeque};
+use validator::Validate;
+
+/// The labels each file directly includes, for every file reachable from
+/// some root.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(transparent)]
+pub struct IncludeGraph {
+    includes: BTreeMap<String, Vec<String>>,
+}
+
+impl JsonSerializable for IncludeGraph {}
+
+impl IncludeGraph {
+    pub fn build<T: Into<BTreeMap<String, Vec<String>>>>(includes: T) -> IncludeGraph {
+        IncludeGraph {
+            includes: includes.into(),
+        }
+    }
+
+    pub fn merge(&mut self, other: IncludeGraph) {
+        self.includes.extend(other.includes);
+    }
+
+    pub fn insert(&mut self, label: &str, children: Vec<String>) {
+        self.includes.insert(String::from(label), children);
+    }
+
+    pub fn children(&self, label: &str) -> &[String] {
+        self.includes.get(label).map(Vec::as_slice).unwrap_or(&[])
+    }
+
+    /// The shortest chain of includes from one label to another, including
+    /// both ends, if there is one.
+    pub fn chain(&self, from: &str, to: &str) -> Option<Vec<String>> {
+        let mut parents: BTreeMap<&str, &str> = BTreeMap::new();
+        let mut seen = BTreeSet::from([from]);
+        let mut queue = VecDeque::from([from]);
+
+        while let Some(label) = queue.pop_front() {
+            if label == to {
+                let mut chain = vec![String::from(label)];
+                let mut current = label;
+                while let Some(parent) = parents.get(current) {
+                    chain.push(String::from(*parent));
+                    current = parent;
+                }
+                chain.reverse();
+                return Some(chain);
+            }
+            for child in self.children(label) {
+                if seen.insert(child) {
+                    parents.insert(child, label);
+                    queue.push_back(child);
+                }
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod include_graph_test {
+    use super::IncludeGraph;
+    use std::collections::BTreeMap;
+
+    fn graph(edges: &[(&str, &[&str])]) -> IncludeGraph {
+        IncludeGraph::build(BTreeMap::from_iter(edges.iter().map(|(from, to)| {
+            (
+                String::from(*from),
+                to.iter().map(|t| String::from(*t)).collect(),
+            )
+        })))
+    }
+
+    #[test]
+    fn test_merge() {
+        let mut g = graph(&[("a", &["b"]), ("b", &[])]);
+        g.merge(graph(&[("c", &["d"])]));
+        g.insert("d", vec![String::from("b")]);
+        assert_eq!(
+            g,
+            graph(&[("a", &["b"]), ("b", &[]), ("c", &["d"]), ("d", &["b"])])
+        );
+        assert_eq!(g.children("c"), ["d"]);
+        assert!(g.children("e").is_empty());
+    }
+
+    #[test]
+    fn test_chain() {
+        let g = graph(&[
+            ("a", &["b", "c"]),
+            ("b", &["d"]),
+            ("c", &["e"]),
+            ("e", &["d", "f"]),
+            ("f", &["a"]),
+        ]);
+        assert_eq!(g.chain("a", "a"), Some(vec![String::from("a")]));
+        assert_eq!(
+            g.chain("a", "d"),
+            Some(vec![
+                String::from("a"),
+                String::from("b"),
+                String::from("d")
+            ])
+        );
+        assert_eq!(
+            g.chain("a", "f"),
+            Some(vec![
+                String::from("a"),
+                String::from("c"),
+                String::from("e"),
+                String::from("f")
+            ])
+        );
+        assert_eq!(g.chain("b", "a"), None);
+        assert_eq!(g.chain("x", "a"), None);
+    }
+}
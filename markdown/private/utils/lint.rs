@@ -0,0 +1,142 @@
+use crate::problems::Severity;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use validator::Validate;
+
+/// Overrides for a lint rule's defaults.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct RuleConfig {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    enabled: Option<bool>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    severity: Option<Severity>,
+}
+
+impl RuleConfig {
+    pub fn build(enabled: Option<bool>, severity: Option<Severity>) -> RuleConfig {
+        RuleConfig { enabled, severity }
+    }
+
+    pub fn enabled(&self) -> Option<bool> {
+        self.enabled
+    }
+
+    pub fn severity(&self) -> Option<Severity> {
+        self.severity
+    }
+}
+
+/// Overrides for lint rules, by rule name.
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(transparent)]
+pub struct RuleConfigs {
+    rules: BTreeMap<String, RuleConfig>,
+}
+
+impl RuleConfigs {
+    pub fn build(rules: BTreeMap<String, RuleConfig>) -> RuleConfigs {
+        RuleConfigs { rules }
+    }
+
+    /// The severity of a rule, or None if it's disabled.
+    pub fn severity(
+        &self,
+        name: &str,
+        default_enabled: bool,
+        default_severity: Severity,
+    ) -> Option<Severity> {
+        let config = self.rules.get(name);
+        if config
+            .and_then(RuleConfig::enabled)
+            .unwrap_or(default_enabled)
+        {
+            return Some(
+                config
+                    .and_then(RuleConfig::severity)
+                    .unwrap_or(default_severity),
+            );
+        }
+        None
+    }
+
+    /// Checks that every configured rule is one of known.
+    pub fn check_names<'a>(&self, known: impl IntoIterator<Item = &'a str>) -> Result<(), String> {
+        let known: Vec<&str> = known.into_iter().collect();
+        for name in self.rules.keys() {
+            if !known.contains(&name.as_str()) {
+                return Err(format!("Unknown lint rule '{name}'"));
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod lint_test {
+    use super::{RuleConfig, RuleConfigs};
+    use crate::json::from_json;
+    use crate::problems::Severity;
+    use std::collections::BTreeMap;
+
+    #[test]
+    fn test_rule_configs() {
+        let configs: RuleConfigs = from_json(
+            r#"{
+  "a": {"enabled": false},
+  "b": {"severity": "warning"},
+  "c": {"enabled": true, "severity": "error"},
+  "d": {}
+}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            configs,
+            RuleConfigs::build(BTreeMap::from([
+                (String::from("a"), RuleConfig::build(Some(false), None)),
+                (
+                    String::from("b"),
+                    RuleConfig::build(None, Some(Severity::Warning))
+                ),
+                (
+                    String::from("c"),
+                    RuleConfig::build(Some(true), Some(Severity::Error))
+                ),
+                (String::from("d"), RuleConfig::default()),
+            ]))
+        );
+
+        assert_eq!(configs.severity("a", true, Severity::Error), None);
+        assert_eq!(
+            configs.severity("b", true, Severity::Error),
+            Some(Severity::Warning)
+        );
+        assert_eq!(configs.severity("b", false, Severity::Error), None);
+        assert_eq!(
+            configs.severity("c", false, Severity::Warning),
+            Some(Severity::Error)
+        );
+        assert_eq!(
+            configs.severity("d", true, Severity::Warning),
+            Some(Severity::Warning)
+        );
+        assert_eq!(
+            configs.severity("e", true, Severity::Error),
+            Some(Severity::Error)
+        );
+
+        assert!(configs.check_names(["a", "b", "c", "d", "e"]).is_ok());
+        assert_eq!(
+            configs.check_names(["a", "b", "c"]).unwrap_err(),
+            "Unknown lint rule 'd'"
+        );
+
+        assert!(from_json::<RuleConfigs>(r#"{"a": {"foo": true}}"#).is_err());
+        assert!(from_json::<RuleConfigs>(r#"{"a": {"severity": "bad"}}"#).is_err());
+    }
+}
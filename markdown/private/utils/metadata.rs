@@ -1,26 +1,146 @@
-use crate::{deserializers, field_validators, json::JsonSerializable, publications::Publications};
+use crate::{
+    deserializers, field_validators,
+    json::JsonSerializable,
+    publications::Publications,
+    schema::{self, JsonSchema},
+    venues::VenueRegistry,
+};
 use chrono::naive::NaiveDate;
+use clap::ValueEnum;
 use derive_builder::Builder;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
 use std::collections::BTreeMap;
 use std::collections::HashSet;
+use std::fmt::{Display, Formatter};
 use validator::{Validate, ValidationError, ValidationErrors};
 
+/// Where a version came from, if it wasn't computed from the workspace
+/// status.
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum OverrideSource {
+    VersionFile,
+    VersionOverride,
+}
+
+/// The state of a version. The version string is for display only; decisions
+/// about a version are made on these fields.
+#[derive(Clone, PartialEq, Default, Debug, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct VersionInfo {
+    /// Tag or commit, without any dirty marker
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "field_validators::non_empty"))]
+    pub commit: Option<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub dirty: bool,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "is_false")]
+    pub unversioned: bool,
+
+    /// Transitive deps that are dirty
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub dirty_deps: Vec<String>,
+
+    /// Transitive deps that are unversioned
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub unversioned_deps: Vec<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub override_source: Option<OverrideSource>,
+}
+
+impl VersionInfo {
+    fn is_default(&self) -> bool {
+        *self == VersionInfo::default()
+    }
+
+    /// Whether the version, or any of its deps, is dirty.
+    pub fn has_dirty(&self) -> bool {
+        self.dirty || !self.dirty_deps.is_empty()
+    }
+
+    /// Whether the version, or any of its deps, is unversioned.
+    pub fn has_unversioned(&self) -> bool {
+        self.unversioned || !self.unversioned_deps.is_empty()
+    }
+
+    /// Short description of the state, for summaries.
+    pub fn status(&self) -> String {
+        let mut status = Vec::new();
+        if self.dirty {
+            status.push(String::from("DIRTY"));
+        }
+        if !self.dirty_deps.is_empty() {
+            status.push(format!("DIRTY DEPS: {}", self.dirty_deps.join(" ")));
+        }
+        if status.is_empty() {
+            return String::from("ok");
+        }
+        status.join(", ")
+    }
+}
+
+impl JsonSchema for VersionInfo {
+    fn json_schema() -> Value {
+        schema::object(
+            vec![
+                ("commit", schema::non_empty_string()),
+                ("dirty", schema::boolean()),
+                ("unversioned", schema::boolean()),
+                ("dirty-deps", schema::array(schema::non_empty_string())),
+                (
+                    "unversioned-deps",
+                    schema::array(schema::non_empty_string()),
+                ),
+                (
+                    "override-source",
+                    schema::string_enum(&["version-file", "version-override"]),
+                ),
+            ],
+            &[],
+        )
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
 pub struct Version {
     #[validate(custom(function = "field_validators::non_empty"))]
     pub version: String,
     #[validate(custom(function = "field_validators::non_empty"))]
     pub repo: String,
+    #[serde(default)]
+    #[serde(skip_serializing_if = "VersionInfo::is_default")]
+    #[validate(nested)]
+    pub version_info: VersionInfo,
 }
 
 impl Version {
     pub fn build<T: Into<String>>(version: T, repo: T) -> Result<Version, ValidationErrors> {
+        Version::build_with_info(version, repo, VersionInfo::default())
+    }
+
+    pub fn build_with_info<T: Into<String>>(
+        version: T,
+        repo: T,
+        version_info: VersionInfo,
+    ) -> Result<Version, ValidationErrors> {
         let v = Version {
             version: version.into(),
             repo: repo.into(),
+            version_info,
         };
         v.validate()?;
         Ok(v)
@@ -29,18 +149,109 @@ impl Version {
 
 impl JsonSerializable for Version {}
 
+/// Algorithm used for the content hash of a document.
+#[derive(Clone, Copy, PartialEq, Debug, Default, Serialize, Deserialize, ValueEnum)]
+#[serde(rename_all = "kebab-case")]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl Display for HashAlgorithm {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            HashAlgorithm::Sha256 => write!(f, "sha256"),
+            HashAlgorithm::Blake3 => write!(f, "blake3"),
+        }
+    }
+}
+
+/// A collision-resistant hash of a document's sources, suitable for detecting
+/// changes and as a cache key.
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+pub struct ContentHash {
+    pub algorithm: HashAlgorithm,
+    #[validate(custom(function = "field_validators::non_empty"))]
+    pub digest: String,
+}
+
+impl ContentHash {
+    pub fn build<T: Into<String>>(
+        algorithm: HashAlgorithm,
+        digest: T,
+    ) -> Result<ContentHash, ValidationErrors> {
+        let h = ContentHash {
+            algorithm,
+            digest: digest.into(),
+        };
+        h.validate()?;
+        Ok(h)
+    }
+}
+
+impl Display for ContentHash {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.algorithm, self.digest)
+    }
+}
+
+impl JsonSchema for ContentHash {
+    fn json_schema() -> Value {
+        schema::object(
+            vec![
+                ("algorithm", schema::string_enum(&["sha256", "blake3"])),
+                ("digest", schema::non_empty_string()),
+            ],
+            &["algorithm", "digest"],
+        )
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "kebab-case")]
 pub struct SourceHash {
+    /// md5, because the PDF trailer ID must be 16 bytes
     #[validate(custom(function = "field_validators::non_empty"))]
     pub source_hash: String,
+    #[validate(nested)]
+    pub content_hash: ContentHash,
+    /// Path of the source, relative to the workspace root
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "field_validators::non_empty"))]
+    pub hash_src: Option<String>,
+    /// Labels of the deps whose hashes were hashed
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hash_deps: Vec<String>,
+    /// Names of the files other than the source that were hashed
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hash_inputs: Vec<String>,
+    /// Which of the source and the other files were generated or come from
+    /// another repository, so aren't in the working tree
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub hash_generated: Vec<String>,
 }
 
 impl SourceHash {
-    pub fn build<T: Into<String>>(source_hash: T) -> Result<SourceHash, ValidationErrors> {
+    pub fn build<T: Into<String>>(
+        source_hash: T,
+        content_hash: ContentHash,
+        hash_deps: Vec<String>,
+        hash_inputs: Vec<String>,
+    ) -> Result<SourceHash, ValidationErrors> {
         let s = SourceHash {
             source_hash: source_hash.into(),
+            content_hash,
+            hash_src: None,
+            hash_deps,
+            hash_inputs,
+            hash_generated: Vec::new(),
         };
         s.validate()?;
         Ok(s)
@@ -112,6 +323,20 @@ impl ParsedDateSet {
 
 impl JsonSerializable for ParsedDateSet {}
 
+impl JsonSchema for ParsedDateSet {
+    fn json_schema() -> Value {
+        json!({
+            "type": "array",
+            "items": {
+                "type": "string",
+                "pattern": "^[0-9]{4}(/[0-9]{2}(/[0-9]{2})?)?$",
+            },
+            "uniqueItems": true,
+            "description": "sorted dates in YYYY, YYYY/MM or YYYY/MM/DD format",
+        })
+    }
+}
+
 #[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "kebab-case")]
@@ -156,6 +381,32 @@ impl Identifier {
 
 impl JsonSerializable for Identifier {}
 
+impl JsonSchema for Identifier {
+    fn json_schema() -> Value {
+        schema::object(
+            vec![
+                ("scheme", schema::non_empty_string()),
+                ("text", schema::non_empty_string()),
+            ],
+            &["scheme", "text"],
+        )
+    }
+}
+
+fn common_properties() -> Vec<(&'static str, Value)> {
+    vec![
+        ("title", schema::optional_string()),
+        ("author", schema::non_empty_strings()),
+        ("translator", schema::non_empty_strings()),
+        ("illustrator", schema::non_empty_strings()),
+        ("date", schema::optional_string()),
+        ("notes", schema::optional_string()),
+        ("finished", schema::boolean()),
+        ("publications", Publications::json_schema()),
+        ("identifier", schema::array(Identifier::json_schema())),
+    ]
+}
+
 fn is_false(b: &bool) -> bool {
     !b
 }
@@ -180,6 +431,22 @@ pub struct InputMetadata {
     #[builder(default)]
     authors: Vec<String>,
 
+    #[serde(default)]
+    #[serde(rename = "translator")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(deserialize_with = "deserializers::str_or_seq")]
+    #[validate(custom(function = "field_validators::each_non_empty"))]
+    #[builder(default)]
+    translators: Vec<String>,
+
+    #[serde(default)]
+    #[serde(rename = "illustrator")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(deserialize_with = "deserializers::str_or_seq")]
+    #[validate(custom(function = "field_validators::each_non_empty"))]
+    #[builder(default)]
+    illustrators: Vec<String>,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(deserialize_with = "deserializers::option_string")]
@@ -218,6 +485,8 @@ impl InputMetadataBuilder {
         if let Err(err) = InputMetadata::build(
             self.title.clone().unwrap_or_default().as_deref(),
             self.authors.clone().unwrap_or_default(),
+            self.translators.clone().unwrap_or_default(),
+            self.illustrators.clone().unwrap_or_default(),
             self.date.clone().unwrap_or_default().as_deref(),
             self.notes.clone().unwrap_or_default().as_deref(),
             self.finished.unwrap_or_default(),
@@ -232,9 +501,12 @@ impl InputMetadataBuilder {
 }
 
 impl InputMetadata {
+    #[allow(clippy::too_many_arguments)]
     fn build<S, I>(
         title: Option<&str>,
         authors: S,
+        translators: S,
+        illustrators: S,
         date: Option<&str>,
         notes: Option<&str>,
         finished: bool,
@@ -248,6 +520,8 @@ impl InputMetadata {
         let m = InputMetadata {
             title: title.map(str::to_string),
             authors: authors.into(),
+            translators: translators.into(),
+            illustrators: illustrators.into(),
             date: date.map(str::to_string),
             notes: notes.map(str::to_string),
             finished,
@@ -266,6 +540,14 @@ impl InputMetadata {
         &self.authors
     }
 
+    pub fn translators(&self) -> &Vec<String> {
+        &self.translators
+    }
+
+    pub fn illustrators(&self) -> &Vec<String> {
+        &self.illustrators
+    }
+
     pub fn date(&self) -> Option<&String> {
         self.date.as_ref()
     }
@@ -285,10 +567,22 @@ impl InputMetadata {
     pub fn identifiers(&self) -> &Vec<Identifier> {
         &self.identifiers
     }
+
+    pub fn canonicalise_venues(&self, registry: &VenueRegistry) -> Result<InputMetadata, String> {
+        let mut m = self.clone();
+        m.publications = self.publications.canonicalise_venues(registry)?;
+        Ok(m)
+    }
 }
 
 impl JsonSerializable for InputMetadata {}
 
+impl JsonSchema for InputMetadata {
+    fn json_schema() -> Value {
+        schema::object(common_properties(), &[])
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Validate, Builder)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "kebab-case")]
@@ -309,6 +603,22 @@ pub struct OutputMetadata {
     #[builder(default)]
     authors: Vec<String>,
 
+    #[serde(default)]
+    #[serde(rename = "translator")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(deserialize_with = "deserializers::str_or_seq")]
+    #[validate(custom(function = "field_validators::each_non_empty"))]
+    #[builder(default)]
+    translators: Vec<String>,
+
+    #[serde(default)]
+    #[serde(rename = "illustrator")]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[serde(deserialize_with = "deserializers::str_or_seq")]
+    #[validate(custom(function = "field_validators::each_non_empty"))]
+    #[builder(default)]
+    illustrators: Vec<String>,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[serde(deserialize_with = "deserializers::option_string")]
@@ -356,9 +666,42 @@ pub struct OutputMetadata {
     #[validate(custom(function = "field_validators::non_empty"))]
     repo: String,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "VersionInfo::is_default")]
+    #[validate(nested)]
+    #[builder(default)]
+    version_info: VersionInfo,
+
     #[validate(custom(function = "field_validators::non_empty"))]
     source_hash: String,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(nested)]
+    #[builder(default)]
+    content_hash: Option<ContentHash>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[validate(custom(function = "field_validators::non_empty"))]
+    #[builder(default)]
+    hash_src: Option<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    hash_deps: Vec<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    hash_inputs: Vec<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[builder(default)]
+    hash_generated: Vec<String>,
+
     #[serde(default)]
     #[validate(nested)]
     #[builder(default)]
@@ -370,6 +713,8 @@ impl OutputMetadataBuilder {
         if let Err(err) = OutputMetadata::build(
             self.title.clone().unwrap_or_default().as_deref(),
             self.authors.clone().unwrap_or_default(),
+            self.translators.clone().unwrap_or_default(),
+            self.illustrators.clone().unwrap_or_default(),
             self.date.clone().unwrap_or_default().as_deref(),
             self.notes.clone().unwrap_or_default().as_deref(),
             self.finished.unwrap_or_default(),
@@ -380,7 +725,13 @@ impl OutputMetadataBuilder {
             self.lang.clone().unwrap_or_default(),
             self.version.clone().unwrap_or_default(),
             self.repo.clone().unwrap_or_default(),
+            self.version_info.clone().unwrap_or_default(),
             self.source_hash.clone().unwrap_or_default(),
+            self.content_hash.clone().unwrap_or_default(),
+            self.hash_src.clone().unwrap_or_default(),
+            self.hash_deps.clone().unwrap_or_default(),
+            self.hash_inputs.clone().unwrap_or_default(),
+            self.hash_generated.clone().unwrap_or_default(),
             self.parsed_dates.clone().unwrap_or_default(),
         ) {
             return Err(err.to_string());
@@ -395,6 +746,8 @@ impl OutputMetadata {
     fn build<VS, VI, S>(
         title: Option<&str>,
         authors: VS,
+        translators: VS,
+        illustrators: VS,
         date: Option<&str>,
         notes: Option<&str>,
         finished: bool,
@@ -405,7 +758,13 @@ impl OutputMetadata {
         lang: S,
         version: S,
         repo: S,
+        version_info: VersionInfo,
         source_hash: S,
+        content_hash: Option<ContentHash>,
+        hash_src: Option<String>,
+        hash_deps: Vec<String>,
+        hash_inputs: Vec<String>,
+        hash_generated: Vec<String>,
         parsed_dates: ParsedDateSet,
     ) -> Result<OutputMetadata, ValidationErrors>
     where
@@ -416,6 +775,8 @@ impl OutputMetadata {
         let m = OutputMetadata {
             title: title.map(str::to_string),
             authors: authors.into(),
+            translators: translators.into(),
+            illustrators: illustrators.into(),
             date: date.map(str::to_string),
             notes: notes.map(str::to_string),
             finished,
@@ -426,7 +787,13 @@ impl OutputMetadata {
             lang: lang.into(),
             version: version.into(),
             repo: repo.into(),
+            version_info,
             source_hash: source_hash.into(),
+            content_hash,
+            hash_src,
+            hash_deps,
+            hash_inputs,
+            hash_generated,
             parsed_dates,
         };
         m.validate()?;
@@ -441,6 +808,14 @@ impl OutputMetadata {
         &self.authors
     }
 
+    pub fn translators(&self) -> &Vec<String> {
+        &self.translators
+    }
+
+    pub fn illustrators(&self) -> &Vec<String> {
+        &self.illustrators
+    }
+
     pub fn date(&self) -> Option<&String> {
         self.date.as_ref()
     }
@@ -481,17 +856,79 @@ impl OutputMetadata {
         &self.repo
     }
 
+    pub fn version_info(&self) -> &VersionInfo {
+        &self.version_info
+    }
+
     pub fn source_hash(&self) -> &str {
         &self.source_hash
     }
 
+    pub fn content_hash(&self) -> Option<&ContentHash> {
+        self.content_hash.as_ref()
+    }
+
+    pub fn hash_src(&self) -> Option<&String> {
+        self.hash_src.as_ref()
+    }
+
+    pub fn hash_deps(&self) -> &Vec<String> {
+        &self.hash_deps
+    }
+
+    pub fn hash_inputs(&self) -> &Vec<String> {
+        &self.hash_inputs
+    }
+
+    pub fn hash_generated(&self) -> &Vec<String> {
+        &self.hash_generated
+    }
+
     pub fn parsed_dates(&self) -> &ParsedDateSet {
         &self.parsed_dates
     }
+
+    pub fn canonicalise_venues(&self, registry: &VenueRegistry) -> Result<OutputMetadata, String> {
+        let mut m = self.clone();
+        m.publications = self.publications.canonicalise_venues(registry)?;
+        Ok(m)
+    }
 }
 
 impl JsonSerializable for OutputMetadata {}
 
+impl JsonSchema for OutputMetadata {
+    fn json_schema() -> Value {
+        let mut properties = common_properties();
+        properties.extend([
+            ("wordcount", schema::uint()),
+            ("poetry-lines", schema::uint()),
+            ("lang", schema::non_empty_string()),
+            ("version", schema::non_empty_string()),
+            ("repo", schema::non_empty_string()),
+            ("version-info", VersionInfo::json_schema()),
+            ("source-hash", schema::non_empty_string()),
+            ("content-hash", ContentHash::json_schema()),
+            ("hash-src", schema::non_empty_string()),
+            ("hash-deps", schema::array(schema::non_empty_string())),
+            ("hash-inputs", schema::array(schema::non_empty_string())),
+            ("hash-generated", schema::array(schema::non_empty_string())),
+            ("parsed-dates", ParsedDateSet::json_schema()),
+        ]);
+        schema::object(
+            properties,
+            &[
+                "wordcount",
+                "poetry-lines",
+                "lang",
+                "version",
+                "repo",
+                "source-hash",
+            ],
+        )
+    }
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
 #[serde(transparent)]
@@ -512,10 +949,33 @@ impl MetadataMap {
     pub fn data(&self) -> &BTreeMap<String, OutputMetadata> {
         &self.data
     }
+
+    pub fn canonicalise_venues(&self, registry: &VenueRegistry) -> Result<MetadataMap, String> {
+        let mut problems = Vec::new();
+        let mut data = BTreeMap::new();
+        for (target, m) in &self.data {
+            match m.canonicalise_venues(registry) {
+                Ok(m) => {
+                    data.insert(target.clone(), m);
+                }
+                Err(e) => problems.push(format!("{target}: {e}")),
+            }
+        }
+        if !problems.is_empty() {
+            return Err(problems.join("\n"));
+        }
+        Ok(MetadataMap { data })
+    }
 }
 
 impl JsonSerializable for MetadataMap {}
 
+impl JsonSchema for MetadataMap {
+    fn json_schema() -> Value {
+        schema::map(OutputMetadata::json_schema())
+    }
+}
+
 #[cfg(test)]
 mod test_utils {
     use chrono::NaiveDate;
@@ -527,7 +987,7 @@ mod test_utils {
 
 #[cfg(test)]
 mod version_test {
-    use super::Version;
+    use super::{OverrideSource, Version, VersionInfo};
     use crate::json::{JsonSerializable, from_json};
 
     #[test]
@@ -588,23 +1048,148 @@ mod version_test {
             .is_err()
         );
     }
+    #[test]
+    fn test_version_info() {
+        let info = VersionInfo {
+            commit: Some(String::from("foo")),
+            dirty: true,
+            dirty_deps: vec![String::from("a:b"), String::from("c:d")],
+            override_source: Some(OverrideSource::VersionFile),
+            ..Default::default()
+        };
+        let v = Version::build_with_info("foo-dirty", "bar", info.clone()).unwrap();
+        let json = v.to_json().unwrap();
+        assert_eq!(
+            json,
+            r#"{
+  "repo": "bar",
+  "version": "foo-dirty",
+  "version-info": {
+    "commit": "foo",
+    "dirty": true,
+    "dirty-deps": [
+      "a:b",
+      "c:d"
+    ],
+    "override-source": "version-file"
+  }
+}"#
+        );
+        assert_eq!(from_json::<Version>(&json).unwrap(), v);
+
+        assert!(
+            Version::build_with_info(
+                "foo",
+                "bar",
+                VersionInfo {
+                    commit: Some(String::new()),
+                    ..Default::default()
+                }
+            )
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_version_info_status() {
+        assert_eq!(VersionInfo::default().status(), "ok");
+
+        let mut info = VersionInfo {
+            unversioned: true,
+            unversioned_deps: vec![String::from("a:b")],
+            ..Default::default()
+        };
+        assert_eq!(info.status(), "ok");
+        assert!(info.has_unversioned());
+        assert!(!info.has_dirty());
+
+        info.dirty = true;
+        assert_eq!(info.status(), "DIRTY");
+        assert!(info.has_dirty());
+
+        info.dirty_deps = vec![String::from("a:b"), String::from("c:d")];
+        assert_eq!(info.status(), "DIRTY, DIRTY DEPS: a:b c:d");
+
+        info.dirty = false;
+        assert_eq!(info.status(), "DIRTY DEPS: a:b c:d");
+    }
 }
 
 #[cfg(test)]
 mod source_hash_test {
-    use super::SourceHash;
+    use super::{ContentHash, HashAlgorithm, SourceHash};
     use crate::json::{JsonSerializable, from_json};
 
+    fn content_hash() -> ContentHash {
+        ContentHash::build(HashAlgorithm::Sha256, "bar").unwrap()
+    }
+
     #[test]
     fn test_build() {
-        assert!(SourceHash::build("").is_err());
+        assert!(SourceHash::build("", content_hash(), Vec::new(), Vec::new()).is_err());
+        assert!(ContentHash::build(HashAlgorithm::Blake3, "").is_err());
+        assert_eq!(content_hash().to_string(), "sha256:bar");
     }
 
     #[test]
     fn test_serialization() {
         assert_eq!(
-            SourceHash::build("foo").unwrap().to_json().unwrap(),
+            SourceHash::build("foo", content_hash(), Vec::new(), Vec::new())
+                .unwrap()
+                .to_json()
+                .unwrap(),
+            r#"{
+  "content-hash": {
+    "algorithm": "sha256",
+    "digest": "bar"
+  },
+  "source-hash": "foo"
+}"#
+        );
+        assert_eq!(
+            SourceHash::build(
+                "foo",
+                content_hash(),
+                Vec::new(),
+                vec![String::from("a/b.jpg")]
+            )
+            .unwrap()
+            .to_json()
+            .unwrap(),
+            r#"{
+  "content-hash": {
+    "algorithm": "sha256",
+    "digest": "bar"
+  },
+  "hash-inputs": [
+    "a/b.jpg"
+  ],
+  "source-hash": "foo"
+}"#
+        );
+        let mut h = SourceHash::build(
+            "foo",
+            content_hash(),
+            vec![String::from("a:dep")],
+            Vec::new(),
+        )
+        .unwrap();
+        h.hash_src = Some(String::from("a/doc.md"));
+        h.hash_generated = vec![String::from("a/doc.md")];
+        assert_eq!(
+            h.to_json().unwrap(),
             r#"{
+  "content-hash": {
+    "algorithm": "sha256",
+    "digest": "bar"
+  },
+  "hash-deps": [
+    "a:dep"
+  ],
+  "hash-generated": [
+    "a/doc.md"
+  ],
+  "hash-src": "a/doc.md",
   "source-hash": "foo"
 }"#
         )
@@ -614,16 +1199,40 @@ mod source_hash_test {
     fn test_deserialization() {
         let h: SourceHash = from_json(
             r#"{
-  "source-hash": "foo"
+  "source-hash": "foo",
+  "content-hash": {
+    "algorithm": "blake3",
+    "digest": "bar"
+  }
 }"#,
         )
         .unwrap();
         assert_eq!(h.source_hash, "foo");
+        assert_eq!(
+            h.content_hash,
+            ContentHash::build(HashAlgorithm::Blake3, "bar").unwrap()
+        );
 
         assert!(
             from_json::<SourceHash>(
                 r#"{
   "source-hash": "",
+  "content-hash": {
+    "algorithm": "sha256",
+    "digest": "bar"
+  }
+}"#
+            )
+            .is_err()
+        );
+        assert!(
+            from_json::<SourceHash>(
+                r#"{
+  "source-hash": "foo",
+  "content-hash": {
+    "algorithm": "md5",
+    "digest": "bar"
+  }
 }"#
             )
             .is_err()
@@ -945,6 +1554,19 @@ mod input_metadata_test {
         assert!(m.identifiers().is_empty());
     }
 
+    #[test]
+    fn test_deserialization_roles() {
+        let m = from_json::<InputMetadata>(
+            r#"{"author": ["foo", "bar"], "translator": "baz", "illustrator": ["quux"]}"#,
+        )
+        .unwrap();
+        assert_eq!(m.authors(), &["foo", "bar"]);
+        assert_eq!(m.translators(), &["baz"]);
+        assert_eq!(m.illustrators(), &["quux"]);
+
+        assert!(from_json::<InputMetadata>(r#"{"translator": [""]}"#).is_err());
+    }
+
     #[test]
     fn test_deserialization_full() {
         let m = from_json::<InputMetadata>(
@@ -1018,7 +1640,7 @@ mod input_metadata_test {
         assert_eq!(p.published().copied().unwrap(), ymd(2023, 5, 18));
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
 
         let p = &ps.publications()[1];
         assert_eq!(p.venue(), "Book2");
@@ -1031,7 +1653,7 @@ mod input_metadata_test {
         assert!(p.published().is_none());
         assert_eq!(p.urls(), &["foo2", "bar2"]);
         assert_eq!(p.notes().unwrap(), "baz2");
-        assert_eq!(p.paid().unwrap(), "quux2");
+        assert_eq!(p.paid().unwrap().to_string(), "quux2");
 
         assert_eq!(
             m.identifiers(),
@@ -1304,7 +1926,7 @@ mod output_metadata_test {
         assert_eq!(p.published().copied().unwrap(), ymd(2023, 5, 18));
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
 
         let p = &ps.publications()[1];
         assert_eq!(p.venue(), "Book2");
@@ -1317,7 +1939,7 @@ mod output_metadata_test {
         assert!(p.published().is_none());
         assert_eq!(p.urls(), &["foo2", "bar2"]);
         assert_eq!(p.notes().unwrap(), "baz2");
-        assert_eq!(p.paid().unwrap(), "quux2");
+        assert_eq!(p.paid().unwrap().to_string(), "quux2");
 
         assert_eq!(
             m.identifiers(),
@@ -0,0 +1,585 @@
+use crate::{
+    field_validators,
+    json::JsonSerializable,
+    schema::{self, JsonSchema},
+    validation,
+};
+use regex::Regex;
+use serde::{Deserialize, Deserializer, Serialize, Serializer, de};
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+use std::iter::Sum;
+use std::ops::{Add, AddAssign};
+use std::sync::LazyLock;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+// Free-text payment forms; see Payment::parse.
+static SYMBOL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\s*(?P<symbol>[£$€])\s*(?P<amount>\d+(?:\.\d+)?)\s*(?P<per_word>/\s*word)?\s*$")
+        .unwrap()
+});
+static MINOR: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:(?P<code1>[A-Z]{3})\s*)?(?P<amount>\d+(?:\.\d+)?)\s*(?P<unit>c|p)\s*/\s*word\s*(?P<code2>[A-Z]{3})?\s*$",
+    )
+    .unwrap()
+});
+static CODE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\s*(?:(?P<code1>[A-Z]{3})\s*)?(?P<amount>\d+(?:\.\d+)?)\s*(?P<code2>[A-Z]{3})?\s*(?P<per_word>/\s*word)?\s*$",
+    )
+    .unwrap()
+});
+
+const MONEY_PLACES: usize = 6;
+const MONEY_SCALE: u64 = 1_000_000;
+
+/// An exact, non-negative amount of money, stored in millionths of the
+/// currency's major unit so that per-word rates in fractions of a cent add up
+/// without rounding. It is only rounded when displayed.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Default)]
+pub struct Money(u64);
+
+impl Money {
+    pub const ZERO: Money = Money(0);
+
+    /// Parses a decimal with at most six decimal places, e.g. "12.50".
+    pub fn parse(s: &str) -> Option<Money> {
+        let (whole, frac) = s.split_once('.').unwrap_or((s, ""));
+        if whole.is_empty()
+            || frac.len() > MONEY_PLACES
+            || !whole
+                .bytes()
+                .chain(frac.bytes())
+                .all(|b| b.is_ascii_digit())
+        {
+            return None;
+        }
+        let whole = whole.parse::<u64>().ok()?.checked_mul(MONEY_SCALE)?;
+        let frac = format!("{frac:0<MONEY_PLACES$}").parse::<u64>().ok()?;
+        Some(Money(whole.checked_add(frac)?))
+    }
+
+    /// The amount in hundredths, e.g. cents as a fraction of a dollar, or
+    /// None if that can't be represented exactly.
+    fn hundredths(self) -> Option<Money> {
+        if !self.0.is_multiple_of(100) {
+            return None;
+        }
+        Some(Money(self.0 / 100))
+    }
+
+    pub fn times(self, n: u32) -> Money {
+        Money(self.0.saturating_mul(n as u64))
+    }
+
+    /// The amount divided into n parts, rounded to the nearest millionth.
+    pub fn divided_by(self, n: u32) -> Option<Money> {
+        if n == 0 {
+            return None;
+        }
+        let n = n as u64;
+        Some(Money((self.0 + n / 2) / n))
+    }
+}
+
+impl Display for Money {
+    /// Formats the exact amount with at least two decimal places, or rounds
+    /// to the given precision, e.g. "{:.3}".
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match f.precision().map(|p| p.min(MONEY_PLACES)) {
+            Some(places) => {
+                let unit = 10u64.pow((MONEY_PLACES - places) as u32);
+                let rounded = self.0.saturating_add(unit / 2) / unit;
+                let scale = 10u64.pow(places as u32);
+                if places == 0 {
+                    return write!(f, "{rounded}");
+                }
+                write!(f, "{}.{:0places$}", rounded / scale, rounded % scale)
+            }
+            None => {
+                let frac = format!("{:0MONEY_PLACES$}", self.0 % MONEY_SCALE);
+                let frac = frac.trim_end_matches('0');
+                write!(f, "{}.{frac:0<2}", self.0 / MONEY_SCALE)
+            }
+        }
+    }
+}
+
+impl Add for Money {
+    type Output = Money;
+
+    fn add(self, other: Money) -> Money {
+        Money(self.0.saturating_add(other.0))
+    }
+}
+
+impl AddAssign for Money {
+    fn add_assign(&mut self, other: Money) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for Money {
+    fn sum<I: Iterator<Item = Money>>(iter: I) -> Money {
+        iter.fold(Money::ZERO, Add::add)
+    }
+}
+
+// Money is a plain number in json, as it was before it was stored exactly.
+impl Serialize for Money {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_f64(self.0 as f64 / MONEY_SCALE as f64)
+    }
+}
+
+impl<'de> Deserialize<'de> for Money {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Money, D::Error> {
+        // The shortest representation of the parsed float is the number as
+        // written in the json.
+        let v = f64::deserialize(deserializer)?;
+        Money::parse(&v.to_string()).ok_or_else(|| {
+            de::Error::custom(format!(
+                "invalid amount {v}: must be non-negative, with at most {MONEY_PLACES} decimal places"
+            ))
+        })
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum PaymentStatus {
+    Pending,
+    Received,
+}
+
+impl Display for PaymentStatus {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        f.write_str(&format!("{:?}", self).to_ascii_lowercase())
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+#[validate(schema(function = "Payment::validate_contents"))]
+pub struct Payment {
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    amount: Option<Money>,
+
+    #[validate(custom(function = "field_validators::non_empty"))]
+    currency: String,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rate_per_word: Option<Money>,
+
+    status: PaymentStatus,
+}
+
+impl Payment {
+    pub fn build<S: Into<String>>(
+        amount: Option<Money>,
+        currency: S,
+        rate_per_word: Option<Money>,
+        status: PaymentStatus,
+    ) -> Result<Payment, ValidationErrors> {
+        let p = Payment {
+            amount,
+            currency: currency.into(),
+            rate_per_word,
+            status,
+        };
+        p.validate()?;
+        Ok(p)
+    }
+
+    /// Parses the free-text form used before payments were structured, e.g.
+    /// "£10", "50 USD", "2p/word" or "8c/word USD". Returns None if the text
+    /// isn't recognised, including cents with no currency code, since many
+    /// currencies have cents. Free-text payments are assumed to have been
+    /// received.
+    pub fn parse(text: &str) -> Option<Payment> {
+        let (amount, currency, per_word) = if let Some(caps) = SYMBOL.captures(text) {
+            let currency = match &caps["symbol"] {
+                "£" => "GBP",
+                "$" => "USD",
+                _ => "EUR",
+            };
+            (
+                Money::parse(&caps["amount"])?,
+                currency,
+                caps.name("per_word").is_some(),
+            )
+        } else if let Some(caps) = MINOR.captures(text) {
+            let currency = match (&caps["unit"], caps.name("code1"), caps.name("code2")) {
+                (_, Some(c), None) | (_, None, Some(c)) => c.as_str(),
+                ("p", None, None) => "GBP",
+                _ => return None,
+            };
+            (Money::parse(&caps["amount"])?.hundredths()?, currency, true)
+        } else if let Some(caps) = CODE.captures(text) {
+            let currency = match (caps.name("code1"), caps.name("code2")) {
+                (Some(c), None) | (None, Some(c)) => c.as_str(),
+                _ => return None,
+            };
+            (
+                Money::parse(&caps["amount"])?,
+                currency,
+                caps.name("per_word").is_some(),
+            )
+        } else {
+            return None;
+        };
+
+        let (amount, rate_per_word) = if per_word {
+            (None, Some(amount))
+        } else {
+            (Some(amount), None)
+        };
+        Payment::build(amount, currency, rate_per_word, PaymentStatus::Received).ok()
+    }
+
+    pub fn amount(&self) -> Option<Money> {
+        self.amount
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    pub fn rate_per_word(&self) -> Option<Money> {
+        self.rate_per_word
+    }
+
+    pub fn status(&self) -> PaymentStatus {
+        self.status
+    }
+
+    /// The total amount for a piece of the given length, computed from the
+    /// per-word rate if no amount is set.
+    pub fn amount_for(&self, wordcount: u32) -> Money {
+        if let Some(amount) = self.amount {
+            return amount;
+        }
+        self.rate_per_word.unwrap_or_default().times(wordcount)
+    }
+
+    /// The per-word rate for a piece of the given length, computed from the
+    /// amount if no rate is set.
+    pub fn rate_for(&self, wordcount: u32) -> Option<Money> {
+        if self.rate_per_word.is_some() {
+            return self.rate_per_word;
+        }
+        self.amount.and_then(|a| a.divided_by(wordcount))
+    }
+
+    fn validate_contents(&self) -> Result<(), ValidationError> {
+        if self.amount.is_none() && self.rate_per_word.is_none() {
+            return Err(validation::error_with_fix(
+                "at least one of amount and rate-per-word must be set",
+                "add the amount paid, or the rate-per-word",
+            ));
+        }
+        Ok(())
+    }
+}
+
+impl Display for Payment {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        let mut parts = Vec::new();
+        if let Some(amount) = self.amount {
+            parts.push(format!("{amount} {}", self.currency));
+        }
+        if let Some(rate) = self.rate_per_word {
+            parts.push(format!("{rate} {}/word", self.currency));
+        }
+        parts.push(self.status.to_string());
+        f.write_str(&parts.join(", "))
+    }
+}
+
+impl JsonSerializable for Payment {}
+
+impl JsonSchema for Payment {
+    fn json_schema() -> Value {
+        schema::object(
+            vec![
+                ("amount", schema::number()),
+                ("currency", schema::non_empty_string()),
+                ("rate-per-word", schema::number()),
+                ("status", schema::string_enum(&["pending", "received"])),
+            ],
+            &["currency", "status"],
+        )
+    }
+}
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum Paid {
+    // Free text, as used before payments were structured
+    Text(String),
+    Payment(Payment),
+}
+
+impl Paid {
+    /// The structured form of the payment, parsing free text if needed.
+    pub fn payment(&self) -> Option<Payment> {
+        match self {
+            Paid::Text(text) => Payment::parse(text),
+            Paid::Payment(p) => Some(p.clone()),
+        }
+    }
+}
+
+impl JsonSchema for Paid {
+    fn json_schema() -> Value {
+        schema::one_of(vec![schema::optional_string(), Payment::json_schema()])
+    }
+}
+
+impl Validate for Paid {
+    fn validate(&self) -> Result<(), ValidationErrors> {
+        match self {
+            Paid::Text(text) => {
+                if let Err(e) = field_validators::non_empty(text) {
+                    let mut errs = ValidationErrors::new();
+                    errs.add("paid", e);
+                    return Err(errs);
+                }
+                Ok(())
+            }
+            Paid::Payment(p) => p.validate(),
+        }
+    }
+}
+
+impl Display for Paid {
+    fn fmt(&self, f: &mut Formatter) -> fmt::Result {
+        match self {
+            Paid::Text(text) => f.write_str(text),
+            Paid::Payment(p) => p.fmt(f),
+        }
+    }
+}
+
+impl From<&str> for Paid {
+    fn from(text: &str) -> Self {
+        Paid::Text(String::from(text))
+    }
+}
+
+impl From<Payment> for Paid {
+    fn from(p: Payment) -> Self {
+        Paid::Payment(p)
+    }
+}
+
+pub fn option_paid<'de, D>(deserializer: D) -> Result<Option<Paid>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    match Paid::deserialize(deserializer)? {
+        Paid::Text(text) if text.is_empty() => Ok(None),
+        p => Ok(Some(p)),
+    }
+}
+
+#[cfg(test)]
+mod payment_test {
+    use super::{Money, Paid, Payment, PaymentStatus};
+    use crate::json::{JsonSerializable, from_json};
+
+    fn money(s: &str) -> Option<Money> {
+        Some(Money::parse(s).unwrap())
+    }
+
+    #[test]
+    fn test_money_parse() {
+        assert_eq!(Money::parse("10"), Some(Money(10_000_000)));
+        assert_eq!(Money::parse("12.50"), Some(Money(12_500_000)));
+        assert_eq!(Money::parse("0.000001"), Some(Money(1)));
+        assert_eq!(Money::parse("0.0000001"), None);
+        assert_eq!(Money::parse("-1"), None);
+        assert_eq!(Money::parse(".5"), None);
+        assert_eq!(Money::parse("1e3"), None);
+        assert_eq!(Money::parse(""), None);
+    }
+
+    #[test]
+    fn test_money_arithmetic() {
+        // 0.1 + 0.2 is exact, unlike with floats
+        assert_eq!(
+            money("0.1").unwrap() + money("0.2").unwrap(),
+            money("0.3").unwrap()
+        );
+        assert_eq!(
+            [money("0.01").unwrap(); 3].into_iter().sum::<Money>(),
+            money("0.03").unwrap()
+        );
+        assert_eq!(money("0.015").unwrap().times(1000), money("15").unwrap());
+        assert_eq!(money("10").unwrap().divided_by(3), money("3.333333"));
+        assert_eq!(money("20").unwrap().divided_by(3), money("6.666667"));
+        assert_eq!(money("10").unwrap().divided_by(0), None);
+    }
+
+    #[test]
+    fn test_money_display() {
+        assert_eq!(money("10").unwrap().to_string(), "10.00");
+        assert_eq!(money("12.5").unwrap().to_string(), "12.50");
+        assert_eq!(money("0.015").unwrap().to_string(), "0.015");
+        assert_eq!(format!("{:.2}", money("0.015").unwrap()), "0.02");
+        assert_eq!(format!("{:.3}", money("6.666667").unwrap()), "6.667");
+        assert_eq!(format!("{:.0}", money("2.5").unwrap()), "3");
+    }
+
+    #[test]
+    fn test_money_serialization() {
+        assert_eq!(serde_json::to_string(&money("0.08")).unwrap(), "0.08");
+        assert_eq!(serde_json::to_string(&money("10")).unwrap(), "10.0");
+        assert_eq!(
+            from_json::<Payment>(r#"{"amount": 0.07, "currency": "USD", "status": "pending"}"#)
+                .unwrap()
+                .amount(),
+            money("0.07")
+        );
+        assert_eq!(serde_json::from_str::<Money>("20").ok(), money("20"));
+        assert!(serde_json::from_str::<Money>("-1").is_err());
+        assert!(serde_json::from_str::<Money>("0.0000001").is_err());
+    }
+
+    #[test]
+    fn test_build() {
+        assert!(Payment::build(money("10"), "GBP", None, PaymentStatus::Pending).is_ok());
+        assert!(Payment::build(None, "GBP", money("0.08"), PaymentStatus::Pending).is_ok());
+        assert!(Payment::build(None, "GBP", None, PaymentStatus::Pending).is_err());
+        assert!(Payment::build(money("10"), "", None, PaymentStatus::Pending).is_err());
+    }
+
+    #[test]
+    fn test_amount_and_rate() {
+        let p = Payment::build(money("10"), "GBP", None, PaymentStatus::Received).unwrap();
+        assert_eq!(p.amount_for(100), money("10").unwrap());
+        assert_eq!(p.rate_for(100), money("0.1"));
+        assert_eq!(p.rate_for(0), None);
+
+        let p = Payment::build(None, "USD", money("0.08"), PaymentStatus::Received).unwrap();
+        assert_eq!(p.amount_for(1000), money("80").unwrap());
+        assert_eq!(p.rate_for(1000), money("0.08"));
+    }
+
+    #[test]
+    fn test_parse() {
+        let received = |amount, currency: &str, rate| {
+            Some(Payment::build(amount, currency, rate, PaymentStatus::Received).unwrap())
+        };
+        assert_eq!(Payment::parse("£10"), received(money("10"), "GBP", None));
+        assert_eq!(
+            Payment::parse("$12.50"),
+            received(money("12.5"), "USD", None)
+        );
+        assert_eq!(Payment::parse("€5"), received(money("5"), "EUR", None));
+        assert_eq!(Payment::parse("50 AUD"), received(money("50"), "AUD", None));
+        assert_eq!(Payment::parse("CAD 50"), received(money("50"), "CAD", None));
+        assert_eq!(
+            Payment::parse("$0.08/word"),
+            received(None, "USD", money("0.08"))
+        );
+        assert_eq!(
+            Payment::parse("8c/word USD"),
+            received(None, "USD", money("0.08"))
+        );
+        assert_eq!(
+            Payment::parse("CAD 1.5c/word"),
+            received(None, "CAD", money("0.015"))
+        );
+        assert_eq!(
+            Payment::parse("2p / word"),
+            received(None, "GBP", money("0.02"))
+        );
+        // Cents with no currency are ambiguous
+        assert_eq!(Payment::parse("8c/word"), None);
+        assert_eq!(Payment::parse("USD 8c/word CAD"), None);
+        assert_eq!(Payment::parse("contributor copy"), None);
+        assert_eq!(Payment::parse("50"), None);
+        assert_eq!(Payment::parse("USD 50 GBP"), None);
+    }
+
+    #[test]
+    fn test_display() {
+        assert_eq!(
+            Payment::build(money("10"), "GBP", money("0.015"), PaymentStatus::Received)
+                .unwrap()
+                .to_string(),
+            "10.00 GBP, 0.015 GBP/word, received"
+        );
+        assert_eq!(
+            Payment::build(money("10"), "GBP", None, PaymentStatus::Pending)
+                .unwrap()
+                .to_string(),
+            "10.00 GBP, pending"
+        );
+        assert_eq!(Paid::from("£10").to_string(), "£10");
+    }
+
+    #[test]
+    fn test_serialization() {
+        assert_eq!(
+            Payment::build(money("10"), "GBP", money("0.01"), PaymentStatus::Received)
+                .unwrap()
+                .to_json()
+                .unwrap(),
+            r#"{
+  "amount": 10.0,
+  "currency": "GBP",
+  "rate-per-word": 0.01,
+  "status": "received"
+}"#
+        );
+    }
+
+    #[test]
+    fn test_deserialization() {
+        let p: Paid = from_json(r#""£10""#).unwrap();
+        assert_eq!(p, Paid::from("£10"));
+        assert_eq!(
+            p.payment(),
+            Payment::build(money("10"), "GBP", None, PaymentStatus::Received).ok()
+        );
+
+        let p: Paid = from_json(
+            r#"{
+  "amount": 10,
+  "currency": "GBP",
+  "status": "pending"
+}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            p.payment(),
+            Payment::build(money("10"), "GBP", None, PaymentStatus::Pending).ok()
+        );
+
+        assert!(from_json::<Paid>(r#""""#).is_err());
+        assert!(from_json::<Paid>(r#"{"currency": "GBP", "status": "pending"}"#).is_err());
+        assert!(
+            from_json::<Paid>(r#"{"amount": 1, "currency": "GBP", "status": "lost"}"#).is_err()
+        );
+        // The date of a payment is the publication's paid-date
+        assert!(
+            from_json::<Paid>(
+                r#"{"amount": 1, "currency": "GBP", "date": "2023-01-01", "status": "received"}"#
+            )
+            .is_err()
+        );
+        assert!(
+            from_json::<Paid>(r#"{"amount": -1, "currency": "GBP", "status": "pending"}"#).is_err()
+        );
+        assert!(
+            from_json::<Paid>(r#"{"rate-per-word": -1, "currency": "GBP", "status": "pending"}"#)
+                .is_err()
+        );
+    }
+}
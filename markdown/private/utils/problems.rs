@@ -1,10 +1,65 @@
+use clap::ValueEnum;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::BTreeSet;
+use std::env;
 use std::fmt::{Display, Formatter};
+use std::fs;
+use std::path::Path;
 use std::process;
 
-#[derive(Clone, Debug)]
+/// Environment variable that sets the problems output format, for binaries
+/// that don't set it with a flag.
+pub const FORMAT_ENV_VAR: &str = "MARKDOWN_PROBLEMS_FORMAT";
+
+const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Severity {
+    #[default]
+    Error,
+    Warning,
+    Info,
+}
+
+impl Severity {
+    fn sarif_level(&self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Info => "note",
+        }
+    }
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Severity::Error => write!(f, "error"),
+            Severity::Warning => write!(f, "warning"),
+            Severity::Info => write!(f, "info"),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub enum Format {
+    /// Human-readable text
+    #[default]
+    Text,
+    /// One JSON object per problem, per line
+    JsonLines,
+    /// A SARIF 2.1.0 log
+    Sarif,
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct RowProblem {
     row: usize,
     msg: String,
+    file: Option<String>,
+    rule: Option<String>,
 }
 
 impl RowProblem {
@@ -12,9 +67,21 @@ impl RowProblem {
         RowProblem {
             row,
             msg: String::from(msg),
+            file: None,
+            rule: None,
         }
     }
 
+    pub fn with_file(mut self, file: &str) -> RowProblem {
+        self.file = Some(String::from(file));
+        self
+    }
+
+    pub fn with_rule(mut self, rule: &str) -> RowProblem {
+        self.rule = Some(String::from(rule));
+        self
+    }
+
     pub fn row(&self) -> usize {
         self.row
     }
@@ -22,6 +89,14 @@ impl RowProblem {
     pub fn msg(&self) -> &str {
         &self.msg
     }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
 }
 
 impl Display for RowProblem {
@@ -30,10 +105,12 @@ impl Display for RowProblem {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct ColProblem {
     col: usize,
     msg: String,
+    file: Option<String>,
+    rule: Option<String>,
 }
 
 impl ColProblem {
@@ -41,9 +118,21 @@ impl ColProblem {
         ColProblem {
             col,
             msg: String::from(msg),
+            file: None,
+            rule: None,
         }
     }
 
+    pub fn with_file(mut self, file: &str) -> ColProblem {
+        self.file = Some(String::from(file));
+        self
+    }
+
+    pub fn with_rule(mut self, rule: &str) -> ColProblem {
+        self.rule = Some(String::from(rule));
+        self
+    }
+
     pub fn col(&self) -> usize {
         self.col
     }
@@ -52,16 +141,32 @@ impl ColProblem {
         &self.msg
     }
 
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
+
     pub fn add_row(self, row: usize) -> RowColProblem {
-        RowColProblem::new(row, self.col, &self.msg)
+        RowColProblem {
+            row,
+            col: self.col,
+            msg: self.msg,
+            file: self.file,
+            rule: self.rule,
+        }
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, PartialEq)]
 pub struct RowColProblem {
     row: usize,
     col: usize,
     msg: String,
+    file: Option<String>,
+    rule: Option<String>,
 }
 
 impl RowColProblem {
@@ -70,9 +175,21 @@ impl RowColProblem {
             row,
             col,
             msg: String::from(msg),
+            file: None,
+            rule: None,
         }
     }
 
+    pub fn with_file(mut self, file: &str) -> RowColProblem {
+        self.file = Some(String::from(file));
+        self
+    }
+
+    pub fn with_rule(mut self, rule: &str) -> RowColProblem {
+        self.rule = Some(String::from(rule));
+        self
+    }
+
     pub fn row(&self) -> usize {
         self.row
     }
@@ -84,6 +201,14 @@ impl RowColProblem {
     pub fn msg(&self) -> &str {
         &self.msg
     }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
 }
 
 impl Display for RowColProblem {
@@ -92,52 +217,365 @@ impl Display for RowColProblem {
     }
 }
 
+/// Any kind of problem, with everything needed to output it in a
+/// machine-readable format. Rows and columns are 0-based.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Diagnostic {
+    file: Option<String>,
+    row: Option<usize>,
+    col: Option<usize>,
+    severity: Severity,
+    rule: Option<String>,
+    msg: String,
+}
+
+impl Diagnostic {
+    pub fn new(msg: &str) -> Diagnostic {
+        Diagnostic {
+            file: None,
+            row: None,
+            col: None,
+            severity: Severity::Error,
+            rule: None,
+            msg: String::from(msg),
+        }
+    }
+
+    pub fn with_file(mut self, file: &str) -> Diagnostic {
+        self.file = Some(String::from(file));
+        self
+    }
+
+    pub fn with_rule(mut self, rule: &str) -> Diagnostic {
+        self.rule = Some(String::from(rule));
+        self
+    }
+
+    pub fn with_severity(mut self, severity: Severity) -> Diagnostic {
+        self.severity = severity;
+        self
+    }
+
+    pub fn file(&self) -> Option<&str> {
+        self.file.as_deref()
+    }
+
+    pub fn row(&self) -> Option<usize> {
+        self.row
+    }
+
+    pub fn col(&self) -> Option<usize> {
+        self.col
+    }
+
+    pub fn severity(&self) -> Severity {
+        self.severity
+    }
+
+    pub fn rule(&self) -> Option<&str> {
+        self.rule.as_deref()
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    fn to_json(&self, default_file: Option<&str>) -> String {
+        let out = JsonDiagnostic {
+            file: self.file.as_deref().or(default_file),
+            row: self.row.map(|r| r + 1),
+            column: self.col.map(|c| c + 1),
+            severity: self.severity,
+            rule: self.rule.as_deref(),
+            message: &self.msg,
+        };
+        serde_json::to_string(&out).unwrap()
+    }
+
+    fn to_sarif(&self, default_file: Option<&str>) -> serde_json::Value {
+        let mut result = json!({
+            "level": self.severity.sarif_level(),
+            "message": {"text": self.msg},
+        });
+        if let Some(rule) = &self.rule {
+            result["ruleId"] = json!(rule);
+        }
+        if let Some(file) = self.file.as_deref().or(default_file) {
+            let mut location = json!({"artifactLocation": {"uri": file}});
+            if let Some(row) = self.row {
+                location["region"] = json!({"startLine": row + 1});
+                if let Some(col) = self.col {
+                    location["region"]["startColumn"] = json!(col + 1);
+                }
+            }
+            result["locations"] = json!([{ "physicalLocation": location }]);
+        }
+        result
+    }
+}
+
+// A Diagnostic as output in JSON lines, with 1-based rows and columns.
+#[derive(Serialize)]
+struct JsonDiagnostic<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    file: Option<&'a str>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    row: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+    severity: Severity,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    rule: Option<&'a str>,
+    message: &'a str,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match (self.row, self.col) {
+            (Some(row), Some(col)) => write!(f, "row {} col {}: {}", row + 1, col + 1, self.msg),
+            (Some(row), None) => write!(f, "row {}: {}", row + 1, self.msg),
+            (None, Some(col)) => write!(f, "col {}: {}", col + 1, self.msg),
+            (None, None) => write!(f, "{}", self.msg),
+        }
+    }
+}
+
+impl From<String> for Diagnostic {
+    fn from(msg: String) -> Diagnostic {
+        Diagnostic::new(&msg)
+    }
+}
+
+impl From<RowProblem> for Diagnostic {
+    fn from(p: RowProblem) -> Diagnostic {
+        Diagnostic {
+            file: p.file,
+            row: Some(p.row),
+            col: None,
+            severity: Severity::Error,
+            rule: p.rule,
+            msg: p.msg,
+        }
+    }
+}
+
+impl From<ColProblem> for Diagnostic {
+    fn from(p: ColProblem) -> Diagnostic {
+        Diagnostic {
+            file: p.file,
+            row: None,
+            col: Some(p.col),
+            severity: Severity::Error,
+            rule: p.rule,
+            msg: p.msg,
+        }
+    }
+}
+
+impl From<RowColProblem> for Diagnostic {
+    fn from(p: RowColProblem) -> Diagnostic {
+        Diagnostic {
+            file: p.file,
+            row: Some(p.row),
+            col: Some(p.col),
+            severity: Severity::Error,
+            rule: p.rule,
+            msg: p.msg,
+        }
+    }
+}
+
 pub struct Problems {
     err_msg: String,
-    problems: Vec<Box<dyn Display>>,
+    file: Option<String>,
+    format: Option<Format>,
+    warnings_as_errors: bool,
+    report_file: Option<String>,
+    problems: Vec<Diagnostic>,
 }
 
 impl Problems {
     pub fn new(err_msg: &str) -> Problems {
         Problems {
             err_msg: String::from(err_msg),
+            file: None,
+            format: None,
+            warnings_as_errors: false,
+            report_file: None,
             problems: Vec::new(),
         }
     }
 
-    pub fn push(&mut self, p: Box<dyn Display>) {
+    /// Sets the file for problems that don't have their own.
+    pub fn with_file(mut self, file: &str) -> Problems {
+        self.file = Some(String::from(file));
+        self
+    }
+
+    /// Sets the output format; if None, it's taken from FORMAT_ENV_VAR, or
+    /// defaults to text.
+    pub fn with_format(mut self, format: Option<Format>) -> Problems {
+        self.format = format;
+        self
+    }
+
+    /// Makes warnings fatal, like errors.
+    pub fn with_warnings_as_errors(mut self, warnings_as_errors: bool) -> Problems {
+        self.warnings_as_errors = warnings_as_errors;
+        self
+    }
+
+    /// Writes problems that aren't fatal here, rather than to stderr. The file
+    /// is always written, even if there are no such problems.
+    pub fn with_report_file(mut self, report_file: &str) -> Problems {
+        self.report_file = Some(String::from(report_file));
+        self
+    }
+
+    pub fn push(&mut self, p: impl Into<Diagnostic>) {
+        self.problems.push(p.into());
+    }
+
+    pub fn push_diagnostic(&mut self, p: Diagnostic) {
         self.problems.push(p);
     }
 
-    fn check_internal(&self) -> Option<String> {
-        if self.problems.is_empty() {
+    fn format(&self) -> Format {
+        self.format
+            .or_else(|| {
+                env::var(FORMAT_ENV_VAR)
+                    .ok()
+                    .and_then(|v| Format::from_str(&v, true).ok())
+            })
+            .unwrap_or_default()
+    }
+
+    /// Whether a problem fails the check, given its severity and the policy.
+    pub fn is_fatal(&self, p: &Diagnostic) -> bool {
+        match p.severity {
+            Severity::Error => true,
+            Severity::Warning => self.warnings_as_errors,
+            Severity::Info => false,
+        }
+    }
+
+    // Fatal problems are all output as errors.
+    fn fatal(&self) -> Vec<Diagnostic> {
+        self.problems
+            .iter()
+            .filter(|p| self.is_fatal(p))
+            .map(|p| p.clone().with_severity(Severity::Error))
+            .collect()
+    }
+
+    fn non_fatal(&self) -> Vec<Diagnostic> {
+        self.problems
+            .iter()
+            .filter(|p| !self.is_fatal(p))
+            .cloned()
+            .collect()
+    }
+
+    fn sarif(&self, problems: &[Diagnostic], tool: &str) -> String {
+        let file = self.file.as_deref();
+        let rules: BTreeSet<&str> = problems.iter().filter_map(|p| p.rule()).collect();
+        let log = json!({
+            "$schema": SARIF_SCHEMA,
+            "version": "2.1.0",
+            "runs": [{
+                "tool": {
+                    "driver": {
+                        "name": tool,
+                        "rules": rules.iter().map(|r| json!({"id": r})).collect::<Vec<_>>(),
+                    },
+                },
+                "results": problems.iter().map(|p| p.to_sarif(file)).collect::<Vec<_>>(),
+            }],
+        });
+        serde_json::to_string_pretty(&log).unwrap() + "\n"
+    }
+
+    fn json_lines(&self, problems: &[Diagnostic]) -> String {
+        let file = self.file.as_deref();
+        problems.iter().map(|p| p.to_json(file) + "\n").collect()
+    }
+
+    fn check_internal(&self, format: Format, tool: &str) -> Option<String> {
+        let problems = self.fatal();
+        if problems.is_empty() {
             return None;
         }
 
-        let mut msg = vec![format!("ERROR: {}", self.err_msg)];
-        for p in self.problems.iter() {
-            msg.push(format!("  {}", p));
+        match format {
+            Format::Text => {
+                let mut msg = vec![format!("ERROR: {}", self.err_msg)];
+                for p in problems.iter() {
+                    msg.push(format!("  {}", p));
+                }
+                Some(format!("{}\n\n", msg.join("\n\n")))
+            }
+            Format::JsonLines => Some(self.json_lines(&problems)),
+            Format::Sarif => Some(self.sarif(&problems, tool)),
+        }
+    }
+
+    // Problems that aren't fatal, which may be empty.
+    fn report(&self, format: Format, tool: &str) -> String {
+        let problems = self.non_fatal();
+        match format {
+            Format::Text => problems
+                .iter()
+                .map(|p| format!("{}: {p}\n", p.severity().to_string().to_uppercase()))
+                .collect(),
+            Format::JsonLines => self.json_lines(&problems),
+            Format::Sarif => self.sarif(&problems, tool),
         }
-        Some(format!("{}\n\n", msg.join("\n\n")))
     }
 
     pub fn check(&self) {
-        if let Some(msg) = self.check_internal() {
-            eprintln!("{}", msg);
+        let tool = env::args()
+            .next()
+            .and_then(|a| {
+                Path::new(&a)
+                    .file_name()
+                    .map(|n| n.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        let format = self.format();
+
+        match &self.report_file {
+            Some(path) => {
+                if let Err(e) = fs::write(path, self.report(format, &tool)) {
+                    eprintln!("ERROR: failed to write {path}: {e}");
+                    process::exit(1);
+                }
+            }
+            None => {
+                if !self.non_fatal().is_empty() {
+                    eprint!("{}", self.report(format, &tool));
+                }
+            }
+        }
+
+        if let Some(msg) = self.check_internal(format, &tool) {
+            match format {
+                Format::Text => eprintln!("{}", msg),
+                _ => eprint!("{}", msg),
+            }
             process::exit(1);
         }
     }
 }
 
-impl Extend<Box<dyn Display>> for Problems {
-    fn extend<T: IntoIterator<Item = Box<dyn Display>>>(&mut self, iter: T) {
+impl Extend<Diagnostic> for Problems {
+    fn extend<T: IntoIterator<Item = Diagnostic>>(&mut self, iter: T) {
         self.problems.extend(iter);
     }
 }
 
 #[cfg(test)]
 mod test_problems {
-    use super::{ColProblem, Display, Problems, RowColProblem, RowProblem};
+    use super::{ColProblem, Diagnostic, Format, Problems, RowColProblem, RowProblem, Severity};
 
     #[test]
     fn test_row_problem() {
@@ -170,15 +608,11 @@ mod test_problems {
     #[test]
     fn test_problems() {
         let mut p = Problems::new("foo");
-        assert!(p.check_internal().is_none());
-        p.push(Box::new(String::from("bar")));
-        let v: Vec<Box<dyn Display>> = vec![
-            Box::new(String::from("baz")),
-            Box::new(String::from("quux")),
-        ];
-        p.extend(v);
+        assert!(p.check_internal(Format::Text, "tool").is_none());
+        p.push(String::from("bar"));
+        p.extend([Diagnostic::new("baz"), Diagnostic::new("quux")]);
         assert_eq!(
-            p.check_internal().unwrap(),
+            p.check_internal(Format::Text, "tool").unwrap(),
             "ERROR: foo
 
   bar
@@ -190,4 +624,170 @@ mod test_problems {
 "
         );
     }
+
+    #[test]
+    fn test_file_and_rule() {
+        let p = RowProblem::new(2, "foo").with_file("a.md").with_rule("bar");
+        assert_eq!(p.file(), Some("a.md"));
+        assert_eq!(p.rule(), Some("bar"));
+        assert_eq!(format!("{p}"), "row 3: foo");
+
+        let p = ColProblem::new(2, "foo").with_rule("bar").add_row(1);
+        assert_eq!(p.file(), None);
+        assert_eq!(p.rule(), Some("bar"));
+        assert_eq!(format!("{p}"), "row 2 col 3: foo");
+    }
+
+    #[test]
+    fn test_diagnostic() {
+        let d = Diagnostic::from(RowColProblem::new(2, 5, "foo").with_rule("bar"));
+        assert_eq!(d.row(), Some(2));
+        assert_eq!(d.col(), Some(5));
+        assert_eq!(d.rule(), Some("bar"));
+        assert_eq!(d.severity(), Severity::Error);
+        assert_eq!(format!("{d}"), "row 3 col 6: foo");
+
+        let d = Diagnostic::from(RowProblem::new(2, "foo").with_file("a.md"));
+        assert_eq!(d.file(), Some("a.md"));
+        assert_eq!(format!("{d}"), "row 3: foo");
+
+        let d = Diagnostic::from(ColProblem::new(2, "foo"));
+        assert_eq!(format!("{d}"), "col 3: foo");
+
+        let d = Diagnostic::new("foo").with_severity(Severity::Warning);
+        assert_eq!(d.severity(), Severity::Warning);
+        assert_eq!(format!("{d}"), "foo");
+    }
+
+    fn problems() -> Problems {
+        let mut p = Problems::new("foo").with_file("a.md");
+        p.push(String::from("bar"));
+        p.extend([
+            Diagnostic::from(RowColProblem::new(2, 5, "baz").with_rule("r1")),
+            Diagnostic::from(RowProblem::new(0, "quux").with_file("b.md").with_rule("r2")),
+        ]);
+        p
+    }
+
+    #[test]
+    fn test_problems_text() {
+        assert_eq!(
+            problems().check_internal(Format::Text, "tool").unwrap(),
+            "ERROR: foo
+
+  bar
+
+  row 3 col 6: baz
+
+  row 1: quux
+
+"
+        );
+    }
+
+    #[test]
+    fn test_problems_json_lines() {
+        assert_eq!(
+            problems()
+                .check_internal(Format::JsonLines, "tool")
+                .unwrap(),
+            r#"{"file":"a.md","severity":"error","message":"bar"}
+{"file":"a.md","row":3,"column":6,"severity":"error","rule":"r1","message":"baz"}
+{"file":"b.md","row":1,"severity":"error","rule":"r2","message":"quux"}
+"#
+        );
+        assert!(
+            Problems::new("foo")
+                .check_internal(Format::JsonLines, "tool")
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_problems_sarif() {
+        let sarif: serde_json::Value =
+            serde_json::from_str(&problems().check_internal(Format::Sarif, "tool").unwrap())
+                .unwrap();
+        assert_eq!(
+            sarif,
+            serde_json::json!({
+                "$schema": "https://json.schemastore.org/sarif-2.1.0.json",
+                "version": "2.1.0",
+                "runs": [{
+                    "tool": {"driver": {"name": "tool", "rules": [{"id": "r1"}, {"id": "r2"}]}},
+                    "results": [
+                        {
+                            "level": "error",
+                            "message": {"text": "bar"},
+                            "locations": [{"physicalLocation": {
+                                "artifactLocation": {"uri": "a.md"},
+                            }}],
+                        },
+                        {
+                            "level": "error",
+                            "message": {"text": "baz"},
+                            "ruleId": "r1",
+                            "locations": [{"physicalLocation": {
+                                "artifactLocation": {"uri": "a.md"},
+                                "region": {"startLine": 3, "startColumn": 6},
+                            }}],
+                        },
+                        {
+                            "level": "error",
+                            "message": {"text": "quux"},
+                            "ruleId": "r2",
+                            "locations": [{"physicalLocation": {
+                                "artifactLocation": {"uri": "b.md"},
+                                "region": {"startLine": 1},
+                            }}],
+                        },
+                    ],
+                }],
+            })
+        );
+    }
+
+    fn severities() -> Problems {
+        let mut p = Problems::new("foo");
+        p.extend([
+            Diagnostic::new("bar").with_severity(Severity::Warning),
+            Diagnostic::new("baz").with_severity(Severity::Info),
+        ]);
+        p
+    }
+
+    #[test]
+    fn test_problems_severity() {
+        // Warnings and info aren't fatal by default, so they're only reported
+        let p = severities();
+        assert!(p.check_internal(Format::Text, "tool").is_none());
+        assert_eq!(p.report(Format::Text, "tool"), "WARNING: bar\nINFO: baz\n");
+        assert_eq!(
+            p.report(Format::JsonLines, "tool"),
+            r#"{"severity":"warning","message":"bar"}
+{"severity":"info","message":"baz"}
+"#
+        );
+        let sarif: serde_json::Value =
+            serde_json::from_str(&p.report(Format::Sarif, "tool")).unwrap();
+        assert_eq!(sarif["runs"][0]["results"][0]["level"], "warning");
+        assert_eq!(sarif["runs"][0]["results"][1]["level"], "note");
+
+        // Warnings become errors, but info is still only reported
+        let p = severities().with_warnings_as_errors(true);
+        assert!(p.is_fatal(&Diagnostic::new("bar").with_severity(Severity::Warning)));
+        assert!(!p.is_fatal(&Diagnostic::new("baz").with_severity(Severity::Info)));
+        assert_eq!(
+            p.check_internal(Format::Text, "tool").unwrap(),
+            "ERROR: foo\n\n  bar\n\n"
+        );
+        assert_eq!(
+            p.check_internal(Format::JsonLines, "tool").unwrap(),
+            "{\"severity\":\"error\",\"message\":\"bar\"}\n"
+        );
+        assert_eq!(p.report(Format::Text, "tool"), "INFO: baz\n");
+
+        // Errors aren't in the report
+        assert_eq!(problems().report(Format::Text, "tool"), "");
+    }
 }
@@ -1,7 +1,15 @@
-use crate::{deserializers, field_validators, json::JsonSerializable};
+use crate::{
+    deserializers, field_validators,
+    json::JsonSerializable,
+    payments::{self, Paid, PaymentStatus},
+    schema::{self, JsonSchema},
+    validation,
+    venues::VenueRegistry,
+};
 use chrono::naive::NaiveDate;
 use derive_builder::Builder;
 use serde::{Deserialize, Serialize};
+use serde_json::Value;
 use std::fmt::{self, Debug, Display, Formatter};
 use validator::{Validate, ValidationError, ValidationErrors};
 
@@ -9,7 +17,10 @@ use validator::{Validate, ValidationError, ValidationErrors};
 pub enum State {
     // Intermediate
     Submitted,
+    Shortlisted,
     Accepted,
+    Contracted,
+    Paid,
     // Bad end states
     Abandoned,
     Withdrawn,
@@ -17,6 +28,7 @@ pub enum State {
     // Good end states
     SelfPublished,
     Published,
+    Reprinted,
 }
 
 impl Display for State {
@@ -59,21 +71,37 @@ pub struct Publication {
 
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
-    #[serde(deserialize_with = "deserializers::option_string")]
-    #[validate(custom(function = "field_validators::non_empty"))]
+    #[serde(deserialize_with = "payments::option_paid")]
+    #[validate(nested)]
     #[builder(default)]
-    paid: Option<String>,
+    paid: Option<Paid>,
 
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default)]
     submitted: Option<NaiveDate>,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(alias = "held")]
+    #[builder(default)]
+    shortlisted: Option<NaiveDate>,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default)]
     accepted: Option<NaiveDate>,
 
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    contracted: Option<NaiveDate>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    paid_date: Option<NaiveDate>,
+
     #[serde(default)]
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default)]
@@ -98,6 +126,11 @@ pub struct Publication {
     #[serde(skip_serializing_if = "Option::is_none")]
     #[builder(default)]
     published: Option<NaiveDate>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[builder(default)]
+    reprinted: Option<NaiveDate>,
 }
 
 impl PublicationBuilder {
@@ -106,14 +139,18 @@ impl PublicationBuilder {
             self.venue.clone().unwrap_or_default(),
             self.urls.clone().unwrap_or_default(),
             self.notes.clone().unwrap_or_default().as_deref(),
-            self.paid.clone().unwrap_or_default().as_deref(),
+            self.paid.clone().unwrap_or_default(),
             self.submitted.unwrap_or_default(),
+            self.shortlisted.unwrap_or_default(),
             self.accepted.unwrap_or_default(),
+            self.contracted.unwrap_or_default(),
+            self.paid_date.unwrap_or_default(),
             self.abandoned.unwrap_or_default(),
             self.withdrawn.unwrap_or_default(),
             self.rejected.unwrap_or_default(),
             self.self_published.unwrap_or_default(),
             self.published.unwrap_or_default(),
+            self.reprinted.unwrap_or_default(),
         ) {
             return Err(err.to_string());
         }
@@ -128,27 +165,35 @@ impl Publication {
         venue: S,
         urls: V,
         notes: Option<&str>,
-        paid: Option<&str>,
+        paid: Option<Paid>,
         submitted: Option<NaiveDate>,
+        shortlisted: Option<NaiveDate>,
         accepted: Option<NaiveDate>,
+        contracted: Option<NaiveDate>,
+        paid_date: Option<NaiveDate>,
         abandoned: Option<NaiveDate>,
         withdrawn: Option<NaiveDate>,
         rejected: Option<NaiveDate>,
         self_published: Option<NaiveDate>,
         published: Option<NaiveDate>,
+        reprinted: Option<NaiveDate>,
     ) -> Result<Publication, ValidationErrors> {
         let p = Publication {
             venue: venue.into(),
             urls: urls.into(),
             notes: notes.map(str::to_string),
-            paid: paid.map(str::to_string),
+            paid,
             submitted,
+            shortlisted,
             accepted,
+            contracted,
+            paid_date,
             abandoned,
             withdrawn,
             rejected,
             self_published,
             published,
+            reprinted,
         };
         p.validate()?;
         Ok(p)
@@ -166,7 +211,7 @@ impl Publication {
         self.notes.as_ref()
     }
 
-    pub fn paid(&self) -> Option<&String> {
+    pub fn paid(&self) -> Option<&Paid> {
         self.paid.as_ref()
     }
 
@@ -174,10 +219,22 @@ impl Publication {
         self.submitted.as_ref()
     }
 
+    pub fn shortlisted(&self) -> Option<&NaiveDate> {
+        self.shortlisted.as_ref()
+    }
+
     pub fn accepted(&self) -> Option<&NaiveDate> {
         self.accepted.as_ref()
     }
 
+    pub fn contracted(&self) -> Option<&NaiveDate> {
+        self.contracted.as_ref()
+    }
+
+    pub fn paid_date(&self) -> Option<&NaiveDate> {
+        self.paid_date.as_ref()
+    }
+
     pub fn abandoned(&self) -> Option<&NaiveDate> {
         self.abandoned.as_ref()
     }
@@ -198,8 +255,27 @@ impl Publication {
         self.published.as_ref()
     }
 
+    pub fn reprinted(&self) -> Option<&NaiveDate> {
+        self.reprinted.as_ref()
+    }
+
+    fn with_venue(&self, venue: &str) -> Publication {
+        let mut p = self.clone();
+        p.venue = String::from(venue);
+        p
+    }
+
     pub fn dates(&self) -> Vec<Date> {
-        self.all_dates().into_iter().flatten().collect()
+        // Payment can happen at any point after acceptance, so it's merged in
+        // by date rather than having a fixed position.
+        let mut out: Vec<Date> = self
+            .all_dates()
+            .into_iter()
+            .chain(self.payment_dates())
+            .flatten()
+            .collect();
+        out.sort_by_key(|d| d.date);
+        out
     }
 
     pub fn latest(&self) -> Date {
@@ -209,6 +285,13 @@ impl Publication {
         panic!("validation should ensure this never happens");
     }
 
+    pub fn state(&self) -> State {
+        if let Some(s) = self.dates().into_iter().map(|d| d.state).max() {
+            return s;
+        }
+        panic!("validation should ensure this never happens");
+    }
+
     pub fn active(&self) -> bool {
         self.bad_end_dates().iter().flatten().count() == 0
     }
@@ -225,6 +308,7 @@ impl Publication {
         Self::filter_dates(&[
             (State::SelfPublished, self.self_published()),
             (State::Published, self.published()),
+            (State::Reprinted, self.reprinted()),
         ])
     }
 
@@ -237,10 +321,16 @@ impl Publication {
     fn intermediate_dates(&self) -> Vec<Option<Date>> {
         Self::filter_dates(&[
             (State::Submitted, self.submitted()),
+            (State::Shortlisted, self.shortlisted()),
             (State::Accepted, self.accepted()),
+            (State::Contracted, self.contracted()),
         ])
     }
 
+    fn payment_dates(&self) -> Vec<Option<Date>> {
+        Self::filter_dates(&[(State::Paid, self.paid_date())])
+    }
+
     fn all_dates(&self) -> Vec<Option<Date>> {
         let mut out = self.intermediate_dates();
         out.append(&mut self.end_dates());
@@ -261,41 +351,90 @@ impl Publication {
 
     fn validate_contents(&self) -> Result<(), ValidationError> {
         if self.all_dates().iter().flatten().count() == 0 {
-            return Err(ValidationError::new("at least one date must be set"));
+            return Err(validation::error_with_fix(
+                "at least one date must be set",
+                "add the date it was submitted, e.g. 'submitted: 2023-01-31'",
+            ));
         }
 
         if self.end_dates().iter().flatten().count() > 1 {
-            return Err(ValidationError::new("at most one end date can be set"));
+            return Err(validation::error_with_fix(
+                "at most one end date can be set",
+                "keep only the final outcome; a later reprint should be a separate publication",
+            ));
         }
 
         if self.self_published.is_some() && self.intermediate_dates().iter().flatten().count() > 0 {
-            return Err(ValidationError::new(
+            return Err(validation::error_with_fix(
                 "intermediate dates cannot be used with self_published",
+                "remove submitted, shortlisted, accepted and contracted, or replace self-published with published",
             ));
         }
 
         if self.accepted.is_some() && self.bad_end_dates().iter().flatten().count() > 0 {
-            return Err(ValidationError::new(
+            return Err(validation::error_with_fix(
                 "bad end dates cannot be used with accepted",
+                "remove accepted if it was never accepted, or the abandoned, withdrawn or rejected date if it was",
             ));
         }
 
-        if self.published.is_some()
-            && self
-                .intermediate_dates()
-                .into_iter()
-                .filter(Option::is_none)
-                .count()
-                > 0
+        if (self.published.is_some() || self.reprinted.is_some())
+            && (self.submitted.is_none() || self.accepted.is_none())
         {
-            return Err(ValidationError::new(
-                "all intermediate dates must be set when published is set",
+            return Err(validation::error_with_fix(
+                "submitted and accepted must be set when published or reprinted is set",
+                "add the dates it was submitted and accepted",
+            ));
+        }
+
+        if self.shortlisted.is_some() && self.submitted.is_none() {
+            return Err(validation::error_with_fix(
+                "submitted must be set if shortlisted is set",
+                "add the date it was submitted",
             ));
         }
 
+        if (self.contracted.is_some() || self.paid_date.is_some()) && self.accepted.is_none() {
+            return Err(validation::error_with_fix(
+                "accepted must be set if contracted or paid-date is set",
+                "add the date it was accepted",
+            ));
+        }
+
+        if let (Some(paid), Some(accepted)) = (self.paid_date, self.accepted) {
+            if paid < accepted {
+                return Err(validation::error_with_fix(
+                    "paid-date cannot be before accepted",
+                    "check the paid-date and accepted dates",
+                ));
+            }
+        }
+
+        // Free-text payments predate these checks, so only structured
+        // payments are held to them. A payment needs an acceptance, which also
+        // rules out self-published pieces. Its date is paid-date, which is
+        // deliberately not checked against published: venues pay on
+        // acceptance, on publication, or some time after, so any date from
+        // acceptance on is consistent with it.
+        if let Some(Paid::Payment(payment)) = &self.paid {
+            if self.accepted.is_none() {
+                return Err(validation::error_with_fix(
+                    "accepted must be set if a payment is set",
+                    "add the date it was accepted, or record the payment as text in paid",
+                ));
+            }
+            if self.paid_date.is_some() && payment.status() == PaymentStatus::Pending {
+                return Err(validation::error_with_fix(
+                    "payment cannot be pending if paid-date is set",
+                    "set the payment status to received, or remove paid-date",
+                ));
+            }
+        }
+
         if self.bad_end_dates().iter().flatten().count() > 0 && self.submitted.is_none() {
-            return Err(ValidationError::new(
+            return Err(validation::error_with_fix(
                 "submitted must be set if any bad end dates are set",
+                "add the date it was submitted",
             ));
         }
 
@@ -308,7 +447,11 @@ impl Publication {
         let mut sorted = dates.clone();
         sorted.sort();
         if dates != sorted {
-            return Err(ValidationError::new("dates must be in increasing order"));
+            return Err(validation::error_with_fix(
+                "dates must be in increasing order",
+                "check each date; they must follow the order submitted, shortlisted, accepted, \
+contracted, then the end date",
+            ));
         }
 
         Ok(())
@@ -317,9 +460,36 @@ impl Publication {
 
 impl JsonSerializable for Publication {}
 
+impl JsonSchema for Publication {
+    fn json_schema() -> Value {
+        schema::object(
+            vec![
+                ("venue", schema::non_empty_string()),
+                ("urls", schema::array(schema::non_empty_string())),
+                ("notes", schema::optional_string()),
+                ("paid", Paid::json_schema()),
+                ("submitted", schema::date()),
+                ("shortlisted", schema::date()),
+                ("held", schema::date()),
+                ("accepted", schema::date()),
+                ("contracted", schema::date()),
+                ("paid-date", schema::date()),
+                ("abandoned", schema::date()),
+                ("withdrawn", schema::date()),
+                ("rejected", schema::date()),
+                ("self-published", schema::date()),
+                ("published", schema::date()),
+                ("reprinted", schema::date()),
+            ],
+            &["venue"],
+        )
+    }
+}
+
 #[derive(Clone, Default, Debug, Serialize, Deserialize, Validate)]
 #[serde(deny_unknown_fields)]
 #[serde(transparent)]
+#[validate(schema(function = "Publications::validate_reprints"))]
 pub struct Publications {
     #[serde(skip_serializing_if = "Vec::is_empty")]
     #[validate(nested)]
@@ -354,7 +524,7 @@ impl Publications {
             .publications
             .iter()
             .filter(|p| p.active())
-            .map(|p| p.latest().state)
+            .map(Publication::state)
             .collect();
         states.sort();
         if let Some(&s) = states.last() {
@@ -362,10 +532,49 @@ impl Publications {
         }
         None
     }
+
+    pub fn canonicalise_venues(&self, registry: &VenueRegistry) -> Result<Publications, String> {
+        let mut problems = Vec::new();
+        let mut publications = Vec::new();
+        for p in &self.publications {
+            match registry.canonical_name(p.venue()) {
+                Ok(venue) => publications.push(p.with_venue(venue)),
+                Err(e) => problems.push(e),
+            }
+        }
+        if !problems.is_empty() {
+            return Err(problems.join("\n"));
+        }
+        Ok(Publications { publications })
+    }
+
+    fn validate_reprints(&self) -> Result<(), ValidationError> {
+        for p in &self.publications {
+            if let Some(reprinted) = p.reprinted() {
+                if !self.publications.iter().any(|o| {
+                    o.published()
+                        .or(o.self_published())
+                        .is_some_and(|d| d <= reprinted)
+                }) {
+                    return Err(validation::error_with_fix(
+                        "reprinted must follow an earlier published or self_published",
+                        "add the original publication, or change reprinted to published",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
 }
 
 impl JsonSerializable for Publications {}
 
+impl JsonSchema for Publications {
+    fn json_schema() -> Value {
+        schema::array(Publication::json_schema())
+    }
+}
+
 #[cfg(test)]
 mod test_utils {
     use super::{Date, State};
@@ -390,6 +599,7 @@ mod publication_test {
         test_utils::{date, ymd},
     };
     use crate::json::{JsonSerializable, from_json};
+    use crate::payments::{Money, Paid, Payment, PaymentStatus};
 
     #[test]
     fn test_good_minimal() {
@@ -427,7 +637,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert_eq!(p.submitted().copied().unwrap(), ymd(2023, 5, 16));
         assert!(p.accepted().is_none());
         assert!(p.rejected().is_none());
@@ -454,7 +664,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert_eq!(p.submitted().copied().unwrap(), ymd(2023, 5, 16));
         assert_eq!(p.accepted().copied().unwrap(), ymd(2023, 5, 17));
         assert!(p.rejected().is_none());
@@ -487,7 +697,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert_eq!(p.submitted().copied().unwrap(), ymd(2023, 5, 16));
         assert!(p.accepted().is_none());
         assert_eq!(p.rejected().copied().unwrap(), ymd(2023, 5, 17));
@@ -520,7 +730,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert_eq!(p.submitted().copied().unwrap(), ymd(2023, 5, 16));
         assert!(p.accepted().is_none());
         assert!(p.rejected().is_none());
@@ -553,7 +763,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert_eq!(p.submitted().copied().unwrap(), ymd(2023, 5, 16));
         assert!(p.accepted().is_none());
         assert!(p.rejected().is_none());
@@ -585,7 +795,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert!(p.submitted().is_none());
         assert!(p.accepted().is_none());
         assert!(p.rejected().is_none());
@@ -613,7 +823,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert_eq!(p.submitted().copied().unwrap(), ymd(2023, 5, 16));
         assert_eq!(p.accepted().copied().unwrap(), ymd(2023, 5, 17));
         assert!(p.rejected().is_none());
@@ -633,6 +843,162 @@ mod publication_test {
         assert!(p.active());
     }
 
+    #[test]
+    fn test_good_extended() {
+        let p = PublicationBuilder::default()
+            .venue("foo")
+            .submitted(ymd(2023, 5, 16))
+            .shortlisted(ymd(2023, 5, 17))
+            .accepted(ymd(2023, 5, 18))
+            .contracted(ymd(2023, 5, 19))
+            .published(ymd(2023, 5, 20))
+            .paid_date(ymd(2023, 5, 21))
+            .build()
+            .unwrap();
+        assert_eq!(p.shortlisted().copied().unwrap(), ymd(2023, 5, 17));
+        assert_eq!(p.contracted().copied().unwrap(), ymd(2023, 5, 19));
+        assert_eq!(p.paid_date().copied().unwrap(), ymd(2023, 5, 21));
+        assert!(p.reprinted().is_none());
+        assert_eq!(
+            p.dates(),
+            [
+                date(State::Submitted, (2023, 5, 16)),
+                date(State::Shortlisted, (2023, 5, 17)),
+                date(State::Accepted, (2023, 5, 18)),
+                date(State::Contracted, (2023, 5, 19)),
+                date(State::Published, (2023, 5, 20)),
+                date(State::Paid, (2023, 5, 21))
+            ]
+        );
+        assert_eq!(p.latest(), date(State::Paid, (2023, 5, 21)));
+        assert_eq!(p.state(), State::Published);
+        assert!(p.active());
+    }
+
+    #[test]
+    fn test_good_paid_before_published() {
+        let p = PublicationBuilder::default()
+            .venue("foo")
+            .submitted(ymd(2023, 5, 16))
+            .accepted(ymd(2023, 5, 17))
+            .paid_date(ymd(2023, 5, 17))
+            .build()
+            .unwrap();
+        assert_eq!(p.latest(), date(State::Paid, (2023, 5, 17)));
+        assert_eq!(p.state(), State::Paid);
+    }
+
+    #[test]
+    fn test_good_shortlisted_rejected() {
+        let p = PublicationBuilder::default()
+            .venue("foo")
+            .submitted(ymd(2023, 5, 16))
+            .shortlisted(ymd(2023, 5, 17))
+            .rejected(ymd(2023, 5, 18))
+            .build()
+            .unwrap();
+        assert_eq!(p.state(), State::Rejected);
+        assert!(!p.active());
+    }
+
+    #[test]
+    fn test_good_payment() {
+        let payment =
+            Payment::build(Money::parse("10"), "GBP", None, PaymentStatus::Received).unwrap();
+        let p = PublicationBuilder::default()
+            .venue("foo")
+            .paid(payment.clone())
+            .submitted(ymd(2023, 5, 16))
+            .accepted(ymd(2023, 5, 17))
+            .paid_date(ymd(2023, 5, 18))
+            .build()
+            .unwrap();
+        assert_eq!(p.paid().unwrap(), &Paid::Payment(payment.clone()));
+        assert_eq!(p.paid().unwrap().payment(), Some(payment.clone()));
+
+        // Paid either side of publication
+        for paid in [ymd(2023, 5, 18), ymd(2023, 5, 20)] {
+            assert!(
+                PublicationBuilder::default()
+                    .venue("foo")
+                    .paid(payment.clone())
+                    .submitted(ymd(2023, 5, 16))
+                    .accepted(ymd(2023, 5, 17))
+                    .paid_date(paid)
+                    .published(ymd(2023, 5, 19))
+                    .build()
+                    .is_ok()
+            );
+        }
+    }
+
+    #[test]
+    fn test_bad_payment() {
+        let pending =
+            Payment::build(Money::parse("10"), "GBP", None, PaymentStatus::Pending).unwrap();
+        let received =
+            Payment::build(Money::parse("10"), "GBP", None, PaymentStatus::Received).unwrap();
+
+        // Not accepted
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .paid(pending.clone())
+                .submitted(ymd(2023, 5, 16))
+                .build()
+                .is_err()
+        );
+        // Paid before accepted
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .paid(received.clone())
+                .submitted(ymd(2023, 5, 16))
+                .accepted(ymd(2023, 5, 17))
+                .paid_date(ymd(2023, 5, 16))
+                .build()
+                .is_err()
+        );
+        // Pending, but paid-date is set
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .paid(pending)
+                .submitted(ymd(2023, 5, 16))
+                .accepted(ymd(2023, 5, 17))
+                .paid_date(ymd(2023, 5, 18))
+                .build()
+                .is_err()
+        );
+        // Self-published, so never accepted
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .paid(received)
+                .self_published(ymd(2023, 5, 16))
+                .build()
+                .is_err()
+        );
+        // Free text isn't checked
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .paid("£10")
+                .submitted(ymd(2023, 5, 16))
+                .build()
+                .is_ok()
+        );
+    }
+
+    #[test]
+    fn test_state_display() {
+        assert_eq!(State::Shortlisted.to_string(), "shortlisted");
+        assert_eq!(State::Contracted.to_string(), "contracted");
+        assert_eq!(State::Paid.to_string(), "paid");
+        assert_eq!(State::SelfPublished.to_string(), "self_published");
+        assert_eq!(State::Reprinted.to_string(), "reprinted");
+    }
+
     #[test]
     fn test_bad_no_venue() {
         assert!(
@@ -716,6 +1082,79 @@ mod publication_test {
         );
     }
 
+    #[test]
+    fn test_bad_reprinted_missing_intermediate() {
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .submitted(ymd(2023, 5, 16))
+                .reprinted(ymd(2023, 5, 16))
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bad_shortlisted_missing_submitted() {
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .shortlisted(ymd(2023, 5, 16))
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bad_contracted_missing_accepted() {
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .submitted(ymd(2023, 5, 16))
+                .contracted(ymd(2023, 5, 17))
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bad_paid_missing_accepted() {
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .submitted(ymd(2023, 5, 16))
+                .paid_date(ymd(2023, 5, 17))
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bad_paid_before_accepted() {
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .submitted(ymd(2023, 5, 16))
+                .accepted(ymd(2023, 5, 17))
+                .paid_date(ymd(2023, 5, 16))
+                .build()
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_bad_shortlisted_wrong_order() {
+        assert!(
+            PublicationBuilder::default()
+                .venue("foo")
+                .submitted(ymd(2023, 5, 16))
+                .shortlisted(ymd(2023, 5, 18))
+                .accepted(ymd(2023, 5, 17))
+                .build()
+                .is_err()
+        );
+    }
+
     #[test]
     fn test_bad_bad_missing_submitted() {
         assert!(
@@ -837,7 +1276,7 @@ mod publication_test {
         assert_eq!(p.venue(), "foo");
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
         assert_eq!(p.submitted().copied().unwrap(), ymd(2023, 5, 16));
         assert_eq!(p.accepted().copied().unwrap(), ymd(2023, 5, 17));
         assert!(p.rejected().is_none());
@@ -857,6 +1296,58 @@ mod publication_test {
         assert!(p.active());
     }
 
+    #[test]
+    fn test_deserialization_held() {
+        let p: Publication = from_json(
+            r#"{
+  "held": "2023-05-17",
+  "submitted": "2023-05-16",
+  "venue": "foo"
+}"#,
+        )
+        .unwrap();
+        assert_eq!(p.shortlisted().copied().unwrap(), ymd(2023, 5, 17));
+        assert_eq!(
+            p.to_json().unwrap(),
+            r#"{
+  "shortlisted": "2023-05-17",
+  "submitted": "2023-05-16",
+  "venue": "foo"
+}"#
+        );
+    }
+
+    #[test]
+    fn test_deserialization_payment() {
+        let p: Publication = from_json(
+            r#"{
+  "accepted": "2023-05-17",
+  "paid": {
+    "currency": "USD",
+    "rate-per-word": 0.08,
+    "status": "pending"
+  },
+  "submitted": "2023-05-16",
+  "venue": "foo"
+}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            p.paid().unwrap().payment(),
+            Payment::build(None, "USD", Money::parse("0.08"), PaymentStatus::Pending).ok()
+        );
+
+        let p: Publication = from_json(
+            r#"{
+  "paid": "",
+  "submitted": "2023-05-16",
+  "venue": "foo"
+}"#,
+        )
+        .unwrap();
+        assert!(p.paid().is_none());
+    }
+
     #[test]
     fn test_deserialization_bad_no_venue() {
         assert!(from_json::<Publication>(r#"{"submitted": "2023-05-16"}"#).is_err());
@@ -881,6 +1372,9 @@ mod publication_test {
 mod publications_test {
     use super::{PublicationBuilder, Publications, State, test_utils::ymd};
     use crate::json::{JsonSerializable, from_json};
+    use crate::validation::field_errors;
+    use crate::venues::{Venue, VenueRegistry};
+    use validator::Validate;
 
     #[test]
     fn test_good_active() {
@@ -922,7 +1416,7 @@ mod publications_test {
         assert_eq!(p.published().copied().unwrap(), ymd(2023, 5, 18));
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
 
         let p = &ps.publications()[1];
         assert_eq!(p.venue(), "Book2");
@@ -935,7 +1429,54 @@ mod publications_test {
         assert!(p.published().is_none());
         assert_eq!(p.urls(), &["foo2", "bar2"]);
         assert_eq!(p.notes().unwrap(), "baz2");
-        assert_eq!(p.paid().unwrap(), "quux2");
+        assert_eq!(p.paid().unwrap().to_string(), "quux2");
+    }
+
+    #[test]
+    fn test_good_reprinted() {
+        let ps = Publications::build([
+            PublicationBuilder::default()
+                .venue("Book")
+                .self_published(ymd(2023, 5, 16))
+                .build()
+                .unwrap(),
+            PublicationBuilder::default()
+                .venue("Book2")
+                .submitted(ymd(2023, 5, 17))
+                .accepted(ymd(2023, 5, 18))
+                .contracted(ymd(2023, 5, 19))
+                .reprinted(ymd(2023, 5, 20))
+                .build()
+                .unwrap(),
+        ])
+        .unwrap();
+        assert_eq!(ps.highest_active_state(), Some(State::Reprinted));
+    }
+
+    #[test]
+    fn test_bad_reprinted() {
+        let reprint = PublicationBuilder::default()
+            .venue("Book2")
+            .submitted(ymd(2023, 5, 17))
+            .accepted(ymd(2023, 5, 18))
+            .reprinted(ymd(2023, 5, 20))
+            .build()
+            .unwrap();
+
+        assert!(Publications::build([reprint.clone()]).is_err());
+        assert!(
+            Publications::build([
+                PublicationBuilder::default()
+                    .venue("Book")
+                    .submitted(ymd(2023, 5, 16))
+                    .accepted(ymd(2023, 5, 17))
+                    .published(ymd(2023, 5, 21))
+                    .build()
+                    .unwrap(),
+                reprint,
+            ])
+            .is_err()
+        );
     }
 
     #[test]
@@ -977,7 +1518,7 @@ mod publications_test {
         assert!(p.published().is_none());
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
 
         let p = &ps.publications()[1];
         assert_eq!(p.venue(), "Book2");
@@ -990,7 +1531,7 @@ mod publications_test {
         assert!(p.published().is_none());
         assert_eq!(*p.urls(), &["foo2", "bar2"]);
         assert_eq!(p.notes().unwrap(), "baz2");
-        assert_eq!(p.paid().unwrap(), "quux2");
+        assert_eq!(p.paid().unwrap().to_string(), "quux2");
     }
 
     #[test]
@@ -1102,7 +1643,7 @@ mod publications_test {
         assert_eq!(p.published().copied().unwrap(), ymd(2023, 5, 18));
         assert_eq!(p.urls(), &["foo", "bar"]);
         assert_eq!(p.notes().unwrap(), "baz");
-        assert_eq!(p.paid().unwrap(), "quux");
+        assert_eq!(p.paid().unwrap().to_string(), "quux");
 
         let p = &ps.publications()[1];
         assert_eq!(p.venue(), "Book2");
@@ -1115,11 +1656,129 @@ mod publications_test {
         assert!(p.published().is_none());
         assert_eq!(p.urls(), &["foo2", "bar2"]);
         assert_eq!(p.notes().unwrap(), "baz2");
-        assert_eq!(p.paid().unwrap(), "quux2");
+        assert_eq!(p.paid().unwrap().to_string(), "quux2");
     }
 
     #[test]
     fn test_deserialization_bad_nested() {
         assert!(from_json::<Publications>(r#"[{"venue": "foo"}]"#).is_err());
     }
+
+    #[test]
+    fn test_canonicalise_venues() {
+        let registry = VenueRegistry::build([
+            Venue::build(
+                "Foo",
+                [String::from("Foo Magazine")],
+                None,
+                None,
+                None,
+                None,
+            )
+            .unwrap(),
+            Venue::build("Bar", Vec::new(), None, None, None, None).unwrap(),
+        ])
+        .unwrap();
+
+        let ps = Publications::build([
+            PublicationBuilder::default()
+                .venue("Foo Magazine")
+                .submitted(ymd(2023, 5, 16))
+                .build()
+                .unwrap(),
+            PublicationBuilder::default()
+                .venue("Bar")
+                .submitted(ymd(2023, 5, 17))
+                .build()
+                .unwrap(),
+        ])
+        .unwrap()
+        .canonicalise_venues(&registry)
+        .unwrap();
+        assert_eq!(ps.publications()[0].venue(), "Foo");
+        assert_eq!(
+            ps.publications()[0].submitted().copied().unwrap(),
+            ymd(2023, 5, 16)
+        );
+        assert_eq!(ps.publications()[1].venue(), "Bar");
+
+        let err = Publications::build([
+            PublicationBuilder::default()
+                .venue("Baz")
+                .submitted(ymd(2023, 5, 16))
+                .build()
+                .unwrap(),
+            PublicationBuilder::default()
+                .venue("Quux")
+                .submitted(ymd(2023, 5, 17))
+                .build()
+                .unwrap(),
+        ])
+        .unwrap()
+        .canonicalise_venues(&registry)
+        .unwrap_err();
+        assert_eq!(err, "unknown venue 'Baz'\nunknown venue 'Quux'");
+    }
+
+    #[test]
+    fn test_suggested_fix() {
+        // One case for each rule
+        for (ps, code) in [
+            (r#"[{"venue": "a"}]"#, "at least one date must be set"),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "rejected": "2023-01-02", "withdrawn": "2023-01-03"}]"#,
+                "at most one end date can be set",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "self-published": "2023-01-02"}]"#,
+                "intermediate dates cannot be used with self_published",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "accepted": "2023-01-02", "withdrawn": "2023-01-03"}]"#,
+                "bad end dates cannot be used with accepted",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "published": "2023-01-02"}]"#,
+                "submitted and accepted must be set when published or reprinted is set",
+            ),
+            (
+                r#"[{"venue": "a", "shortlisted": "2023-01-01"}]"#,
+                "submitted must be set if shortlisted is set",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "contracted": "2023-01-02"}]"#,
+                "accepted must be set if contracted or paid-date is set",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "accepted": "2023-01-03", "paid-date": "2023-01-02"}]"#,
+                "paid-date cannot be before accepted",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "paid": {"currency": "USD", "amount": 1, "status": "received"}}]"#,
+                "accepted must be set if a payment is set",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "accepted": "2023-01-02", "paid-date": "2023-01-03", "paid": {"currency": "USD", "amount": 1, "status": "pending"}}]"#,
+                "payment cannot be pending if paid-date is set",
+            ),
+            (
+                r#"[{"venue": "a", "rejected": "2023-01-01"}]"#,
+                "submitted must be set if any bad end dates are set",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-02", "rejected": "2023-01-01"}]"#,
+                "dates must be in increasing order",
+            ),
+            (
+                r#"[{"venue": "a", "submitted": "2023-01-01", "accepted": "2023-01-02", "reprinted": "2023-01-03"}]"#,
+                "reprinted must follow an earlier published or self_published",
+            ),
+        ] {
+            let ps: Publications = serde_json::from_str(ps).unwrap();
+            let errors = field_errors(&ps.validate().unwrap_err());
+            assert_eq!(errors.len(), 1);
+            assert_eq!(errors[0].code(), code);
+            assert!(errors[0].suggested_fix().is_some(), "{code}");
+        }
+    }
 }
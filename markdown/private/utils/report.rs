@@ -0,0 +1,85 @@
+use clap::ValueEnum;
+use serde::Serialize;
+use std::error::Error;
+use tabled::{Table, Tabled, settings::Style};
+
+/// Output format of a report made up of sections of rows.
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub enum Format {
+    Table,
+    Csv,
+    Json,
+}
+
+/// Rows as a markdown table.
+pub fn table<T: Tabled>(rows: &[T]) -> String {
+    Table::new(rows).with(Style::markdown()).to_string()
+}
+
+/// Rows as a markdown table under a title, for reports with several sections.
+pub fn titled_table<T: Tabled>(title: &str, rows: &[T]) -> String {
+    format!("{title}\n\n{}", table(rows))
+}
+
+/// Rows as csv, with a header row from the field names.
+pub fn csv<T: Serialize>(rows: &[T]) -> Result<String, Box<dyn Error>> {
+    let mut out = csv::Writer::from_writer(Vec::new());
+    for row in rows {
+        out.serialize(row)?;
+    }
+    Ok(String::from_utf8(out.into_inner()?)?)
+}
+
+/// The rows of a section if it's shown, or none, so that sections that
+/// weren't asked for are empty in json output.
+pub fn shown<T>(show: bool, rows: Vec<T>) -> Vec<T> {
+    if show { rows } else { Vec::new() }
+}
+
+#[cfg(test)]
+mod report_test {
+    use super::{csv, shown, table, titled_table};
+    use serde::Serialize;
+    use tabled::Tabled;
+
+    #[derive(Serialize, Tabled)]
+    struct Row {
+        name: &'static str,
+        count: u32,
+    }
+
+    const ROWS: [Row; 2] = [
+        Row {
+            name: "a",
+            count: 1,
+        },
+        Row {
+            name: "b",
+            count: 10,
+        },
+    ];
+
+    #[test]
+    fn test_table() {
+        assert_eq!(
+            table(&ROWS),
+            "| name | count |
+|------|-------|
+| a    | 1     |
+| b    | 10    |"
+        );
+        assert!(titled_table("Rows", &ROWS).starts_with("Rows\n\n| name"));
+    }
+
+    #[test]
+    fn test_csv() {
+        assert_eq!(csv(&ROWS).unwrap(), "name,count\na,1\nb,10\n");
+        assert_eq!(csv::<Row>(&[]).unwrap(), "");
+    }
+
+    #[test]
+    fn test_shown() {
+        assert_eq!(shown(true, vec![1, 2]), [1, 2]);
+        assert!(shown(false, vec![1, 2]).is_empty());
+    }
+}
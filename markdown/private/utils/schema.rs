@@ -0,0 +1,262 @@
+use serde_json::{Map, Value, json};
+
+pub const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+pub trait JsonSchema {
+    fn json_schema() -> Value;
+}
+
+/// A top-level schema document for T.
+pub fn document<T: JsonSchema>(title: &str) -> Value {
+    let mut schema = T::json_schema();
+    if let Value::Object(m) = &mut schema {
+        m.insert(String::from("$schema"), Value::from(DRAFT));
+        m.insert(String::from("title"), Value::from(title));
+    }
+    schema
+}
+
+pub fn string() -> Value {
+    json!({"type": "string"})
+}
+
+/// A string that's treated as unset if empty.
+pub fn optional_string() -> Value {
+    json!({"type": "string", "description": "an empty string is treated as unset"})
+}
+
+pub fn non_empty_string() -> Value {
+    json!({"type": "string", "minLength": 1})
+}
+
+/// A string or a list of strings, all non-empty.
+pub fn non_empty_strings() -> Value {
+    json!({"oneOf": [non_empty_string(), array(non_empty_string())]})
+}
+
+pub fn date() -> Value {
+    json!({"type": "string", "format": "date"})
+}
+
+pub fn boolean() -> Value {
+    json!({"type": "boolean"})
+}
+
+pub fn number() -> Value {
+    json!({"type": "number", "minimum": 0})
+}
+
+/// An unsigned int, or a string containing one.
+pub fn uint() -> Value {
+    json!({
+        "oneOf": [
+            {"type": "integer", "minimum": 0, "maximum": u32::MAX},
+            {"type": "string", "pattern": "^[0-9]+$"},
+        ]
+    })
+}
+
+pub fn string_enum(values: &[&str]) -> Value {
+    json!({"type": "string", "enum": values})
+}
+
+pub fn array(items: Value) -> Value {
+    json!({"type": "array", "items": items})
+}
+
+pub fn one_of(schemas: Vec<Value>) -> Value {
+    json!({ "oneOf": schemas })
+}
+
+/// An object with the given properties, which rejects unknown fields.
+pub fn object(properties: Vec<(&str, Value)>, required: &[&str]) -> Value {
+    let properties: Map<String, Value> = properties
+        .into_iter()
+        .map(|(k, v)| (String::from(k), v))
+        .collect();
+    json!({
+        "type": "object",
+        "properties": properties,
+        "required": required,
+        "additionalProperties": false,
+    })
+}
+
+/// An object with arbitrary keys and values matching the given schema.
+pub fn map(values: Value) -> Value {
+    json!({"type": "object", "additionalProperties": values})
+}
+
+#[cfg(test)]
+mod schema_test {
+    use super::{JsonSchema, document};
+    use crate::json::from_json;
+    use crate::metadata::{ContentHash, InputMetadata, MetadataMap, OutputMetadata, VersionInfo};
+    use crate::publications::Publication;
+    use serde::Serialize;
+    use serde_json::Value;
+    use std::collections::BTreeSet;
+
+    const INPUT_METADATA: &str = r#"{
+  "author": ["A"],
+  "date": "2023",
+  "finished": true,
+  "identifier": [{"scheme": "ISBN", "text": "123"}],
+  "illustrator": "C",
+  "notes": "foo",
+  "publications": [
+    {
+      "accepted": "2023-05-17",
+      "contracted": "2023-05-18",
+      "notes": "bar",
+      "paid": "£10",
+      "paid-date": "2023-05-19",
+      "published": "2023-05-20",
+      "shortlisted": "2023-05-16",
+      "submitted": "2023-05-15",
+      "urls": ["http://example.com"],
+      "venue": "Foo"
+    }
+  ],
+  "title": "Title",
+  "translator": ["B"]
+}"#;
+
+    fn property_names(schema: &Value) -> BTreeSet<String> {
+        schema["properties"]
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    fn field_names<T: Serialize>(t: &T) -> BTreeSet<String> {
+        serde_json::to_value(t)
+            .unwrap()
+            .as_object()
+            .unwrap()
+            .keys()
+            .cloned()
+            .collect()
+    }
+
+    fn output_metadata() -> OutputMetadata {
+        let mut m: Value = serde_json::from_str(INPUT_METADATA).unwrap();
+        let extra: Value = serde_json::from_str(
+            r#"{
+  "lang": "en",
+  "parsed-dates": ["2023"],
+  "poetry-lines": 1,
+  "repo": "repo",
+  "source-hash": "abc",
+  "content-hash": {
+    "algorithm": "sha256",
+    "digest": "def"
+  },
+  "hash-src": "a/b.md",
+  "hash-deps": ["a:c"],
+  "hash-inputs": ["a/b.jpg"],
+  "hash-generated": ["a/b.md"],
+  "version": "1",
+  "version-info": {
+    "commit": "1",
+    "dirty": true,
+    "dirty-deps": ["a:b"],
+    "override-source": "version-file",
+    "unversioned": true,
+    "unversioned-deps": ["a:c"]
+  },
+  "wordcount": 10
+}"#,
+        )
+        .unwrap();
+        m.as_object_mut()
+            .unwrap()
+            .extend(extra.as_object().unwrap().clone());
+        from_json(&m.to_string()).unwrap()
+    }
+
+    // Every serialised field must be in the schema, and vice versa, so that
+    // the schemas can't drift from the types.
+    #[test]
+    fn test_input_metadata_fields() {
+        let m: InputMetadata = from_json(INPUT_METADATA).unwrap();
+        assert_eq!(
+            property_names(&InputMetadata::json_schema()),
+            field_names(&m)
+        );
+    }
+
+    #[test]
+    fn test_output_metadata_fields() {
+        assert_eq!(
+            property_names(&OutputMetadata::json_schema()),
+            field_names(&output_metadata())
+        );
+    }
+
+    #[test]
+    fn test_version_info_fields() {
+        assert_eq!(
+            property_names(&VersionInfo::json_schema()),
+            field_names(output_metadata().version_info())
+        );
+    }
+
+    #[test]
+    fn test_content_hash_fields() {
+        assert_eq!(
+            property_names(&ContentHash::json_schema()),
+            field_names(output_metadata().content_hash().unwrap())
+        );
+    }
+
+    #[test]
+    fn test_publication_fields() {
+        let m = output_metadata();
+        let mut fields = field_names(&m.publications().publications()[0]);
+        for f in [
+            "abandoned",
+            "withdrawn",
+            "rejected",
+            "self-published",
+            "reprinted",
+        ] {
+            fields.insert(String::from(f));
+        }
+        let mut properties = property_names(&Publication::json_schema());
+        // Alias of shortlisted
+        properties.remove("held");
+        assert_eq!(properties, fields);
+    }
+
+    #[test]
+    fn test_schemas_checked_in() {
+        // To update the checked-in schemas, run
+        // `cargo run --bin json_schema -- <type> <file>` for each type.
+        for (schema, checked_in) in [
+            (
+                document::<InputMetadata>("InputMetadata"),
+                include_str!("schemas/input_metadata.schema.json"),
+            ),
+            (
+                document::<OutputMetadata>("OutputMetadata"),
+                include_str!("schemas/output_metadata.schema.json"),
+            ),
+            (
+                document::<Publication>("Publication"),
+                include_str!("schemas/publication.schema.json"),
+            ),
+            (
+                document::<MetadataMap>("MetadataMap"),
+                include_str!("schemas/metadata_map.schema.json"),
+            ),
+        ] {
+            assert_eq!(
+                serde_json::to_string_pretty(&schema).unwrap() + "\n",
+                checked_in
+            );
+        }
+    }
+}
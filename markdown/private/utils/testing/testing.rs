@@ -0,0 +1,45 @@
+//! Fixtures shared by the tests of binaries that read metadata.
+
+use chrono::NaiveDate;
+use markdown::metadata::OutputMetadataBuilder;
+
+pub fn ymd(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
+
+pub fn strings(s: &[&str]) -> Vec<String> {
+    s.iter().map(|s| String::from(*s)).collect()
+}
+
+/// A builder with all the fields OutputMetadata requires already set, so
+/// tests only need to set the fields they care about.
+pub fn output_metadata() -> OutputMetadataBuilder {
+    let mut b = OutputMetadataBuilder::default();
+    b.wordcount(10u32)
+        .poetry_lines(0u32)
+        .lang("en")
+        .version("1")
+        .repo("foo")
+        .source_hash("abc");
+    b
+}
+
+#[cfg(test)]
+mod testing_test {
+    use super::output_metadata;
+
+    #[test]
+    fn test_output_metadata() {
+        let m = output_metadata().title("Foo").build().unwrap();
+        assert_eq!(m.title().unwrap(), "Foo");
+        assert_eq!(m.wordcount(), 10);
+        assert_eq!(
+            output_metadata()
+                .wordcount(5u32)
+                .build()
+                .unwrap()
+                .wordcount(),
+            5
+        );
+    }
+}
@@ -2,7 +2,15 @@ pub mod args;
 pub mod bazel;
 pub mod deserializers;
 pub mod field_validators;
+pub mod hashing;
+pub mod include_graph;
 pub mod json;
+pub mod lint;
 pub mod metadata;
+pub mod payments;
 pub mod problems;
 pub mod publications;
+pub mod report;
+pub mod schema;
+pub mod validation;
+pub mod venues;
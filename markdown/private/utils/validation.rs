@@ -0,0 +1,444 @@
+use crate::problems::{Diagnostic, RowColProblem};
+use std::borrow::Cow;
+use std::fmt::{self, Display, Formatter};
+use validator::{ValidationError, ValidationErrors, ValidationErrorsKind};
+
+// Validation errors use the struct field names, so fields that serde renames
+// other than by case need mapping back to the names in the input.
+const RENAMED_FIELDS: &[(&str, &str)] = &[("authors", "author"), ("identifiers", "identifier")];
+
+const FIX_PARAM: &str = "fix";
+
+/// A validation error with a suggested fix, which is shown alongside it.
+pub fn error_with_fix(code: &'static str, fix: &'static str) -> ValidationError {
+    let mut e = ValidationError::new(code);
+    e.add_param(Cow::from(FIX_PARAM), &fix);
+    e
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum PathElement {
+    Field(String),
+    Index(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldError {
+    path: Vec<PathElement>,
+    code: String,
+    msg: String,
+    fix: Option<String>,
+}
+
+impl FieldError {
+    pub fn path(&self) -> &[PathElement] {
+        &self.path
+    }
+
+    pub fn code(&self) -> &str {
+        &self.code
+    }
+
+    pub fn msg(&self) -> &str {
+        &self.msg
+    }
+
+    pub fn path_string(&self) -> String {
+        let mut out = String::new();
+        for e in &self.path {
+            match e {
+                PathElement::Field(f) => {
+                    if !out.is_empty() {
+                        out.push('.');
+                    }
+                    out.push_str(f);
+                }
+                PathElement::Index(i) => out.push_str(&format!("[{i}]")),
+            }
+        }
+        if out.is_empty() {
+            out.push_str("metadata");
+        }
+        out
+    }
+
+    pub fn suggested_fix(&self) -> Option<&str> {
+        self.fix.as_deref()
+    }
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.path_string(), self.msg)?;
+        if let Some(fix) = self.suggested_fix() {
+            write!(f, " (suggested fix: {fix})")?;
+        }
+        Ok(())
+    }
+}
+
+fn field_name(field: &str) -> String {
+    match RENAMED_FIELDS.iter().find(|(from, _)| *from == field) {
+        Some((_, to)) => String::from(*to),
+        None => field.replace('_', "-"),
+    }
+}
+
+fn collect(errors: &ValidationErrors, path: &[PathElement], out: &mut Vec<FieldError>) {
+    // Errors are in a HashMap, so sort them to get a stable order, with
+    // errors for the whole struct first.
+    let mut fields: Vec<_> = errors.errors().iter().collect();
+    fields.sort_by_key(|(field, _)| (*field != "__all__", field.to_string()));
+
+    for (field, kind) in fields {
+        let mut path = path.to_vec();
+        if field != "__all__" {
+            // Transparent wrappers like Publications name their only field
+            // after themselves, which doesn't appear in the input.
+            let element = PathElement::Field(field_name(field));
+            if path.last() != Some(&element) {
+                path.push(element);
+            }
+        }
+
+        match kind {
+            ValidationErrorsKind::Field(errs) => {
+                for e in errs {
+                    out.push(FieldError {
+                        path: path.clone(),
+                        code: e.code.to_string(),
+                        msg: e.message.as_ref().unwrap_or(&e.code).to_string(),
+                        fix: e
+                            .params
+                            .get(FIX_PARAM)
+                            .and_then(|v| v.as_str())
+                            .map(String::from),
+                    });
+                }
+            }
+            ValidationErrorsKind::Struct(inner) => collect(inner, &path, out),
+            ValidationErrorsKind::List(items) => {
+                for (i, inner) in items {
+                    let mut path = path.clone();
+                    path.push(PathElement::Index(*i));
+                    collect(inner, &path, out);
+                }
+            }
+        }
+    }
+}
+
+/// Flattens validation errors, giving each one the path to the field it's
+/// about.
+pub fn field_errors(errors: &ValidationErrors) -> Vec<FieldError> {
+    let mut out = Vec::new();
+    collect(errors, &[], &mut out);
+    out
+}
+
+fn is_blank(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    trimmed.is_empty() || trimmed.starts_with('#')
+}
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+// Rows in a block starting at (row, col), with each row's start column.
+// The block ends at the first row indented less than col.
+fn block_rows<'a>(
+    lines: &'a [&'a str],
+    row: usize,
+    col: usize,
+) -> impl Iterator<Item = (usize, usize, &'a str)> {
+    lines
+        .iter()
+        .enumerate()
+        .skip(row)
+        .filter(|(_, line)| !is_blank(line))
+        .map(move |(r, line)| (r, if r == row { col } else { indent(line) }, *line))
+        .take_while(move |(_, c, _)| *c >= col)
+        .filter(move |(_, c, _)| *c == col)
+}
+
+fn key_len(text: &str, key: &str) -> Option<usize> {
+    for quote in ["", "\"", "'"] {
+        let quoted = format!("{quote}{key}{quote}:");
+        if let Some(rest) = text.strip_prefix(&quoted) {
+            if rest.is_empty() || rest.starts_with(' ') {
+                return Some(quoted.len());
+            }
+        }
+    }
+    None
+}
+
+// The position of the value for a key at (row, key_col) that ends at
+// value_col, if it's in block style.
+fn value_start(
+    lines: &[&str],
+    row: usize,
+    key_col: usize,
+    value_col: usize,
+) -> Option<(usize, usize)> {
+    let rest = &lines[row][value_col..];
+    if !is_blank(rest) {
+        // A flow-style value, which can't be looked into
+        return None;
+    }
+    let (r, line) = lines
+        .iter()
+        .enumerate()
+        .skip(row + 1)
+        .find(|(_, line)| !is_blank(line))?;
+    let c = indent(line);
+    if c > key_col || (c == key_col && line[c..].starts_with('-')) {
+        return Some((r, c));
+    }
+    None
+}
+
+fn locate_in(
+    lines: &[&str],
+    row: usize,
+    col: usize,
+    path: &[PathElement],
+) -> Option<(usize, usize)> {
+    let (first, rest) = path.split_first()?;
+    let (pos, value) = match first {
+        PathElement::Field(key) => block_rows(lines, row, col).find_map(|(r, c, line)| {
+            let len = key_len(&line[c..], key)?;
+            Some(((r, c), value_start(lines, r, c, c + len)))
+        }),
+        PathElement::Index(i) => block_rows(lines, row, col)
+            .take_while(|(_, c, line)| line[*c..].starts_with('-'))
+            .nth(*i)
+            .map(|(r, c, line)| {
+                let content = c + 1 + indent(&line[c + 1..]);
+                ((r, c), Some((r, content)))
+            }),
+    }?;
+    Some(
+        value
+            .and_then(|(r, c)| locate_in(lines, r, c, rest))
+            .unwrap_or(pos),
+    )
+}
+
+/// Finds the row and column of the field at path in the YAML front matter
+/// of a markdown document. If the full path can't be found, the position of
+/// the closest enclosing field is returned instead, or the start of the front
+/// matter. Returns None if there's no front matter.
+pub fn locate(source: &str, path: &[PathElement]) -> Option<(usize, usize)> {
+    let lines: Vec<&str> = source.lines().collect();
+    if lines.first().map(|l| l.trim_end()) != Some("---") {
+        return None;
+    }
+    let end = lines
+        .iter()
+        .skip(1)
+        .position(|l| matches!(l.trim_end(), "---" | "..."))?
+        + 1;
+
+    let (row, col) = locate_in(&lines[..end], 1, 0, path).unwrap_or((0, 0));
+    Some((row, lines[row][..col].chars().count()))
+}
+
+/// Rule id for validation problems.
+pub const RULE: &str = "metadata";
+
+/// Problems for validation errors, with source positions if the source
+/// markdown is given.
+pub fn problems(errors: &ValidationErrors, source: Option<&str>) -> Vec<Diagnostic> {
+    field_errors(errors)
+        .into_iter()
+        .map(|e| match source.and_then(|s| locate(s, e.path())) {
+            Some((row, col)) => RowColProblem::new(row, col, &e.to_string())
+                .with_rule(RULE)
+                .into(),
+            None => Diagnostic::new(&e.to_string()).with_rule(RULE),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod validation_test {
+    use super::{PathElement, field_errors, locate, problems};
+    use crate::metadata::InputMetadata;
+    use validator::{Validate, ValidationError, ValidationErrors};
+
+    const SOURCE: &str = r#"---
+title: Foo
+author:
+  - A
+  - ""
+publications:
+- venue: Foo
+  submitted: 2023-01-01
+  rejected: 2023-02-01
+- venue: Bar
+  submitted: 2023-03-01
+  accepted: 2023-02-01
+
+- venue: "Baz"
+  # Comment
+  urls: [""]
+  submitted: 2023-04-01
+...
+
+Text
+"#;
+
+    fn field(f: &str) -> PathElement {
+        PathElement::Field(String::from(f))
+    }
+
+    fn index(i: usize) -> PathElement {
+        PathElement::Index(i)
+    }
+
+    fn metadata() -> InputMetadata {
+        serde_json::from_str(
+            r#"{
+  "title": "Foo",
+  "author": ["A", ""],
+  "publications": [
+    {"venue": "Foo", "submitted": "2023-01-01", "rejected": "2023-02-01"},
+    {"venue": "Bar", "submitted": "2023-03-01", "accepted": "2023-02-01"},
+    {"venue": "Baz", "urls": [""], "submitted": "2023-04-01"}
+  ]
+}"#,
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn test_field_errors() {
+        let errors = field_errors(&metadata().validate().unwrap_err());
+        let got: Vec<(String, &str)> = errors.iter().map(|e| (e.path_string(), e.msg())).collect();
+        assert_eq!(
+            got,
+            [
+                (String::from("author"), "each element must be non-empty"),
+                (
+                    String::from("publications[1]"),
+                    "dates must be in increasing order"
+                ),
+                (
+                    String::from("publications[2].urls"),
+                    "each element must be non-empty"
+                ),
+            ]
+        );
+        assert_eq!(
+            errors[1].path(),
+            [field("publications"), index(1)].as_slice()
+        );
+        assert_eq!(
+            errors[2].to_string(),
+            "publications[2].urls: each element must be non-empty (suggested fix: remove the \
+empty elements)"
+        );
+    }
+
+    #[test]
+    fn test_suggested_fix() {
+        // One case for each rule outside publications, which has its own
+        for (json, code) in [
+            (
+                r#"{"publications": [{"venue": "", "submitted": "2023-01-01"}]}"#,
+                "must be non-empty",
+            ),
+            (r#"{"author": ["A", ""]}"#, "each element must be non-empty"),
+            (
+                r#"{"publications": [{"venue": "a", "submitted": "2023-01-01", "accepted": "2023-01-02", "paid": {"currency": "USD", "status": "pending"}}]}"#,
+                "at least one of amount and rate-per-word must be set",
+            ),
+        ] {
+            let m: InputMetadata = serde_json::from_str(json).unwrap();
+            let errors = field_errors(&m.validate().unwrap_err());
+            assert_eq!(errors.len(), 1, "{code}");
+            assert_eq!(errors[0].code(), code);
+            assert!(errors[0].suggested_fix().is_some(), "{code}");
+        }
+
+        // Other errors have no fix
+        let mut errors = ValidationErrors::new();
+        errors.add("foo", ValidationError::new("bar"));
+        assert_eq!(field_errors(&errors)[0].suggested_fix(), None);
+    }
+
+    #[test]
+    fn test_locate() {
+        assert_eq!(locate(SOURCE, &[]), Some((0, 0)));
+        assert_eq!(locate(SOURCE, &[field("title")]), Some((1, 0)));
+        assert_eq!(locate(SOURCE, &[field("author")]), Some((2, 0)));
+        assert_eq!(locate(SOURCE, &[field("author"), index(1)]), Some((4, 2)));
+        assert_eq!(locate(SOURCE, &[field("publications")]), Some((5, 0)));
+        assert_eq!(
+            locate(SOURCE, &[field("publications"), index(0)]),
+            Some((6, 0))
+        );
+        assert_eq!(
+            locate(
+                SOURCE,
+                &[field("publications"), index(1), field("accepted")]
+            ),
+            Some((11, 2))
+        );
+        assert_eq!(
+            locate(SOURCE, &[field("publications"), index(2), field("urls")]),
+            Some((15, 2))
+        );
+        assert_eq!(
+            locate(SOURCE, &[field("publications"), index(2), field("venue")]),
+            Some((13, 2))
+        );
+
+        // Falls back to the closest enclosing field
+        assert_eq!(
+            locate(SOURCE, &[field("publications"), index(5)]),
+            Some((5, 0))
+        );
+        assert_eq!(
+            locate(SOURCE, &[field("publications"), index(1), field("paid")]),
+            Some((9, 0))
+        );
+        assert_eq!(locate(SOURCE, &[field("title"), index(0)]), Some((1, 0)));
+
+        assert_eq!(locate("Text\n", &[field("title")]), None);
+        assert_eq!(locate("---\ntitle: Foo\n", &[field("title")]), None);
+    }
+
+    #[test]
+    fn test_problems() {
+        let errors = metadata().validate().unwrap_err();
+        let got: Vec<String> = problems(&errors, Some(SOURCE))
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            got,
+            [
+                "row 3 col 1: author: each element must be non-empty (suggested fix: remove the \
+empty elements)",
+                "row 10 col 1: publications[1]: dates must be in increasing order (suggested fix: \
+check each date; they must follow the order submitted, shortlisted, accepted, contracted, then \
+the end date)",
+                "row 16 col 3: publications[2].urls: each element must be non-empty (suggested fix: \
+remove the empty elements)",
+            ]
+        );
+
+        let got: Vec<String> = problems(&errors, None)
+            .iter()
+            .map(|p| p.to_string())
+            .collect();
+        assert_eq!(
+            got[2],
+            "publications[2].urls: each element must be non-empty (suggested fix: remove the \
+empty elements)"
+        );
+    }
+}
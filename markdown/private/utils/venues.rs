@@ -0,0 +1,305 @@
+use crate::{deserializers, field_validators, json::JsonSerializable};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use validator::{Validate, ValidationError, ValidationErrors};
+
+#[derive(Clone, PartialEq, Debug, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "kebab-case")]
+pub struct Venue {
+    #[validate(custom(function = "field_validators::non_empty"))]
+    name: String,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    #[validate(custom(function = "field_validators::each_non_empty"))]
+    aliases: Vec<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserializers::option_string")]
+    #[validate(custom(function = "field_validators::non_empty"))]
+    url: Option<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[serde(deserialize_with = "deserializers::option_string")]
+    #[validate(custom(function = "field_validators::non_empty"))]
+    pay_rate: Option<String>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    simultaneous_submissions: Option<bool>,
+
+    #[serde(default)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_days: Option<u32>,
+}
+
+impl Venue {
+    pub fn build<S: Into<String>, V: Into<Vec<String>>>(
+        name: S,
+        aliases: V,
+        url: Option<&str>,
+        pay_rate: Option<&str>,
+        simultaneous_submissions: Option<bool>,
+        response_days: Option<u32>,
+    ) -> Result<Venue, ValidationErrors> {
+        let v = Venue {
+            name: name.into(),
+            aliases: aliases.into(),
+            url: url.map(str::to_string),
+            pay_rate: pay_rate.map(str::to_string),
+            simultaneous_submissions,
+            response_days,
+        };
+        v.validate()?;
+        Ok(v)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn aliases(&self) -> &Vec<String> {
+        &self.aliases
+    }
+
+    pub fn url(&self) -> Option<&String> {
+        self.url.as_ref()
+    }
+
+    pub fn pay_rate(&self) -> Option<&String> {
+        self.pay_rate.as_ref()
+    }
+
+    pub fn simultaneous_submissions(&self) -> Option<bool> {
+        self.simultaneous_submissions
+    }
+
+    pub fn response_days(&self) -> Option<u32> {
+        self.response_days
+    }
+
+    pub fn details(&self) -> Vec<String> {
+        let mut out = Vec::new();
+        if let Some(url) = self.url() {
+            out.push(format!("URL: {url}"));
+        }
+        if let Some(pay_rate) = self.pay_rate() {
+            out.push(format!("Pay rate: {pay_rate}"));
+        }
+        if let Some(sim_subs) = self.simultaneous_submissions() {
+            out.push(format!(
+                "Simultaneous submissions: {}",
+                if sim_subs { "yes" } else { "no" }
+            ));
+        }
+        if let Some(days) = self.response_days() {
+            out.push(format!("Response time: {days} days"));
+        }
+        out
+    }
+}
+
+impl JsonSerializable for Venue {}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, Validate)]
+#[serde(deny_unknown_fields)]
+#[serde(transparent)]
+#[validate(schema(function = "VenueRegistry::validate_names"))]
+pub struct VenueRegistry {
+    #[validate(nested)]
+    venues: Vec<Venue>,
+}
+
+impl VenueRegistry {
+    pub fn build<T: Into<Vec<Venue>>>(venues: T) -> Result<VenueRegistry, ValidationErrors> {
+        let r = VenueRegistry {
+            venues: venues.into(),
+        };
+        r.validate()?;
+        Ok(r)
+    }
+
+    pub fn venues(&self) -> &Vec<Venue> {
+        &self.venues
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Venue> {
+        self.venues
+            .iter()
+            .find(|v| v.name == name || v.aliases.iter().any(|a| a == name))
+    }
+
+    pub fn canonical_name(&self, name: &str) -> Result<&str, String> {
+        match self.get(name) {
+            Some(v) => Ok(v.name()),
+            None => Err(format!("unknown venue '{name}'")),
+        }
+    }
+
+    fn validate_names(&self) -> Result<(), ValidationError> {
+        let mut seen = HashMap::new();
+        for v in &self.venues {
+            for name in [&v.name].into_iter().chain(v.aliases.iter()) {
+                if seen.insert(name, &v.name).is_some() {
+                    return Err(ValidationError::new(
+                        "venue names and aliases must be unique",
+                    ));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl JsonSerializable for VenueRegistry {}
+
+#[cfg(test)]
+mod venue_test {
+    use super::Venue;
+    use crate::json::{JsonSerializable, from_json};
+
+    #[test]
+    fn test_build() {
+        assert!(Venue::build("", Vec::new(), None, None, None, None).is_err());
+        assert!(Venue::build("foo", [String::new()], None, None, None, None).is_err());
+        assert!(Venue::build("foo", Vec::new(), Some(""), None, None, None).is_err());
+        assert!(Venue::build("foo", Vec::new(), None, Some(""), None, None).is_err());
+    }
+
+    #[test]
+    fn test_serialization_minimal() {
+        assert_eq!(
+            Venue::build("foo", Vec::new(), None, None, None, None)
+                .unwrap()
+                .to_json()
+                .unwrap(),
+            r#"{
+  "name": "foo"
+}"#
+        );
+    }
+
+    #[test]
+    fn test_serialization_full() {
+        assert_eq!(
+            Venue::build(
+                "foo",
+                [String::from("bar")],
+                Some("http://example.com"),
+                Some("10c/word"),
+                Some(false),
+                Some(30)
+            )
+            .unwrap()
+            .to_json()
+            .unwrap(),
+            r#"{
+  "aliases": [
+    "bar"
+  ],
+  "name": "foo",
+  "pay-rate": "10c/word",
+  "response-days": 30,
+  "simultaneous-submissions": false,
+  "url": "http://example.com"
+}"#
+        );
+    }
+
+    #[test]
+    fn test_deserialization() {
+        let v: Venue = from_json(
+            r#"{
+  "aliases": [
+    "bar"
+  ],
+  "name": "foo",
+  "pay-rate": "10c/word",
+  "response-days": 30,
+  "simultaneous-submissions": false,
+  "url": "http://example.com"
+}"#,
+        )
+        .unwrap();
+        assert_eq!(v.name(), "foo");
+        assert_eq!(v.aliases(), &["bar"]);
+        assert_eq!(v.url().unwrap(), "http://example.com");
+        assert_eq!(v.pay_rate().unwrap(), "10c/word");
+        assert_eq!(v.simultaneous_submissions(), Some(false));
+        assert_eq!(v.response_days(), Some(30));
+        assert_eq!(
+            v.details(),
+            [
+                "URL: http://example.com",
+                "Pay rate: 10c/word",
+                "Simultaneous submissions: no",
+                "Response time: 30 days"
+            ]
+        );
+
+        assert!(from_json::<Venue>(r#"{"name": ""}"#).is_err());
+        assert!(from_json::<Venue>(r#"{"name": "foo", "blah": "bar"}"#).is_err());
+    }
+}
+
+#[cfg(test)]
+mod venue_registry_test {
+    use super::{Venue, VenueRegistry};
+    use crate::json::from_json;
+
+    #[test]
+    fn test_build() {
+        assert!(
+            VenueRegistry::build([
+                Venue::build("foo", [String::from("bar")], None, None, None, None).unwrap(),
+                Venue::build("baz", Vec::new(), None, None, None, None).unwrap(),
+            ])
+            .is_ok()
+        );
+        assert!(
+            VenueRegistry::build([
+                Venue::build("foo", Vec::new(), None, None, None, None).unwrap(),
+                Venue::build("foo", Vec::new(), None, None, None, None).unwrap(),
+            ])
+            .is_err()
+        );
+        assert!(
+            VenueRegistry::build([
+                Venue::build("foo", [String::from("bar")], None, None, None, None).unwrap(),
+                Venue::build("bar", Vec::new(), None, None, None, None).unwrap(),
+            ])
+            .is_err()
+        );
+    }
+
+    #[test]
+    fn test_get() {
+        let r: VenueRegistry = from_json(
+            r#"[
+  {
+    "aliases": [
+      "Foo Magazine",
+      "Foo Mag"
+    ],
+    "name": "Foo"
+  },
+  {
+    "name": "Bar"
+  }
+]"#,
+        )
+        .unwrap();
+
+        assert_eq!(r.venues().len(), 2);
+        assert_eq!(r.get("Foo").unwrap().name(), "Foo");
+        assert_eq!(r.get("Foo Mag").unwrap().name(), "Foo");
+        assert_eq!(r.get("Bar").unwrap().name(), "Bar");
+        assert!(r.get("Baz").is_none());
+
+        assert_eq!(r.canonical_name("Foo Magazine").unwrap(), "Foo");
+        assert!(r.canonical_name("foo").is_err());
+    }
+}